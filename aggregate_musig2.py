@@ -0,0 +1,25 @@
+import multi_party_schnorr
+from time import time
+
+multi_party_schnorr.PyKeyPair()  # dummy
+
+start = time()
+pairs = [multi_party_schnorr.PyKeyPair() for _ in range(3)]
+signers = [x.get_public_key() for x in pairs]
+# nonce round, can be done before message is known
+nonces = [multi_party_schnorr.PyMusig2Nonce() for _ in range(3)]
+public_nonces = [x.get_public_nonce() for x in nonces]
+msg = b"hello world"
+# single signing round
+sessions = [multi_party_schnorr.PyMusig2Session.generate(signers, public_nonces, pair, nonce, msg)
+            for pair, nonce in zip(pairs, nonces)]
+parts = [x.get_partial_sign() for x in sessions]
+sig = sessions[0].aggregate_signature_parts(parts)
+R = sessions[0].R()
+apk = sessions[0].apk()
+print("sig", sig.hex())
+print("apk:", apk.hex())
+print("R:", R.hex())
+print("result:", multi_party_schnorr.verify_aggregate_sign(sig, R, apk, msg))
+print("verify auto?", multi_party_schnorr.verify_auto(sig, R, apk, msg))
+print(int((time() - start) * 1000), "mS")
@@ -13,10 +13,12 @@ eph0 = multi_party_schnorr.PyEphemeralKey()  # 19.5mS
 eph1 = multi_party_schnorr.PyEphemeralKey()
 eph2 = multi_party_schnorr.PyEphemeralKey()
 print("create 3 ephemeral", eph0, eph1, eph2)
+# 1st round: publish commitments, 2nd round: reveal ephemeral and blind_factor
+commitments, blind_factors = zip(*[x.get_commitment() for x in (eph0, eph1, eph2)])
 ephemeral = [x.keypair.get_public_key() for x in (eph0, eph1, eph2)]
-agg0 = multi_party_schnorr.PyAggregate.generate(signers, ephemeral, pair0, eph0)  # 53mS
-agg1 = multi_party_schnorr.PyAggregate.generate(signers, ephemeral, pair1, eph1)
-agg2 = multi_party_schnorr.PyAggregate.generate(signers, ephemeral, pair2, eph2)
+agg0 = multi_party_schnorr.PyAggregate.generate(signers, ephemeral, commitments, blind_factors, pair0, eph0)  # 53mS
+agg1 = multi_party_schnorr.PyAggregate.generate(signers, ephemeral, commitments, blind_factors, pair1, eph1)
+agg2 = multi_party_schnorr.PyAggregate.generate(signers, ephemeral, commitments, blind_factors, pair2, eph2)
 print("create 3 aggregates {} {} {}".format(agg0, agg1, agg2))
 msg = b"hello world"
 sig0 = agg0.get_partial_sign(msg)  # 0.021mS
@@ -24,6 +26,8 @@ sig1 = agg1.get_partial_sign(msg)
 sig2 = agg2.get_partial_sign(msg)
 sig01 = agg0.add_signature_parts(sig0, sig1)  # 0.011mS
 sig012 = agg0.add_signature_parts(sig01, sig2)
+# check each part and blame faulty signers before combining
+assert sig012 == agg0.combine_signature_parts([sig0, sig1, sig2], msg)
 R = agg0.R()  # 0.0025mS
 apk = agg0.apk()  # 0.0011mS
 print("sig", sig012.hex())
@@ -31,7 +31,7 @@ setup(
         "Programming Language :: Rust",
     ],
     rust_extensions=[
-        RustExtension("multi_party_schnorr", binding=Binding.PyO3)
+        RustExtension("multi_party_schnorr", binding=Binding.PyO3, features=["python"])
     ],
     # rust extensions are not zip safe, just like C-extensions.
     python_requires='>=3.5',
@@ -0,0 +1,165 @@
+use crate::bip340::{has_even_y, negate_point};
+use crate::error::Error;
+use crate::hashing::{challenge, SigVersion};
+use crate::utils::{bigint2bytes, KeyType};
+use crate::verifyutils::ephemeral_hash_0;
+use emerald_city::curv::elliptic::curves::secp256_k1::{FE, GE};
+use emerald_city::curv::elliptic::curves::traits::{ECPoint, ECScalar};
+use emerald_city::curv::arithmetic::num_bigint::BigInt;
+
+
+/// adaptor pre-signature, becomes valid signature when t of T = t*G is revealed
+/// R: nonce without T, s: pre-signature s' = k + c*x where c is challenge of R+T
+/// final signature is s = s' + t and r = (R+T).x, verified by verify_aggregate_signature
+/// when R+T has odd Y, k is negated (s' = -k + c*x) and s = s' - t is of even Y nonce -(R+T)
+pub struct PreSignature {
+    pub R: GE,
+    pub T: GE,
+    pub s: FE,
+}
+
+impl PreSignature {
+    /// nonce of final signature R+T, negated to even Y
+    pub fn r_hat(&self) -> GE {
+        let r_hat = self.R.add_point(&self.T.get_element());
+        if has_even_y(&r_hat) { r_hat } else { negate_point(&r_hat) }
+    }
+
+    /// R+T has odd Y, k was negated by signer
+    pub fn is_negated(&self) -> bool {
+        !has_even_y(&self.R.add_point(&self.T.get_element()))
+    }
+
+    /// x coordinate of R+T, r of final signature
+    pub fn R_x(&self) -> BigInt {
+        self.r_hat().x_coor().unwrap()
+    }
+
+    /// s'*G == R + c*apk (-R when negated)
+    pub fn verify(&self, apk: &GE, message: &[u8], musig_bit: bool, version: SigVersion) -> Result<(), Error> {
+        if version == SigVersion::Frost {
+            return Err(Error::InvalidSignature("frost version is only for threshold key"));
+        }
+        let base_point: GE = ECPoint::generator();
+        let c = adaptor_challenge(&self.r_hat(), apk, message, musig_bit, version);
+        let R = if self.is_negated() { negate_point(&self.R) } else { self.R.clone() };
+        let expected = R.add_point(&apk.scalar_mul(&c.get_element()).get_element());
+        if base_point.scalar_mul(&self.s.get_element()) == expected {
+            Ok(())
+        } else {
+            Err(Error::InvalidSignature("s'G do not match with R + c*apk"))
+        }
+    }
+
+    /// complete signature s = s' + t (s' - t when negated)
+    pub fn adapt(&self, t: &FE) -> Result<FE, Error> {
+        self.check_secret(t)?;
+        if self.is_negated() {
+            Ok(self.s.sub(&t.get_element()))
+        } else {
+            Ok(self.s.add(&t.get_element()))
+        }
+    }
+
+    /// recover t = s - s' (s' - s when negated) from final signature
+    pub fn extract(&self, s: &FE) -> Result<FE, Error> {
+        let t = if self.is_negated() {
+            self.s.sub(&s.get_element())
+        } else {
+            s.sub(&self.s.get_element())
+        };
+        self.check_secret(&t)?;
+        Ok(t)
+    }
+
+    fn check_secret(&self, t: &FE) -> Result<(), Error> {
+        let base_point: GE = ECPoint::generator();
+        if base_point.scalar_mul(&t.get_element()) == self.T {
+            Ok(())
+        } else {
+            Err(Error::InvalidScalar("adaptor secret do not match with T".to_owned()))
+        }
+    }
+}
+
+
+/// challenge of signature with nonce R+T, same as verify_aggregate_signature
+/// only x of R+T is hashed, so Y of r_hat does not matter
+/// Tagged: H_challenge((R+T).x || apk || message), Legacy: ephemeral_hash_0
+pub fn adaptor_challenge(r_hat: &GE, apk: &GE, message: &[u8], musig_bit: bool, version: SigVersion) -> FE {
+    match version {
+        SigVersion::Tagged => {
+            let key_type = if musig_bit { KeyType::AggregateSig } else { KeyType::SingleSig };
+            let r_x = bigint2bytes(&r_hat.x_coor().unwrap()).unwrap();
+            challenge(&r_x, apk, key_type, message)
+        },
+        SigVersion::Legacy => ECScalar::from(&ephemeral_hash_0(r_hat, apk, message, musig_bit)),
+        SigVersion::Frost => unreachable!("frost version is only for threshold key"),
+    }
+}
+
+
+#[cfg(test)]
+mod Test {
+    use crate::adaptor::*;
+    use crate::aggregate::verify_aggregate_signature;
+    use crate::keypair::KeyPair;
+    use crate::testutils::aggregates;
+
+    #[test]
+    fn test_single_adaptor_sign() {
+        let keypair = KeyPair::new();
+        let adaptor = KeyPair::new();
+        let pre_sig = keypair.single_pre_sign(&adaptor.public, b"swap", &[3u8;32]).unwrap();
+        assert!(pre_sig.verify(&keypair.public, b"swap", false, SigVersion::Tagged).is_ok());
+        // pre-signature is not a valid signature
+        assert!(verify_aggregate_signature(
+            &pre_sig.s.to_big_int(), &pre_sig.R_x(), &keypair.public, b"swap", false, SigVersion::Tagged).is_err());
+
+        let s = adaptor.secret.with(|t| pre_sig.adapt(t)).unwrap();
+        assert!(verify_aggregate_signature(
+            &s.to_big_int(), &pre_sig.R_x(), &keypair.public, b"swap", false, SigVersion::Tagged).is_ok());
+        assert!(adaptor.secret.with(|t| pre_sig.extract(&s).unwrap() == *t));
+        assert!(pre_sig.adapt(&ECScalar::new_random()).is_err());
+    }
+
+    #[test]
+    fn test_adaptor_nonce_has_even_y() {
+        let keypair = KeyPair::new();
+        let adaptor = KeyPair::new();
+        let mut seen = (false, false);
+        for i in 0..64u8 {
+            let pre_sig = keypair.single_pre_sign(&adaptor.public, b"swap", &[i;32]).unwrap();
+            assert!(pre_sig.verify(&keypair.public, b"swap", false, SigVersion::Tagged).is_ok());
+            let s = adaptor.secret.with(|t| pre_sig.adapt(t)).unwrap();
+            // final nonce s*G - c*P is lifted from x with even Y
+            let base_point: GE = ECPoint::generator();
+            let c = adaptor_challenge(&pre_sig.r_hat(), &keypair.public, b"swap", false, SigVersion::Tagged);
+            let nonce = base_point.scalar_mul(&s.get_element())
+                .sub_point(&keypair.public.scalar_mul(&c.get_element()).get_element());
+            assert!(nonce == pre_sig.r_hat() && has_even_y(&nonce));
+            assert!(adaptor.secret.with(|t| pre_sig.extract(&s).unwrap() == *t));
+            if pre_sig.is_negated() { seen.1 = true } else { seen.0 = true }
+            if seen.0 && seen.1 {
+                break;
+            }
+        }
+        assert!(seen.0 && seen.1);
+    }
+
+    #[test]
+    fn test_aggregate_adaptor_sign() {
+        let aggs = aggregates(2);
+        let adaptor = KeyPair::new();
+        let parts: Vec<FE> = aggs.iter().map(|agg| agg.pre_partial_sign(&adaptor.public, b"swap").unwrap()).collect();
+        let agg = &aggs[0];
+        assert!(agg.verify_pre_partial_sign(1, &parts[1], &adaptor.public, b"swap"));
+        let pre_sig = agg.combine_pre_partial_signs(&parts, &adaptor.public, b"swap").unwrap();
+        assert!(pre_sig.verify(&agg.apk, b"swap", true, SigVersion::Tagged).is_ok());
+
+        let s = adaptor.secret.with(|t| pre_sig.adapt(t)).unwrap();
+        assert!(verify_aggregate_signature(
+            &s.to_big_int(), &pre_sig.R_x(), &agg.apk, b"swap", true, SigVersion::Tagged).is_ok());
+        assert!(adaptor.secret.with(|t| pre_sig.extract(&s).unwrap() == *t));
+    }
+}
@@ -0,0 +1,514 @@
+use crate::adaptor::{adaptor_challenge, PreSignature};
+use crate::bip340::{has_even_y, negate_point, negate_scalar};
+use crate::error::Error;
+use crate::hashing::{challenge, commitment, keyagg_coefficient, keyagg_list_hash, SigVersion};
+use crate::keypair::KeyPair;
+use crate::secret::Wipe;
+use crate::tweak::{add_tweak, tweak_public_key};
+use crate::utils::{KeyType, point2bytes, bigint2bytes};
+use emerald_city::curv::cryptographic_primitives::hashing::{
+    hash_sha256::HSha256,
+    traits::Hash,
+};
+use emerald_city::curv::elliptic::curves::secp256_k1::{FE, GE};
+use emerald_city::curv::elliptic::curves::traits::{ECPoint, ECScalar};
+use emerald_city::curv::arithmetic::traits::Samplable;
+use emerald_city::curv::arithmetic::num_bigint::BigInt;
+use num_traits::{Zero, One};
+use std::sync::Arc;
+
+
+/// keypair is shared by clones and by Aggregate made from this key, partial signing takes its secret once
+#[derive(Clone)]
+pub struct EphemeralKey {
+    pub keypair: Arc<KeyPair>,
+    pub commitment: BigInt,
+    pub blind_factor: BigInt,
+}
+
+impl EphemeralKey {
+    /// generate random ephemeral key
+    pub fn new() -> Self {
+        EphemeralKey::from_keypair(&KeyPair::new())
+    }
+
+    /// get ephemeral key from keypair
+    pub fn from_keypair(keypair: &KeyPair) -> Self {
+        let blind_factor = BigInt::sample(256);
+        let commitment = commitment(&keypair.public, &blind_factor).unwrap();
+        let keypair = Arc::new(keypair.clone());
+        EphemeralKey {keypair, commitment, blind_factor}
+    }
+
+    /// check ephemeral commitments
+    pub fn check_commitments(&self) -> bool {
+        ephemeral_test_com(&self.keypair.public, &self.blind_factor, &self.commitment)
+    }
+
+    /// return commitment & blind_factor
+    /// publish commitment first, then reveal R with blind_factor
+    pub fn get_commitment(&self) -> (BigInt, BigInt) {
+        (self.commitment.clone(), self.blind_factor.clone())
+    }
+
+    /// wipe secret key of all sharing Aggregate and blind_factor
+    pub fn wipe(&mut self) {
+        self.keypair.secret.wipe();
+        self.blind_factor.wipe();
+    }
+}
+
+impl Drop for EphemeralKey {
+    fn drop(&mut self) {
+        self.blind_factor.wipe();
+    }
+}
+
+#[derive(Clone)]
+pub struct Aggregate {
+    pub keypair: KeyPair,
+    pub eph: EphemeralKey,
+    pub apk: GE,
+    pub hash: BigInt,
+    pub r_tag: GE,
+    pub is_musig: bool,
+    /// all signer's public keys, revealed R_i and coefficients (same order)
+    pub signers: Vec<GE>,
+    pub ephemeral: Vec<GE>,
+    pub coefficients: Vec<FE>,
+    /// sum of Taproot-style tweaks applied to apk
+    /// each of n entries signs for X_i + t_i*G where a_i*t_i = t/n
+    pub tweak: Option<FE>,
+}
+
+impl Aggregate {
+    /// get aggregate key
+    /// commitments: published by all signers before ephemeral points are revealed
+    /// blind_factors: opening of the commitments
+    pub fn generate(signers: &[GE], ephemeral: &[GE], commitments: &[BigInt], blind_factors: &[BigInt],
+                    keypair: &KeyPair, eph: &EphemeralKey) -> Result<Aggregate, Error> {
+        // check signature number
+        if signers.len() != ephemeral.len() {
+            return Err(Error::LengthMismatch {
+                name: "ephemeral", expected: signers.len(), actual: ephemeral.len() })
+        } else if signers.len() != commitments.len() {
+            return Err(Error::LengthMismatch {
+                name: "commitments", expected: signers.len(), actual: commitments.len() })
+        } else if signers.len() != blind_factors.len() {
+            return Err(Error::LengthMismatch {
+                name: "blind_factors", expected: signers.len(), actual: blind_factors.len() })
+        } else if signers.len() < 1 {
+            return Err(Error::InvalidParams("no signer found".to_owned()))
+        }
+        // check all revealed R_i with commitments
+        let failed = check_ephemeral_commitments(ephemeral, commitments, blind_factors);
+        if 0 < failed.len() {
+            return Err(Error::Commitment { party_index: failed })
+        }
+        // compute apk
+        let is_musig = 1 < signers.len();
+        let party_index = signers.iter().position(|pk| pk == &keypair.public).ok_or(
+            Error::InvalidParams("not found your public key in signers".to_owned()))?;
+        let (apk, coefficients) = key_aggregation_coefficients(signers)?;
+        let hash = coefficients[party_index].to_big_int();
+        // compute R' = R1+R2:
+        let r_hat = {
+            let mut iter = ephemeral.iter();
+            let head = iter.next().unwrap().clone();
+            iter.fold(head, |a, b| a + b)
+        };
+        let keypair = keypair.clone();
+        let eph = eph.clone();
+        Ok(Aggregate {
+            keypair, eph, apk, hash, r_tag: r_hat, is_musig,
+            signers: signers.to_vec(),
+            ephemeral: ephemeral.to_vec(),
+            coefficients,
+            tweak: None,
+        })
+    }
+
+    /// Taproot-style tweak, apk becomes Q = apk + t*G
+    /// t goes through key-aggregation coefficients, signer i adds c*a_i*t_i = c*t/n to s_i
+    pub fn tweak(&self, commitment: &[u8]) -> Result<Aggregate, Error> {
+        let (_, t) = tweak_public_key(&self.apk, self.key_type(), commitment)?;
+        self.tweak_add(&t)
+    }
+
+    /// apk becomes apk + t*G by raw tweak t (ex. BIP-32 public derivation)
+    pub fn tweak_add(&self, t: &FE) -> Result<Aggregate, Error> {
+        let apk = add_tweak(&self.apk, t)?;
+        let tweak = match &self.tweak {
+            Some(tweak) => tweak.add(&t.get_element()),
+            None => t.clone(),
+        };
+        Ok(Aggregate { apk, tweak: Some(tweak), ..self.clone() })
+    }
+
+    /// wipe secret key and ephemeral key
+    pub fn wipe(&mut self) {
+        self.keypair.wipe();
+        self.eph.wipe();
+    }
+
+    /// get partial signature of whole's
+    /// r_i is negated when R has odd Y, so the signature is of R with even Y
+    /// ephemeral key is wiped, only one of partial_sign or pre_partial_sign is allowed
+    pub fn partial_sign(&self, message: &[u8]) -> Result<FE, Error> {
+        // compute c = H_challenge(R.x || apk || message)
+        let c_fe = challenge(&self.r_x_bytes(), &self.apk, self.key_type(), message);
+        self.partial_sign_with(&c_fe, self.is_odd_r())
+    }
+
+    /// adaptor pre-signature part, challenge is of R+T
+    /// r_i is negated when R+T has odd Y, see PreSignature
+    pub fn pre_partial_sign(&self, T: &GE, message: &[u8]) -> Result<FE, Error> {
+        self.partial_sign_with(&self.adaptor_challenge(T, message), self.is_odd_r_hat(T))
+    }
+
+    // compute partial signature s_i = r_i + c*a_i*x_i
+    // r_i is shared by clones, second signature with other challenge would leak x_i
+    fn partial_sign_with(&self, c_fe: &FE, negate: bool) -> Result<FE, Error> {
+        let mut r_i = self.eph.keypair.secret.take().ok_or(
+            Error::InvalidParams("ephemeral key is already used".to_owned()))?;
+        let a_fe: FE = ECScalar::from(&self.hash);
+        let factor = c_fe.clone() * a_fe;
+        let s_i = if negate {
+            // -r_i + c*a_i*x_i = -(r_i - c*a_i*x_i)
+            negate_scalar(&self.keypair.secret.mul_add(&negate_scalar(&factor), &r_i))
+        } else {
+            self.keypair.secret.mul_add(&factor, &r_i)
+        };
+        r_i.wipe();
+        match self.tweak_part() {
+            Some(part) => Ok(Aggregate::add_signature_parts(&s_i, &(c_fe.clone() * part))),
+            None => Ok(s_i),
+        }
+    }
+
+    // a_i*t_i = t/n, same for every entry so that sum of a_i*(x_i + t_i) = x + t
+    fn tweak_part(&self) -> Option<FE> {
+        self.tweak.as_ref().map(|t| {
+            let n: FE = ECScalar::from(&BigInt::from(self.signers.len() as u64));
+            t.clone() * n.invert()
+        })
+    }
+
+    /// check partial signature of signer at index
+    /// s_i*G == R_i + c*a_i*(X_i + t_i*G) (-R_i when R has odd Y)
+    pub fn verify_partial_sign(&self, index: usize, s_i: &FE, message: &[u8]) -> bool {
+        let c = challenge(&self.r_x_bytes(), &self.apk, self.key_type(), message);
+        self.verify_partial_sign_with(index, s_i, &c, self.is_odd_r())
+    }
+
+    /// check adaptor pre-signature part of signer at index
+    pub fn verify_pre_partial_sign(&self, index: usize, s_i: &FE, T: &GE, message: &[u8]) -> bool {
+        self.verify_partial_sign_with(index, s_i, &self.adaptor_challenge(T, message), self.is_odd_r_hat(T))
+    }
+
+    fn verify_partial_sign_with(&self, index: usize, s_i: &FE, c: &FE, negate: bool) -> bool {
+        let (public, r_i, a_i) = match (
+            self.signers.get(index), self.ephemeral.get(index), self.coefficients.get(index)) {
+            (Some(public), Some(r_i), Some(a_i)) => (public, r_i, a_i),
+            _ => return false,
+        };
+        let r_i = if negate { negate_point(r_i) } else { r_i.clone() };
+        let base_point: GE = ECPoint::generator();
+        let expected = r_i.add_point(&public.scalar_mul(&(c.clone() * a_i.clone()).get_element()).get_element());
+        let expected = match self.tweak_part() {
+            Some(part) => expected.add_point(&base_point.scalar_mul(&(c.clone() * part).get_element()).get_element()),
+            None => expected,
+        };
+        base_point.scalar_mul(&s_i.get_element()) == expected
+    }
+
+    /// check all partial signatures (same order as signers) and return s
+    /// error contains indexes of faulty signers
+    pub fn combine_partial_signs(&self, parts: &[FE], message: &[u8]) -> Result<FE, Error> {
+        let c = challenge(&self.r_x_bytes(), &self.apk, self.key_type(), message);
+        self.combine_partial_signs_with(parts, &c, self.is_odd_r())
+    }
+
+    /// check all adaptor pre-signature parts and return pre-signature of R and T
+    pub fn combine_pre_partial_signs(&self, parts: &[FE], T: &GE, message: &[u8]) -> Result<PreSignature, Error> {
+        let s = self.combine_partial_signs_with(parts, &self.adaptor_challenge(T, message), self.is_odd_r_hat(T))?;
+        Ok(PreSignature { R: self.r_tag.clone(), T: T.clone(), s })
+    }
+
+    fn combine_partial_signs_with(&self, parts: &[FE], c: &FE, negate: bool) -> Result<FE, Error> {
+        if parts.len() != self.signers.len() {
+            return Err(Error::LengthMismatch {
+                name: "parts", expected: self.signers.len(), actual: parts.len() });
+        }
+        let failed: Vec<usize> = parts.iter()
+            .enumerate()
+            .filter(|(index, s_i)| !self.verify_partial_sign_with(*index, s_i, c, negate))
+            .map(|(index, _)| index)
+            .collect();
+        if 0 < failed.len() {
+            return Err(Error::InvalidShare { party_index: failed });
+        }
+        let mut iter = parts.iter();
+        let head = iter.next().unwrap().clone();
+        Ok(iter.fold(head, |acc, s_i| Aggregate::add_signature_parts(&acc, s_i)))
+    }
+
+    /// get R point x coordinate
+    pub fn R(&self) -> BigInt {
+        self.r_tag.x_coor().unwrap()
+    }
+
+    // c = H_challenge((R+T).x || apk || message)
+    fn adaptor_challenge(&self, T: &GE, message: &[u8]) -> FE {
+        let r_hat = self.r_tag.add_point(&T.get_element());
+        adaptor_challenge(&r_hat, &self.apk, message, self.is_musig, SigVersion::Tagged)
+    }
+
+    fn is_odd_r(&self) -> bool {
+        !has_even_y(&self.r_tag)
+    }
+
+    fn is_odd_r_hat(&self, T: &GE) -> bool {
+        !has_even_y(&self.r_tag.add_point(&T.get_element()))
+    }
+
+    fn r_x_bytes(&self) -> [u8;32] {
+        bigint2bytes(&self.R()).unwrap()
+    }
+
+    fn key_type(&self) -> KeyType {
+        if self.is_musig { KeyType::AggregateSig } else { KeyType::SingleSig }
+    }
+
+    /// get shared public key with 0x05 0x06 prefix when musig
+    pub fn apk_bytes(&self) -> [u8;33] {
+        point2bytes(&self.apk, self.key_type())
+    }
+
+    /// return a signature + another signature
+    pub fn add_signature_parts(s1: &FE, s2: &FE) -> FE {
+        s1.add(&s2.get_element())
+    }
+}
+
+
+/// KeySort, lexicographic order of 33bytes compressed keys (same as BIP-327)
+pub fn sort_public_keys(pks: &[GE]) -> Vec<GE> {
+    let mut sorted: Vec<([u8;33], GE)> = pks.iter()
+        .map(|pk| (point2bytes(pk, KeyType::SingleSig), pk.clone()))
+        .collect();
+    sorted.sort_by(|a, b| a.0.cmp(&b.0));
+    sorted.into_iter().map(|(_, pk)| pk).collect()
+}
+
+/// generate aggregate key and coefficient of each key (same order as pks)
+/// L = H_list(KeySort(P_1 .. P_n)), a_i = H_coef(L || P_i)
+/// a_i = 1 for the second unique key of sorted list, apk = sum of a_i*P_i
+/// the result does not depend on order of pks, duplicate keys are added each time
+pub fn key_aggregation_coefficients(pks: &[GE]) -> Result<(GE, Vec<FE>), Error> {
+    if pks.len() < 1 {
+        return Err(Error::InvalidParams("no signer found".to_owned()));
+    }
+    let sorted = sort_public_keys(pks);
+    let list_hash = keyagg_list_hash(&sorted);
+    let second_key = sorted.iter().find(|pk| *pk != &sorted[0]);
+    let coefficients: Vec<FE> = pks
+        .iter()
+        .map(|pk| {
+            if Some(pk) == second_key {
+                ECScalar::from(&BigInt::one())
+            } else {
+                keyagg_coefficient(&list_hash, pk)
+            }
+        })
+        .collect();
+
+    let mut apk_vec = pks
+        .iter()
+        .zip(coefficients.iter())
+        .map(|(pk, a)| pk.scalar_mul(&a.get_element()));
+    let pk1 = apk_vec.next().unwrap();
+    let sum = apk_vec.fold(pk1, |acc, pk| acc.add_point(&pk.get_element()));
+    Ok((sum, coefficients))
+}
+
+/// generate aggregate Key
+/// return apk and coefficient of public, same for any position of public in pks
+pub fn key_aggregation_n(pks: &[GE], public: &GE) -> Result<(GE, BigInt), Error> {
+    let party_index = pks.iter().position(|pk| pk == public).ok_or(
+        Error::InvalidParams("not found your public key in signers".to_owned()))?;
+    let (apk, coefficients) = key_aggregation_coefficients(pks)?;
+    // apk, hash
+    Ok((apk, coefficients[party_index].to_big_int()))
+}
+
+
+/// return indexes of signers whose R_i do not match with commitment
+pub fn check_ephemeral_commitments(ephemeral: &[GE], commitments: &[BigInt], blind_factors: &[BigInt]) -> Vec<usize> {
+    ephemeral.iter()
+        .zip(commitments.iter().zip(blind_factors.iter()))
+        .enumerate()
+        .filter(|(_, (r, (comm, blind_factor)))| !ephemeral_test_com(r, blind_factor, comm))
+        .map(|(index, _)| index)
+        .collect()
+}
+
+
+// ephemeral commitments check
+fn ephemeral_test_com(r_to_test: &GE, blind_factor: &BigInt, comm: &BigInt) -> bool {
+    match commitment(r_to_test, blind_factor) {
+        Ok(computed_comm) => &computed_comm == comm,
+        Err(_) => false,
+    }
+}
+
+
+/// verify 1 of 1 and n of n signature, (s, R.x) and public key
+pub fn verify_aggregate_signature(signature: &BigInt, r_x: &BigInt, apk: &GE, message: &[u8], musig_bit: bool,
+                                  version: SigVersion) -> Result<(), Error> {
+    let base_point: GE = ECPoint::generator();
+    if version == SigVersion::Frost {
+        return Err(Error::InvalidSignature("frost version is only for threshold key"));
+    } else if version == SigVersion::Tagged {
+        let key_type = if musig_bit { KeyType::AggregateSig } else { KeyType::SingleSig };
+        let c = challenge(&bigint2bytes(r_x)?, apk, key_type, message);
+        let signature_fe: FE = ECScalar::from(signature);
+        let sG = base_point.scalar_mul(&signature_fe.get_element());
+        let cY = apk.scalar_mul(&c.get_element());
+        if sG == cY {
+            // R is point at infinity
+            return Err(Error::InvalidSignature("sG_x do not match with r_x"));
+        }
+        let sG = sG.sub_point(&cY.get_element());
+        return if sG.x_coor().unwrap() == *r_x {
+            Ok(())
+        } else {
+            Err(Error::InvalidSignature("sG_x do not match with r_x"))
+        };
+    }
+
+    let c = if musig_bit {
+        HSha256::create_hash(&[
+            &BigInt::zero(),
+            &r_x,
+            &apk.bytes_compressed_to_big_int(),
+            &BigInt::from_bytes_be(message),
+        ])
+    } else {
+        HSha256::create_hash(&[
+            r_x,
+            &apk.bytes_compressed_to_big_int(),
+            &BigInt::from_bytes_be(message),
+        ])
+    };
+
+    let signature_fe: FE = ECScalar::from(signature);
+    let sG = base_point.scalar_mul(&signature_fe.get_element());
+    let c: FE = ECScalar::from(&c);
+    let cY = apk.scalar_mul(&c.get_element());
+    let sG = sG.sub_point(&cY.get_element());
+    if sG.x_coor().unwrap() == *r_x {
+        Ok(())
+    } else {
+        Err(Error::InvalidSignature("sG_x do not match with r_x"))
+    }
+}
+
+
+#[cfg(test)]
+mod Test {
+    use crate::utils::bytes2point_inner;
+    use crate::aggregate::verify_aggregate_signature;
+    use crate::hashing::SigVersion;
+    use emerald_city::curv::arithmetic::num_bigint::BigInt;
+
+    #[test]
+    fn test_normal_single_sig() {
+        // let sk = b"\xb7\xe1Qb\x8a\xed*j\xbfqX\x80\x9c\xf4\xf3\xc7b\xe7\x16\x0f8\xb4\xdaV\xa7\x84\xd9\x04Q\x90\xcf\xef";
+        let pk = b"\x02\xdf\xf1\xd7\x7f*g\x1c_6\x187&\xdb#A\xbeX\xfe\xae\x1d\xa2\xde\xce\xd8C$\x0f{P+\xa6Y";
+        let msg = b"$?j\x88\x85\xa3\x08\xd3\x13\x19\x8a.\x03psD\xa4\t8\")\x9f1\xd0\x08.\xfa\x98\xecNl\x89";
+        let sig_a = b"*)\x8d\xac\xaeW9Z\x15\xd0y]\xdb\xfd\x1d\xcbVM\xa8+\x0f&\x9b\xc7\nt\xf8\"\x04)\xba\x1d";
+        let sig_b = b"\x1eQ\xa2,\xce\xc3U\x99\xb8\xf2f\x91\"\x81\xf86_\xfc-\x03Z#\x044\xa1\xa6M\xc5\x9fp\x13\xfd";
+
+        let pk = bytes2point_inner(pk).unwrap();
+        let sig_a = BigInt::from_bytes_be(sig_a);
+        let sig_b = BigInt::from_bytes_be(sig_b);
+        assert!(verify_aggregate_signature(&sig_b, &sig_a, &pk, msg, false, SigVersion::Legacy).is_ok());
+    }
+
+
+    #[test]
+    fn test_zerofill_single_sig() {
+        // let sk = b"\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x01";
+        let pk = b"\x02y\xbef~\xf9\xdc\xbb\xacU\xa0b\x95\xce\x87\x0b\x07\x02\x9b\xfc\xdb-\xce(\xd9Y\xf2\x81[\x16\xf8\x17\x98";
+        let msg = b"\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00";
+        let sig_a = b"xz\x84\x8eq\x04=(\x0cPG\x0e\x8e\x152\xb2\xdd] \xee\x91*E\xdb\xdd+\xd1\xdf\xbf\x18~\xf6";  // r
+        let sig_b = b"p1\xa9\x881\x85\x9d\xc3M\xff\xee\xdd\xa8h1\x84,\xcd\x00y\xe1\xf9*\xf1w\xf7\xf2,\xc1\xdc\xed\x05";  // s
+
+        let pk = bytes2point_inner(pk).unwrap();
+        let sig_a = BigInt::from_bytes_be(sig_a);
+        let sig_b = BigInt::from_bytes_be(sig_b);
+        assert!(verify_aggregate_signature(&sig_b, &sig_a, &pk, msg, false, SigVersion::Legacy).is_ok());
+    }
+
+    #[test]
+    fn test_aggregate_n_of_n() {
+        use crate::testutils::aggregates;
+        use emerald_city::curv::elliptic::curves::traits::ECScalar;
+        let msg = b"hello world";
+        let aggs = aggregates(3);
+        let mut parts: Vec<_> = aggs.iter().map(|agg| agg.partial_sign(msg).unwrap()).collect();
+        // nonce is used once, also by pre_partial_sign or a clone
+        assert!(aggs[0].partial_sign(msg).is_err());
+        assert!(aggs[0].clone().pre_partial_sign(&aggs[1].apk, msg).is_err());
+        let agg = &aggs[0];
+        let s = agg.combine_partial_signs(&parts, msg).unwrap();
+        assert!(verify_aggregate_signature(&s.to_big_int(), &agg.R(), &agg.apk, msg, true, SigVersion::Tagged).is_ok());
+        // garbage from signer 1 is blamed
+        parts[1] = ECScalar::new_random();
+        assert_eq!(agg.combine_partial_signs(&parts, msg).err().unwrap(),
+                   crate::error::Error::InvalidShare { party_index: vec![1] });
+    }
+
+    #[test]
+    fn test_key_aggregation_order_and_duplicate() {
+        use crate::aggregate::{key_aggregation_n, key_aggregation_coefficients};
+        use crate::keypair::KeyPair;
+        let pairs: Vec<KeyPair> = (0..4).map(|_| KeyPair::new()).collect();
+        let signers: Vec<_> = pairs.iter().map(|p| p.public.clone()).collect();
+        let mut reversed = signers.clone();
+        reversed.reverse();
+        let (apk, a0) = key_aggregation_n(&signers, &signers[0]).unwrap();
+        let (apk_r, a0_r) = key_aggregation_n(&reversed, &signers[0]).unwrap();
+        assert!(apk == apk_r);
+        assert_eq!(a0, a0_r);
+        // duplicate key has one coefficient and is added twice
+        // list hash differs, so all coefficients and apk change
+        let mut duplicated = signers.clone();
+        duplicated.push(signers[1].clone());
+        let (apk_d, coefficients) = key_aggregation_coefficients(&duplicated).unwrap();
+        assert!(coefficients[1] == coefficients[4]);
+        let mut terms = duplicated.iter().zip(coefficients.iter()).map(|(pk, a)| pk.clone() * a);
+        let head = terms.next().unwrap();
+        assert!(apk_d == terms.fold(head, |acc, term| acc + term));
+        assert!(apk_d != apk);
+    }
+
+    #[test]
+    fn test_aggregate_reject_rogue_ephemeral() {
+        use crate::aggregate::{Aggregate, EphemeralKey};
+        use crate::error::Error;
+        use crate::keypair::KeyPair;
+        let pairs: Vec<KeyPair> = (0..3).map(|_| KeyPair::new()).collect();
+        let ephs: Vec<EphemeralKey> = (0..3).map(|_| EphemeralKey::new()).collect();
+        let signers: Vec<_> = pairs.iter().map(|p| p.public.clone()).collect();
+        let mut ephemeral: Vec<_> = ephs.iter().map(|e| e.keypair.public.clone()).collect();
+        let commitments: Vec<_> = ephs.iter().map(|e| e.commitment.clone()).collect();
+        let blind_factors: Vec<_> = ephs.iter().map(|e| e.blind_factor.clone()).collect();
+        // party 2 reveals another R after seeing the others
+        ephemeral[2] = KeyPair::new().public;
+        let err = Aggregate::generate(
+            &signers, &ephemeral, &commitments, &blind_factors, &pairs[0], &ephs[0]).err().unwrap();
+        assert_eq!(err, Error::Commitment { party_index: vec![2] });
+    }
+}
@@ -0,0 +1,342 @@
+use crate::bip340::{bip340_challenge, lift_x, negate_point, FIELD_SIZE, CURVE_ORDER};
+use crate::hashing::{challenge, decode_r, SigVersion};
+use crate::threshold::threshold_challenge;
+use crate::utils::{decode_public_bytes, bigint2bytes, bytes2point_inner, KeyType};
+use crate::verifyutils::verify_auto_signature;
+use emerald_city::curv::elliptic::curves::secp256_k1::{FE, GE};
+use emerald_city::curv::elliptic::curves::traits::{ECPoint, ECScalar};
+use emerald_city::curv::arithmetic::num_bigint::BigInt;
+use num_traits::One;
+
+
+/// one signature converted for batch verification
+/// Linear: s*G == R + e*P
+/// XOnly: same as Linear with R of even Y, R of odd Y is also valid and checked alone
+/// Checked: cannot be batched, already verified alone
+enum Equation {
+    Linear { s: FE, r: GE, e: FE, public: GE },
+    XOnly { s: FE, r: GE, e: FE, public: GE },
+    Checked(bool),
+}
+
+impl Equation {
+    /// s*G == -R + e*P, odd Y case of XOnly
+    fn check_odd_y(&self) -> bool {
+        match self {
+            Equation::XOnly { s, r, e, public } => {
+                let base_point: GE = ECPoint::generator();
+                let expected = negate_point(r).add_point(&public.scalar_mul(&e.get_element()).get_element());
+                base_point.scalar_mul(&s.get_element()) == expected
+            },
+            _ => false,
+        }
+    }
+}
+
+/// convert verify_auto_signature params to equation
+/// BIP-340 (32b apk) and threshold (V is full point) are linear
+/// tagged 1 of 1 and n of n send only R.x, signers choose R of even Y (see KeyPair::single_sign)
+/// legacy 1 of 1 and n of n are verified alone
+fn parse_equation(s: &[u8], r: &[u8], apk: &[u8], message: &[u8]) -> Equation {
+    if apk.len() == 32 {
+        if s.len() != 32 || FIELD_SIZE[..] <= *r || CURVE_ORDER[..] <= *s {
+            return Equation::Checked(false);
+        }
+        let public = match lift_x(apk) {
+            Ok(public) => public,
+            Err(_) => return Equation::Checked(false),
+        };
+        let r_point = match lift_x(r) {
+            Ok(r_point) => r_point,
+            Err(_) => return Equation::Checked(false),
+        };
+        let e = bip340_challenge(r, apk, message);
+        let s: FE = ECScalar::from(&BigInt::from_bytes_be(s));
+        return Equation::Linear { s, r: r_point, e, public };
+    }
+    match decode_public_bytes(apk) {
+        Ok((KeyType::ThresholdSig, _)) => {
+            let (version, r) = match decode_r(r, 33) {
+                Ok(decoded) => decoded,
+                Err(_) => return Equation::Checked(false),
+            };
+            match (bytes2point_inner(apk), bytes2point_inner(r)) {
+                (Ok(Y), Ok(V)) => {
+                    let e = threshold_challenge(&Y, &V, message, version);
+                    let s: FE = ECScalar::from(&BigInt::from_bytes_be(s));
+                    Equation::Linear { s, r: V, e, public: Y }
+                },
+                _ => Equation::Checked(false),
+            }
+        },
+        Ok((key_type, _)) => {
+            let (version, r) = match decode_r(r, 32) {
+                Ok(decoded) => decoded,
+                Err(_) => return Equation::Checked(false),
+            };
+            if version == SigVersion::Legacy {
+                return Equation::Checked(verify_auto_signature(s, r, apk, message).unwrap_or(false));
+            }
+            match (bytes2point_inner(apk), lift_x(r)) {
+                (Ok(public), Ok(r_point)) => {
+                    let e = challenge(r, &public, key_type, message);
+                    let s: FE = ECScalar::from(&BigInt::from_bytes_be(s));
+                    Equation::XOnly { s, r: r_point, e, public }
+                },
+                _ => Equation::Checked(false),
+            }
+        },
+        Err(_) => Equation::Checked(false),
+    }
+}
+
+/// P + Q, None is point at infinity
+fn add_points(a: Option<GE>, b: &GE) -> Option<GE> {
+    match a {
+        None => Some(b.clone()),
+        Some(a) => {
+            if a.x_coor() == b.x_coor() && &a != b {
+                None // P + (-P)
+            } else {
+                Some(a.add_point(&b.get_element()))
+            }
+        }
+    }
+}
+
+fn add_optional_points(a: Option<GE>, b: &Option<GE>) -> Option<GE> {
+    match b {
+        Some(b) => add_points(a, b),
+        None => a,
+    }
+}
+
+/// bucket method multi scalar multiplication (Pippenger)
+/// return sum of scalar_i * point_i, None is point at infinity
+pub fn multi_scalar_mul(scalars: &[FE], points: &[GE]) -> Option<GE> {
+    assert_eq!(scalars.len(), points.len());
+    let window: usize = match points.len() {
+        0..=15 => 2,
+        16..=127 => 4,
+        128..=1023 => 6,
+        _ => 8,
+    };
+    let digits: Vec<[u8;32]> = scalars.iter()
+        .map(|s| bigint2bytes(&s.to_big_int()).unwrap())
+        .collect();
+    // little endian bit position
+    let get_digit = |bytes: &[u8;32], offset: usize| -> usize {
+        let mut digit = 0usize;
+        for bit in (offset..(offset + window).min(256)).rev() {
+            let byte = bytes[31 - bit / 8];
+            digit = (digit << 1) | ((byte >> (bit % 8)) & 1) as usize;
+        }
+        digit
+    };
+    let n_windows = (256 + window - 1) / window;
+    let mut result: Option<GE> = None;
+    for w in (0..n_windows).rev() {
+        for _ in 0..window {
+            result = result.map(|p| p.add_point(&p.get_element()));
+        }
+        let mut buckets: Vec<Option<GE>> = vec![None; (1 << window) - 1];
+        for (bytes, point) in digits.iter().zip(points.iter()) {
+            let digit = get_digit(bytes, w * window);
+            if 0 < digit {
+                buckets[digit - 1] = add_points(buckets[digit - 1].take(), point);
+            }
+        }
+        // sum of digit * bucket by running sum
+        let mut running: Option<GE> = None;
+        let mut window_sum: Option<GE> = None;
+        for bucket in buckets.iter().rev() {
+            running = add_optional_points(running, bucket);
+            window_sum = add_optional_points(window_sum, &running);
+        }
+        result = add_optional_points(result, &window_sum);
+    }
+    result
+}
+
+/// check all equations at once with random coefficients
+/// (sum a_i*s_i)*G == sum a_i*R_i + sum a_i*e_i*P_i
+fn check_equations(equations: &[&Equation]) -> bool {
+    let mut s_sum = FE::zero();
+    let mut scalars = Vec::with_capacity(equations.len() * 2);
+    let mut points = Vec::with_capacity(equations.len() * 2);
+    for (index, equation) in equations.iter().enumerate() {
+        match equation {
+            Equation::Checked(false) => return false,
+            Equation::Checked(true) => (),
+            Equation::Linear { s, r, e, public } | Equation::XOnly { s, r, e, public } => {
+                // first coefficient can be one
+                let a: FE = if index == 0 { ECScalar::from(&BigInt::one()) } else { ECScalar::new_random() };
+                s_sum = s_sum + a.clone() * s.clone();
+                scalars.push(a.clone());
+                points.push(r.clone());
+                scalars.push(a * e.clone());
+                points.push(public.clone());
+            },
+        }
+    }
+    if points.len() == 0 {
+        return true;
+    }
+    let base_point: GE = ECPoint::generator();
+    let rhs = multi_scalar_mul(&scalars, &points);
+    if s_sum == FE::zero() {
+        rhs.is_none()
+    } else {
+        rhs == Some(base_point.scalar_mul(&s_sum.get_element()))
+    }
+}
+
+/// find invalid equations by bisection
+fn bisect_equations(equations: &[&Equation], offset: usize, invalid: &mut Vec<usize>) {
+    if check_equations(equations) {
+        return;
+    } else if equations.len() == 1 {
+        if !equations[0].check_odd_y() {
+            invalid.push(offset);
+        }
+        return;
+    }
+    let middle = equations.len() / 2;
+    bisect_equations(&equations[..middle], offset, invalid);
+    bisect_equations(&equations[middle..], offset + middle, invalid);
+}
+
+/// batch verify many signature (s, r, apk, message) with detection
+/// same params as verify_auto_signature, return true only if all valid
+pub fn batch_verify(tasks: &[(Vec<u8>, Vec<u8>, Vec<u8>, Vec<u8>)]) -> bool {
+    let equations: Vec<Equation> = tasks.iter()
+        .map(|(s, r, apk, message)| parse_equation(s, r, apk, message))
+        .collect();
+    let equations: Vec<&Equation> = equations.iter().collect();
+    if check_equations(&equations) {
+        return true;
+    }
+    // R of odd Y is not an error, find it by bisection
+    let has_x_only = equations.iter().any(|e| match e { Equation::XOnly {..} => true, _ => false });
+    if !has_x_only {
+        return false;
+    }
+    let mut invalid = Vec::new();
+    bisect_equations(&equations, 0, &mut invalid);
+    invalid.is_empty()
+}
+
+/// batch verify, fall back to bisection when batch failed
+/// return indexes of invalid signature (empty if all valid)
+pub fn batch_verify_find_invalid(tasks: &[(Vec<u8>, Vec<u8>, Vec<u8>, Vec<u8>)]) -> Vec<usize> {
+    let equations: Vec<Equation> = tasks.iter()
+        .map(|(s, r, apk, message)| parse_equation(s, r, apk, message))
+        .collect();
+    let equations: Vec<&Equation> = equations.iter().collect();
+    let mut invalid = Vec::new();
+    bisect_equations(&equations, 0, &mut invalid);
+    invalid
+}
+
+
+#[cfg(test)]
+mod Test {
+    use crate::batchverify::*;
+    use crate::bip340::has_even_y;
+    use crate::hashing::encode_r;
+    use crate::keypair::KeyPair;
+    use crate::musig2::{aggregate_partial_signatures, Musig2Nonce, Musig2Session};
+    use crate::utils::point2bytes;
+
+    type Task = (Vec<u8>, Vec<u8>, Vec<u8>, Vec<u8>);
+
+    // BIP-340, 1 of 1, n of n and threshold signatures of msg
+    fn mixed_tasks(msg: &[u8]) -> Vec<Task> {
+        let g: GE = ECPoint::generator();
+        let keypair = KeyPair::new();
+        let signature = keypair.bip340_sign(msg, &[0u8;32]).unwrap();
+        let bip340 = (signature[32..].to_vec(), signature[..32].to_vec(), keypair.bip340_public_key().to_vec(), msg.to_vec());
+
+        let (r, s) = keypair.single_sign(msg, &[1u8;32]).unwrap();
+        let single = (bigint2bytes(&s).unwrap().to_vec(), encode_r(&bigint2bytes(&r).unwrap()),
+                      point2bytes(&keypair.public, KeyType::SingleSig).to_vec(), msg.to_vec());
+
+        let pairs: Vec<KeyPair> = (0..2).map(|_| KeyPair::new()).collect();
+        let nonces: Vec<Musig2Nonce> = (0..2).map(|_| Musig2Nonce::new()).collect();
+        let signers: Vec<GE> = pairs.iter().map(|p| p.public.clone()).collect();
+        let public_nonces: Vec<(GE, GE)> = nonces.iter().map(|n| n.public.clone()).collect();
+        let mut sessions: Vec<Musig2Session> = pairs.iter().zip(nonces.into_iter())
+            .map(|(p, n)| Musig2Session::generate(&signers, &public_nonces, p, n, msg).unwrap())
+            .collect();
+        let parts: Vec<FE> = sessions.iter_mut().map(|s| s.partial_sign().unwrap()).collect();
+        let s = aggregate_partial_signatures(&parts).unwrap();
+        let aggregate = (bigint2bytes(&s.to_big_int()).unwrap().to_vec(), encode_r(&bigint2bytes(&sessions[0].R()).unwrap()),
+                         sessions[0].apk_bytes().to_vec(), msg.to_vec());
+
+        // any key is a valid Y of threshold signature
+        let (x, k): (FE, FE) = (ECScalar::new_random(), ECScalar::new_random());
+        let (Y, V) = (g.clone() * &x, g.clone() * &k);
+        let e = threshold_challenge(&Y, &V, msg, SigVersion::Tagged);
+        let sigma = k + e * x;
+        let threshold = (bigint2bytes(&sigma.to_big_int()).unwrap().to_vec(), encode_r(&V.get_element().serialize()),
+                         point2bytes(&Y, KeyType::ThresholdSig).to_vec(), msg.to_vec());
+        vec![bip340, single, aggregate, threshold]
+    }
+
+    #[test]
+    fn test_multi_scalar_mul() {
+        let g: GE = ECPoint::generator();
+        let scalars: Vec<FE> = (0..20).map(|_| ECScalar::new_random()).collect();
+        let points: Vec<GE> = (0..20).map(|_| KeyPair::new().public).collect();
+        let expected = scalars.iter().zip(points.iter())
+            .map(|(s, p)| p.scalar_mul(&s.get_element()))
+            .fold(g.clone(), |acc, p| acc + p);
+        let result = add_points(multi_scalar_mul(&scalars, &points), &g).unwrap();
+        assert!(result == expected);
+    }
+
+    #[test]
+    fn test_batch_verify_bip340() {
+        let msg = b"batch".to_vec();
+        let mut tasks: Vec<(Vec<u8>, Vec<u8>, Vec<u8>, Vec<u8>)> = (0..10).map(|_| {
+            let keypair = KeyPair::new();
+            let signature = keypair.bip340_sign(&msg, &[0u8;32]).unwrap();
+            (signature[32..].to_vec(), signature[..32].to_vec(), keypair.bip340_public_key().to_vec(), msg.clone())
+        }).collect();
+        assert!(batch_verify(&tasks));
+        assert!(batch_verify_find_invalid(&tasks).is_empty());
+        // break 3 and 7
+        tasks[3].3 = b"fake".to_vec();
+        tasks[7].0 = tasks[6].0.clone();
+        assert!(!batch_verify(&tasks));
+        assert_eq!(batch_verify_find_invalid(&tasks), vec![3, 7]);
+    }
+
+    #[test]
+    fn test_batch_verify_mixed_types() {
+        let msg = b"mixed";
+        let mut tasks: Vec<Task> = (0..3).flat_map(|_| mixed_tasks(msg)).collect();
+        // 1 of 1 signature of R with odd Y is still valid
+        let g: GE = ECPoint::generator();
+        let x: FE = ECScalar::new_random();
+        let public = g.clone() * &x;
+        let k: FE = loop {
+            let k: FE = ECScalar::new_random();
+            if !has_even_y(&(g.clone() * &k)) {
+                break k;
+            }
+        };
+        let r = bigint2bytes(&(g.clone() * &k).x_coor().unwrap()).unwrap();
+        let s = k + challenge(&r, &public, KeyType::SingleSig, msg) * x;
+        tasks.push((bigint2bytes(&s.to_big_int()).unwrap().to_vec(), encode_r(&r),
+                    point2bytes(&public, KeyType::SingleSig).to_vec(), msg.to_vec()));
+        assert!(batch_verify(&tasks));
+        assert!(batch_verify_find_invalid(&tasks).is_empty());
+        // one bad signature of each type
+        for index in &[0, 5, 10, 3] {
+            tasks[*index].3 = b"fake".to_vec();
+        }
+        assert!(!batch_verify(&tasks));
+        assert_eq!(batch_verify_find_invalid(&tasks), vec![0, 3, 5, 10]);
+    }
+}
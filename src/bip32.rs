@@ -0,0 +1,347 @@
+use crate::bip340::{tagged_hash, CURVE_ORDER};
+use crate::error::Error;
+use crate::hashing::{hash2scalar, point_bytes, TAG_BIP32_CHAINCODE};
+use crate::keypair::KeyPair;
+use crate::secret::SecretScalar;
+use crate::tweak::add_tweak;
+use crate::utils::{bytes2point_inner, decode_public_bytes, point2bytes, KeyType};
+use emerald_city::curv::elliptic::curves::secp256_k1::{FE, GE};
+use emerald_city::curv::elliptic::curves::traits::{ECPoint, ECScalar};
+use hmac::{Hmac, Mac};
+use ripemd160::Ripemd160;
+use sha2::{Digest, Sha256, Sha512};
+
+
+/// mainnet version bytes of BIP-32
+pub const XPRV_VERSION: [u8;4] = [0x04, 0x88, 0xad, 0xe4];
+pub const XPUB_VERSION: [u8;4] = [0x04, 0x88, 0xb2, 0x1e];
+/// child number of hardened key starts from 2^31
+pub const HARDENED: u32 = 0x8000_0000;
+
+
+/// BIP-32 extended private key of single signer
+#[derive(Clone)]
+pub struct ExtendedPrivKey {
+    pub keypair: KeyPair,
+    pub chain_code: [u8;32],
+    pub depth: u8,
+    pub parent_fingerprint: [u8;4],
+    pub child_number: u32,
+}
+
+/// BIP-32 extended public key, key_type is kept for group keys (0x05 and 0x08)
+/// group keys have no private key, only non-hardened derivation is possible
+#[derive(Clone)]
+pub struct ExtendedPubKey {
+    pub public: GE,
+    pub key_type: KeyType,
+    pub chain_code: [u8;32],
+    pub depth: u8,
+    pub parent_fingerprint: [u8;4],
+    pub child_number: u32,
+}
+
+impl ExtendedPrivKey {
+    /// master key, I = HMAC-SHA512("Bitcoin seed", seed)
+    pub fn from_seed(seed: &[u8]) -> Result<ExtendedPrivKey, Error> {
+        if seed.len() < 16 || 64 < seed.len() {
+            return Err(Error::InvalidParams(format!("seed length {} is not 16~64 bytes", seed.len())));
+        }
+        let (secret, chain_code) = hmac_split(b"Bitcoin seed", &[seed])?;
+        Ok(ExtendedPrivKey {
+            keypair: keypair_from_scalar(secret)?,
+            chain_code,
+            depth: 0,
+            parent_fingerprint: [0u8;4],
+            child_number: 0,
+        })
+    }
+
+    /// child key at index, hardened when HARDENED <= index
+    /// error when I_L is out of range or child is zero, use next index
+    pub fn derive_child(&self, index: u32) -> Result<ExtendedPrivKey, Error> {
+        let (il, chain_code) = if HARDENED <= index {
+            let mut secret = self.keypair.secret.to_bytes();
+            let result = hmac_split(&self.chain_code, &[&[0u8], &secret, &index.to_be_bytes()]);
+            for b in secret.iter_mut() {
+                *b = 0;
+            }
+            result?
+        } else {
+            hmac_split(&self.chain_code, &[&point_bytes(&self.keypair.public), &index.to_be_bytes()])?
+        };
+        Ok(ExtendedPrivKey {
+            keypair: keypair_from_scalar(self.keypair.secret.add(&il))?,
+            chain_code,
+            depth: self.child_depth()?,
+            parent_fingerprint: fingerprint(&self.keypair.public),
+            child_number: index,
+        })
+    }
+
+    /// derive by path of parse_path
+    pub fn derive_path(&self, path: &[u32]) -> Result<ExtendedPrivKey, Error> {
+        let mut key = self.clone();
+        for index in path {
+            key = key.derive_child(*index)?;
+        }
+        Ok(key)
+    }
+
+    /// neutered key
+    pub fn public(&self) -> ExtendedPubKey {
+        ExtendedPubKey {
+            public: self.keypair.public.clone(),
+            key_type: KeyType::SingleSig,
+            chain_code: self.chain_code,
+            depth: self.depth,
+            parent_fingerprint: self.parent_fingerprint,
+            child_number: self.child_number,
+        }
+    }
+
+    /// base58check xprv string
+    pub fn to_xprv(&self) -> String {
+        let mut key = [0u8;33];
+        key[1..].copy_from_slice(&self.keypair.secret.to_bytes());
+        let encoded = encode_extended(&XPRV_VERSION, self.depth, &self.parent_fingerprint,
+                                      self.child_number, &self.chain_code, &key);
+        for b in key.iter_mut() {
+            *b = 0;
+        }
+        encoded
+    }
+
+    pub fn from_xprv(xprv: &str) -> Result<ExtendedPrivKey, Error> {
+        let (version, depth, parent_fingerprint, child_number, chain_code, key) = decode_extended(xprv)?;
+        if version != XPRV_VERSION {
+            return Err(Error::Decode(format!("unknown xprv version {}", hex::encode(&version))));
+        } else if key[0] != 0 {
+            return Err(Error::Decode("private key do not start with 0x00".to_owned()));
+        }
+        let mut secret = [0u8;32];
+        secret.copy_from_slice(&key[1..]);
+        if CURVE_ORDER <= secret {
+            return Err(Error::InvalidScalar("private key is out of range".to_owned()));
+        }
+        Ok(ExtendedPrivKey {
+            keypair: keypair_from_scalar(hash2scalar(&secret))?,
+            chain_code,
+            depth,
+            parent_fingerprint,
+            child_number,
+        })
+    }
+
+    /// first 4bytes of HASH160(public key)
+    pub fn fingerprint(&self) -> [u8;4] {
+        fingerprint(&self.keypair.public)
+    }
+
+    fn child_depth(&self) -> Result<u8, Error> {
+        self.depth.checked_add(1).ok_or(Error::InvalidParams("max depth 255 is reached".to_owned()))
+    }
+}
+
+impl ExtendedPubKey {
+    /// root of group key (apk or Y) to derive without key generation again
+    /// chain_code: shared by signers, derived from the key when None
+    pub fn from_group_key(public: &GE, key_type: KeyType, chain_code: Option<[u8;32]>) -> ExtendedPubKey {
+        let chain_code = chain_code.unwrap_or_else(|| tagged_hash(
+            TAG_BIP32_CHAINCODE, &[&point2bytes(public, key_type)]));
+        ExtendedPubKey {
+            public: public.clone(),
+            key_type,
+            chain_code,
+            depth: 0,
+            parent_fingerprint: [0u8;4],
+            child_number: 0,
+        }
+    }
+
+    /// non-hardened child key and tweak I_L, child = parent + I_L*G
+    /// signers of group key sign for child by the same tweak
+    pub fn derive_child(&self, index: u32) -> Result<(ExtendedPubKey, FE), Error> {
+        if HARDENED <= index {
+            return Err(Error::InvalidParams("hardened derivation needs private key".to_owned()));
+        }
+        let (il, chain_code) = hmac_split(&self.chain_code, &[&point_bytes(&self.public), &index.to_be_bytes()])?;
+        let child = ExtendedPubKey {
+            public: add_tweak(&self.public, &il)?,
+            key_type: self.key_type,
+            chain_code,
+            depth: self.depth.checked_add(1).ok_or(
+                Error::InvalidParams("max depth 255 is reached".to_owned()))?,
+            parent_fingerprint: fingerprint(&self.public),
+            child_number: index,
+        };
+        Ok((child, il))
+    }
+
+    /// derive by path of parse_path and return sum of tweaks
+    pub fn derive_path(&self, path: &[u32]) -> Result<(ExtendedPubKey, FE), Error> {
+        let mut iter = path.iter();
+        let head = iter.next().ok_or(Error::InvalidParams("empty path".to_owned()))?;
+        let (mut key, mut tweak) = self.derive_child(*head)?;
+        for index in iter {
+            let (child, il) = key.derive_child(*index)?;
+            key = child;
+            tweak = tweak.add(&il.get_element());
+        }
+        Ok((key, tweak))
+    }
+
+    /// base58check xpub string, group keys keep 0x05 or 0x08 prefix
+    pub fn to_xpub(&self) -> String {
+        encode_extended(&XPUB_VERSION, self.depth, &self.parent_fingerprint, self.child_number,
+                        &self.chain_code, &point2bytes(&self.public, self.key_type))
+    }
+
+    pub fn from_xpub(xpub: &str) -> Result<ExtendedPubKey, Error> {
+        let (version, depth, parent_fingerprint, child_number, chain_code, key) = decode_extended(xpub)?;
+        if version != XPUB_VERSION {
+            return Err(Error::Decode(format!("unknown xpub version {}", hex::encode(&version))));
+        }
+        let (key_type, _prefix) = decode_public_bytes(&key)?;
+        Ok(ExtendedPubKey {
+            public: bytes2point_inner(&key)?,
+            key_type,
+            chain_code,
+            depth,
+            parent_fingerprint,
+            child_number,
+        })
+    }
+
+    /// first 4bytes of HASH160(public key)
+    pub fn fingerprint(&self) -> [u8;4] {
+        fingerprint(&self.public)
+    }
+}
+
+
+/// "m/44'/0'/0h/1" => [44+HARDENED, HARDENED, HARDENED, 1]
+pub fn parse_path(path: &str) -> Result<Vec<u32>, Error> {
+    let mut items = path.split('/');
+    if items.next() != Some("m") {
+        return Err(Error::Decode(format!("path {} do not start with m", path)));
+    }
+    items.map(|item| {
+        let (number, offset) = if item.ends_with('\'') || item.ends_with('h') {
+            (&item[..item.len() - 1], HARDENED)
+        } else {
+            (item, 0)
+        };
+        match number.parse::<u32>() {
+            Ok(index) if index < HARDENED => Ok(index + offset),
+            _ => Err(Error::Decode(format!("broken path item {}", item))),
+        }
+    }).collect()
+}
+
+/// I = HMAC-SHA512(key, data), return I_L as scalar and I_R as chain code
+fn hmac_split(key: &[u8], data: &[&[u8]]) -> Result<(FE, [u8;32]), Error> {
+    let mut mac = Hmac::<Sha512>::new_varkey(key).unwrap();
+    for d in data {
+        mac.input(d);
+    }
+    let output = mac.result().code();
+    let mut il = [0u8;32];
+    let mut chain_code = [0u8;32];
+    il.copy_from_slice(&output[..32]);
+    chain_code.copy_from_slice(&output[32..]);
+    if CURVE_ORDER <= il {
+        return Err(Error::InvalidScalar("I_L is out of range, use next index".to_owned()));
+    }
+    let scalar = hash2scalar(&il);
+    for b in il.iter_mut() {
+        *b = 0;
+    }
+    Ok((scalar, chain_code))
+}
+
+fn keypair_from_scalar(secret: FE) -> Result<KeyPair, Error> {
+    if secret == FE::zero() {
+        return Err(Error::InvalidScalar("derived key is zero, use next index".to_owned()));
+    }
+    let base_point: GE = ECPoint::generator();
+    let public = base_point.scalar_mul(&secret.get_element());
+    Ok(KeyPair {secret: SecretScalar::new(secret), public})
+}
+
+fn fingerprint(public: &GE) -> [u8;4] {
+    let hash = Ripemd160::digest(&Sha256::digest(&point_bytes(public)));
+    let mut output = [0u8;4];
+    output.copy_from_slice(&hash[..4]);
+    output
+}
+
+/// version(4) || depth(1) || parent fingerprint(4) || child number(4) || chain code(32) || key(33)
+fn encode_extended(version: &[u8;4], depth: u8, parent_fingerprint: &[u8;4], child_number: u32,
+                   chain_code: &[u8;32], key: &[u8;33]) -> String {
+    let mut data = Vec::with_capacity(78);
+    data.extend_from_slice(version);
+    data.push(depth);
+    data.extend_from_slice(parent_fingerprint);
+    data.extend_from_slice(&child_number.to_be_bytes());
+    data.extend_from_slice(chain_code);
+    data.extend_from_slice(key);
+    let encoded = bs58::encode(&data).with_check().into_string();
+    for b in data.iter_mut() {
+        *b = 0;
+    }
+    encoded
+}
+
+fn decode_extended(encoded: &str) -> Result<([u8;4], u8, [u8;4], u32, [u8;32], [u8;33]), Error> {
+    let data = bs58::decode(encoded).with_check(None).into_vec()
+        .map_err(|err| Error::Decode(format!("base58check: {:?}", err)))?;
+    if data.len() != 78 {
+        return Err(Error::Decode(format!("extended key length {} is not 78", data.len())));
+    }
+    let mut version = [0u8;4];
+    let mut parent_fingerprint = [0u8;4];
+    let mut child_number = [0u8;4];
+    let mut chain_code = [0u8;32];
+    let mut key = [0u8;33];
+    version.copy_from_slice(&data[0..4]);
+    let depth = data[4];
+    parent_fingerprint.copy_from_slice(&data[5..9]);
+    child_number.copy_from_slice(&data[9..13]);
+    chain_code.copy_from_slice(&data[13..45]);
+    key.copy_from_slice(&data[45..78]);
+    let child_number = u32::from_be_bytes(child_number);
+    if depth == 0 && (parent_fingerprint != [0u8;4] || child_number != 0) {
+        return Err(Error::Decode("master key with parent fingerprint or child number".to_owned()));
+    }
+    Ok((version, depth, parent_fingerprint, child_number, chain_code, key))
+}
+
+
+#[cfg(test)]
+mod Test {
+    use crate::bip32::*;
+
+    #[test]
+    fn test_vector_1() {
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        let master = ExtendedPrivKey::from_seed(&seed).unwrap();
+        assert_eq!(master.to_xprv(), "xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChkVvvNKmPGJxWUtg6LJ3xSLUHr7EzPWTz4Pqh4zEe5b6g8eRnYZ");
+        assert_eq!(master.public().to_xpub(), "xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8");
+        let child = master.derive_path(&parse_path("m/0'").unwrap()).unwrap();
+        assert_eq!(child.to_xprv(), "xprv9uHRZZhk6KAJC1avXpDAp4MDc3sQKNxDiPvvkX8Br5ngLNv1TxvUxt4cV1rGL5hj6KCesnDYUhd7oWgT11eZG7XnxHrnYeSvkzY7d2bhkJ7");
+        assert_eq!(child.public().to_xpub(), "xpub68Gmy5EdvgibQVfPdqkBBCHxA5htiqg55crXYuXoQRKfDBFA1WEjWgP6LHhwBZeNK1VTsfTFUHCdrfp1bgwQ9xv5ski8PX9rL2dZXvgGDnw");
+        assert!(ExtendedPrivKey::from_xprv(&child.to_xprv()).unwrap().keypair.public == child.keypair.public);
+    }
+
+    #[test]
+    fn test_public_derivation_tweak() {
+        let master = ExtendedPrivKey::from_seed(&[7u8;32]).unwrap();
+        let path = parse_path("m/1/2").unwrap();
+        let child = master.derive_path(&path).unwrap();
+        let (xpub, tweak) = master.public().derive_path(&path).unwrap();
+        assert!(xpub.public == child.keypair.public);
+        assert!(xpub.public == add_tweak(&master.keypair.public, &tweak).unwrap());
+        assert!(master.public().derive_child(HARDENED).is_err());
+    }
+}
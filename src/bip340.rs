@@ -0,0 +1,236 @@
+use crate::error::Error;
+use crate::keypair::KeyPair;
+use crate::utils::{bigint2bytes, bytes2point_inner};
+use emerald_city::curv::elliptic::curves::secp256_k1::{FE, GE};
+use emerald_city::curv::elliptic::curves::traits::{ECPoint, ECScalar};
+use emerald_city::curv::arithmetic::num_bigint::BigInt;
+use sha2::{Digest, Sha256};
+
+
+/// secp256k1 field size p (big endian)
+pub const FIELD_SIZE: [u8;32] = [
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe, 0xff, 0xff, 0xfc, 0x2f,
+];
+
+/// secp256k1 curve order n (big endian)
+pub const CURVE_ORDER: [u8;32] = [
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe,
+    0xba, 0xae, 0xdc, 0xe6, 0xaf, 0x48, 0xa0, 0x3b, 0xbf, 0xd2, 0x5e, 0x8c, 0xd0, 0x36, 0x41, 0x41,
+];
+
+
+/// tagged hash defined by BIP-340
+/// SHA256(SHA256(tag) || SHA256(tag) || x)
+pub fn tagged_hash(tag: &str, msgs: &[&[u8]]) -> [u8;32] {
+    let tag_hash = Sha256::digest(tag.as_bytes());
+    let mut hasher = Sha256::new();
+    hasher.input(&tag_hash);
+    hasher.input(&tag_hash);
+    for msg in msgs {
+        hasher.input(msg);
+    }
+    let mut output = [0u8;32];
+    output.copy_from_slice(&hasher.result());
+    output
+}
+
+/// 32bytes x-only public key
+pub fn point2xonly(point: &GE) -> [u8;32] {
+    bigint2bytes(&point.x_coor().unwrap()).unwrap()
+}
+
+/// even Y point from x-only public key
+pub fn lift_x(x: &[u8]) -> Result<GE, Error> {
+    if x.len() != 32 {
+        return Err(Error::LengthMismatch { name: "x-only key", expected: 32, actual: x.len() });
+    } else if FIELD_SIZE[..] <= *x {
+        return Err(Error::InvalidPoint(hex::encode(x)));
+    }
+    let mut compressed = [2u8;33];
+    compressed[1..].copy_from_slice(x);
+    bytes2point_inner(&compressed)
+}
+
+pub fn has_even_y(point: &GE) -> bool {
+    point.get_element().serialize()[0] == 2
+}
+
+/// -P
+pub fn negate_point(point: &GE) -> GE {
+    let mut compressed = point.get_element().serialize();
+    compressed[0] ^= 1; // 0x02 <=> 0x03
+    bytes2point_inner(&compressed).unwrap()
+}
+
+/// n - s
+pub fn negate_scalar(scalar: &FE) -> FE {
+    FE::zero().sub(&scalar.get_element())
+}
+
+/// flip point and scalar to even Y (BIP-340 normalization)
+pub fn normalize_even_y(scalar: &FE, point: &GE) -> (FE, GE) {
+    if has_even_y(point) {
+        (scalar.clone(), point.clone())
+    } else {
+        (negate_scalar(scalar), negate_point(point))
+    }
+}
+
+/// e = int(hash_challenge(R.x || P.x || m)) mod n
+pub fn bip340_challenge(r_x: &[u8], p_x: &[u8], message: &[u8]) -> FE {
+    let e = tagged_hash("BIP0340/challenge", &[r_x, p_x, message]);
+    ECScalar::from(&BigInt::from_bytes_be(&e))
+}
+
+impl KeyPair {
+    /// 32bytes x-only public key
+    pub fn bip340_public_key(&self) -> [u8;32] {
+        point2xonly(&self.public)
+    }
+
+    /// BIP-340 signature R.x(32b) || s(32b)
+    pub fn bip340_sign(&self, message: &[u8], aux_rand: &[u8;32]) -> Result<[u8;64], Error> {
+        let base_point: GE = ECPoint::generator();
+        // d = x or n - x of even Y public key
+        let (d, public) = if has_even_y(&self.public) {
+            (self.secret.clone(), self.public.clone())
+        } else {
+            (self.secret.negate(), negate_point(&self.public))
+        };
+        let p_x = point2xonly(&public);
+        // t = bytes(d) xor hash_aux(a)
+        let mut t = d.to_bytes();
+        let aux_hash = tagged_hash("BIP0340/aux", &[aux_rand]);
+        for (t_i, a_i) in t.iter_mut().zip(aux_hash.iter()) {
+            *t_i ^= a_i;
+        }
+        let rand = tagged_hash("BIP0340/nonce", &[&t, &p_x, message]);
+        for t_i in t.iter_mut() {
+            *t_i = 0;
+        }
+        let k_prime = BigInt::from_bytes_be(&rand);
+        let k_prime: FE = ECScalar::from(&k_prime);
+        if k_prime == FE::zero() {
+            return Err(Error::InvalidScalar("nonce k is zero".to_owned()));
+        }
+        let r_point = base_point.scalar_mul(&k_prime.get_element());
+        let (k, r_point) = normalize_even_y(&k_prime, &r_point);
+        let r_x = point2xonly(&r_point);
+        let e = bip340_challenge(&r_x, &p_x, message);
+        let s = d.mul_add(&e, &k);
+        let mut signature = [0u8;64];
+        signature[..32].copy_from_slice(&r_x);
+        signature[32..].copy_from_slice(&bigint2bytes(&s.to_big_int())?);
+        Ok(signature)
+    }
+}
+
+/// verify BIP-340 signature with 32bytes x-only public key
+pub fn verify_bip340_signature(signature: &[u8], public: &[u8], message: &[u8]) -> Result<(), Error> {
+    if signature.len() != 64 {
+        return Err(Error::LengthMismatch { name: "signature", expected: 64, actual: signature.len() });
+    }
+    let point = lift_x(public)?;
+    let (r_x, s) = signature.split_at(32);
+    if FIELD_SIZE[..] <= *r_x {
+        return Err(Error::InvalidSignature("r is not less than field size"));
+    } else if CURVE_ORDER[..] <= *s {
+        return Err(Error::InvalidSignature("s is not less than curve order"));
+    }
+    let e = bip340_challenge(r_x, public, message);
+    let s: FE = ECScalar::from(&BigInt::from_bytes_be(s));
+    let base_point: GE = ECPoint::generator();
+    // R = s*G - e*P
+    let sG = base_point.scalar_mul(&s.get_element());
+    let eP = point.scalar_mul(&e.get_element());
+    if sG == eP {
+        return Err(Error::InvalidSignature("R is point at infinity"));
+    }
+    let r_point = sG.sub_point(&eP.get_element());
+    if !has_even_y(&r_point) {
+        Err(Error::InvalidSignature("R has odd Y"))
+    } else if point2xonly(&r_point)[..] != *r_x {
+        Err(Error::InvalidSignature("R.x do not match with r"))
+    } else {
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod Test {
+    use crate::bip340::verify_bip340_signature;
+    use crate::keypair::KeyPair;
+
+    fn hex32(s: &str) -> [u8;32] {
+        let mut bytes = [0u8;32];
+        bytes.copy_from_slice(&hex::decode(s).unwrap());
+        bytes
+    }
+
+    #[test]
+    fn test_bip340_vector_0() {
+        let sk = hex::decode("0000000000000000000000000000000000000000000000000000000000000003").unwrap();
+        let pk = "f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9";
+        let aux = hex32("0000000000000000000000000000000000000000000000000000000000000000");
+        let msg = hex::decode("0000000000000000000000000000000000000000000000000000000000000000").unwrap();
+        let sig = "e907831f80848d1069a5371b402410364bdf1c5f8307b0084c55f1ce2dca8215\
+                   25f66a4a85ea8b71e482a74f382d2ce5ebeee8fdb2172f477df4900d310536c0";
+
+        let keypair = KeyPair::from_secret_key(&sk);
+        assert_eq!(hex::encode(&keypair.bip340_public_key()[..]), pk);
+        let signature = keypair.bip340_sign(&msg, &aux).unwrap();
+        assert_eq!(hex::encode(&signature[..]), sig);
+        assert!(verify_bip340_signature(&signature, &hex::decode(pk).unwrap(), &msg).is_ok());
+    }
+
+    #[test]
+    fn test_bip340_vector_1() {
+        let sk = hex::decode("b7e151628aed2a6abf7158809cf4f3c762e7160f38b4da56a784d9045190cfef").unwrap();
+        let pk = "dff1d77f2a671c5f36183726db2341be58feae1da2deced843240f7b502ba659";
+        let aux = hex32("0000000000000000000000000000000000000000000000000000000000000001");
+        let msg = hex::decode("243f6a8885a308d313198a2e03707344a4093822299f31d0082efa98ec4e6c89").unwrap();
+        let sig = "6896bd60eeae296db48a229ff71dfe071bde413e6d43f917dc8dcf8c78de3341\
+                   8906d11ac976abccb20b091292bff4ea897efcb639ea871cfa95f6de339e4b0a";
+
+        let keypair = KeyPair::from_secret_key(&sk);
+        assert_eq!(hex::encode(&keypair.bip340_public_key()[..]), pk);
+        let signature = keypair.bip340_sign(&msg, &aux).unwrap();
+        assert_eq!(hex::encode(&signature[..]), sig);
+        assert!(verify_bip340_signature(&signature, &hex::decode(pk).unwrap(), &msg).is_ok());
+        // tampered message
+        assert!(verify_bip340_signature(&signature, &hex::decode(pk).unwrap(), b"hello").is_err());
+    }
+
+    #[test]
+    fn test_bip340_vector_12_and_14() {
+        let msg = hex::decode("243f6a8885a308d313198a2e03707344a4093822299f31d0082efa98ec4e6c89").unwrap();
+        // sig[0:32] is equal to field size
+        let pk = hex::decode("dff1d77f2a671c5f36183726db2341be58feae1da2deced843240f7b502ba659").unwrap();
+        let sig = hex::decode("fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f\
+                               69e89b4c5564d00349106b8497785dd7d1d713a8ae82b32fa79d5f7fc407d39b").unwrap();
+        assert!(verify_bip340_signature(&sig, &pk, &msg).is_err());
+        // public key exceeds field size
+        let pk = hex::decode("fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc30").unwrap();
+        let sig = hex::decode("6cff5c3ba86c69ea4b7376f31a9bcb4f74c1976089b2d9963da2e5543e177769\
+                               69e89b4c5564d00349106b8497785dd7d1d713a8ae82b32fa79d5f7fc407d39b").unwrap();
+        assert!(verify_bip340_signature(&sig, &pk, &msg).is_err());
+    }
+
+    #[test]
+    fn test_bip340_infinity_is_rejected() {
+        use crate::bip340::{bip340_challenge, has_even_y};
+        use crate::utils::bigint2bytes;
+        use emerald_city::curv::elliptic::curves::secp256_k1::FE;
+        use emerald_city::curv::elliptic::curves::traits::ECScalar;
+        // s = e*x makes s*G - e*P infinity, P has even Y
+        let keypair = (0..).map(|_| KeyPair::new()).find(|k| has_even_y(&k.public)).unwrap();
+        let pk = keypair.bip340_public_key();
+        let r_x = pk;
+        let e = bip340_challenge(&r_x, &pk, b"msg");
+        let s: FE = keypair.secret.mul_add(&e, &FE::zero());
+        let sig = [&r_x[..], &bigint2bytes(&s.to_big_int()).unwrap()[..]].concat();
+        assert!(verify_bip340_signature(&sig, &pk, b"msg").is_err());
+    }
+}
@@ -0,0 +1,172 @@
+use crate::error::Error;
+use crate::hashing::challenge;
+use crate::keypair::KeyPair;
+use crate::secret::{SecretScalar, Wipe};
+use crate::utils::{bigint2bytes, point2bytes, KeyType};
+use emerald_city::curv::elliptic::curves::secp256_k1::{FE, GE};
+use emerald_city::curv::elliptic::curves::traits::{ECPoint, ECScalar};
+use emerald_city::curv::arithmetic::num_bigint::BigInt;
+use std::collections::BTreeSet;
+use std::sync::{Mutex, MutexGuard};
+
+
+/// public keys with an open blind session in this process, see BlindSigner
+static OPEN_SESSIONS: Mutex<BTreeSet<[u8;33]>> = Mutex::new(BTreeSet::new());
+
+/// signer of blind Schnorr signature
+/// only one session of a key is open at a time, concurrent sessions allow ROS attack
+/// (forgery of l+1 signatures from l concurrent sessions, Benhamouda et al. 2020)
+/// the limit is by public key in this process, every signer of one key (ex. two from_secret_key) shares it
+/// limit across processes or hosts is the caller's responsibility
+/// the session belongs to the signer which opened it, other signers of the key cannot sign or abort it
+pub struct BlindSigner {
+    pub keypair: KeyPair,
+    nonce: Option<SecretScalar>,
+}
+
+impl Drop for BlindSigner {
+    fn drop(&mut self) {
+        self.abort();
+    }
+}
+
+impl BlindSigner {
+    pub fn new(keypair: &KeyPair) -> BlindSigner {
+        BlindSigner { keypair: keypair.clone(), nonce: None }
+    }
+
+    /// open session and return R = k*G
+    /// error if a session of this key is open, by this signer or another
+    pub fn commit(&mut self) -> Result<GE, Error> {
+        if self.nonce.is_some() {
+            return Err(Error::InvalidParams("blind session is already open, sign or abort it first".to_owned()));
+        }
+        if !open_sessions().insert(self.key_id()) {
+            return Err(Error::InvalidParams("blind session of this key is open by another signer".to_owned()));
+        }
+        let nonce = KeyPair::new();
+        let R = nonce.public.clone();
+        self.nonce = Some(nonce.secret);
+        Ok(R)
+    }
+
+    /// s = k + c*x for blinded challenge c, session is closed and k is wiped
+    pub fn sign(&mut self, c: &FE) -> Result<FE, Error> {
+        let nonce = self.nonce.take().ok_or(
+            Error::InvalidParams("no open blind session, commit first".to_owned()))?;
+        open_sessions().remove(&self.key_id());
+        let s = self.keypair.secret.mul_add_secret(c, &nonce);
+        nonce.wipe();
+        Ok(s)
+    }
+
+    /// close my session without signing, nothing is done for a session of another signer
+    pub fn abort(&mut self) {
+        if let Some(nonce) = self.nonce.take() {
+            open_sessions().remove(&self.key_id());
+            nonce.wipe();
+        }
+    }
+
+    /// my session is open
+    pub fn is_open(&self) -> bool {
+        self.nonce.is_some()
+    }
+
+    fn key_id(&self) -> [u8;33] {
+        point2bytes(&self.keypair.public, KeyType::SingleSig)
+    }
+}
+
+fn open_sessions() -> MutexGuard<'static, BTreeSet<[u8;33]>> {
+    // insert and remove never panic, poisoned set is still usable
+    OPEN_SESSIONS.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+
+/// user of blind Schnorr signature, signer cannot link signature to session
+/// R' = R + alpha*G + beta*P, c = H_challenge(R'.x || P || message) + beta
+pub struct BlindUser {
+    pub public: GE,
+    pub R: GE,
+    pub R_hat: GE,
+    pub c: FE,
+    alpha: FE,
+}
+
+impl Drop for BlindUser {
+    fn drop(&mut self) {
+        self.alpha.wipe();
+    }
+}
+
+impl BlindUser {
+    /// blind signer's R and return user state, send c to signer
+    pub fn new(public: &GE, R: &GE, message: &[u8]) -> Result<BlindUser, Error> {
+        let base_point: GE = ECPoint::generator();
+        let alpha: FE = ECScalar::new_random();
+        let mut beta: FE = ECScalar::new_random();
+        let R_hat = R.add_point(&base_point.scalar_mul(&alpha.get_element()).get_element())
+            .add_point(&public.scalar_mul(&beta.get_element()).get_element());
+        let c_hat = challenge(&bigint2bytes(&R_hat.x_coor().unwrap())?, public, KeyType::SingleSig, message);
+        let c = c_hat.add(&beta.get_element());
+        beta.wipe();
+        Ok(BlindUser { public: public.clone(), R: R.clone(), R_hat, c, alpha })
+    }
+
+    /// check signer's s*G == R + c*P and return signature (R'.x, s + alpha)
+    /// verified by verify_aggregate_signature with public key P
+    pub fn unblind(&self, s: &FE) -> Result<(BigInt, FE), Error> {
+        let base_point: GE = ECPoint::generator();
+        let expected = self.R.add_point(&self.public.scalar_mul(&self.c.get_element()).get_element());
+        if base_point.scalar_mul(&s.get_element()) != expected {
+            return Err(Error::InvalidSignature("blind s*G do not match with R + c*P"));
+        }
+        Ok((self.R_hat.x_coor().unwrap(), s.add(&self.alpha.get_element())))
+    }
+}
+
+
+#[cfg(test)]
+mod Test {
+    use crate::aggregate::verify_aggregate_signature;
+    use crate::blind::*;
+    use crate::hashing::SigVersion;
+
+    #[test]
+    fn test_blind_sign() {
+        let keypair = KeyPair::new();
+        let mut signer = BlindSigner::new(&keypair);
+        let R = signer.commit().unwrap();
+        // second session is refused while first is open
+        assert!(signer.commit().is_err());
+        let user = BlindUser::new(&keypair.public, &R, b"token").unwrap();
+        let s = signer.sign(&user.c).unwrap();
+        assert!(!signer.is_open());
+        let (r, s) = user.unblind(&s).unwrap();
+        assert!(r != R.x_coor().unwrap());
+        assert!(verify_aggregate_signature(
+            &s.to_big_int(), &r, &keypair.public, b"token", false, SigVersion::Tagged).is_ok());
+        assert!(signer.sign(&user.c).is_err());
+    }
+
+    #[test]
+    fn test_blind_session_is_per_key() {
+        let keypair = KeyPair::new();
+        let mut signer = BlindSigner::new(&keypair);
+        let mut other = BlindSigner::new(&KeyPair::from_secret_key(&keypair.secret.to_bytes()));
+        signer.commit().unwrap();
+        // another signer of same key cannot open a concurrent session nor abort mine
+        assert!(!other.is_open());
+        assert!(other.commit().is_err());
+        other.abort();
+        assert!(signer.is_open());
+        signer.abort();
+        assert!(other.commit().is_ok());
+        // session is closed when signer is dropped
+        drop(other);
+        assert!(signer.commit().is_ok());
+        // other key is independent
+        assert!(BlindSigner::new(&KeyPair::new()).commit().is_ok());
+    }
+}
@@ -0,0 +1,84 @@
+use crate::error::Error;
+use crate::hashing::point_bytes;
+use crate::keypair::KeyPair;
+use crate::utils::bytes2point_inner;
+use emerald_city::curv::elliptic::curves::secp256_k1::GE;
+use chacha20poly1305::ChaCha20Poly1305;
+use chacha20poly1305::aead::{Aead, NewAead, Payload};
+use chacha20poly1305::aead::generic_array::GenericArray;
+use hkdf::Hkdf;
+use sha2::Sha256;
+
+
+const HKDF_INFO: &[u8] = b"MultiPartySchnorr/ecies";
+/// compressed ephemeral public key
+const EPHEMERAL_LEN: usize = 33;
+/// Poly1305 tag
+const TAG_LEN: usize = 16;
+
+
+/// ECIES, ephemeral ECDH + HKDF-SHA256 + ChaCha20-Poly1305
+/// ciphertext = E(33b) || encrypted plaintext || tag(16b)
+/// aad is authenticated but not encrypted (ex. sender and receiver index)
+pub fn encrypt(public: &GE, plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>, Error> {
+    let ephemeral = KeyPair::new();
+    let shared = ephemeral.shared_point(public);
+    let encoded = point_bytes(&ephemeral.public);
+    let cipher = derive_cipher(&shared, &encoded, public)?;
+    let nonce = [0u8;12];  // key is used once
+    let encrypted = cipher.encrypt(GenericArray::from_slice(&nonce), Payload { msg: plaintext, aad })
+        .map_err(|_| Error::InvalidParams("ecies encryption failed".to_owned()))?;
+    Ok([&encoded[..], &encrypted].concat())
+}
+
+/// decrypt ECIES ciphertext by my keypair, error if broken or aad is different
+pub fn decrypt(keypair: &KeyPair, ciphertext: &[u8], aad: &[u8]) -> Result<Vec<u8>, Error> {
+    if ciphertext.len() < EPHEMERAL_LEN + TAG_LEN {
+        return Err(Error::LengthMismatch {
+            name: "ciphertext", expected: EPHEMERAL_LEN + TAG_LEN, actual: ciphertext.len() });
+    }
+    let ephemeral = bytes2point_inner(&ciphertext[..EPHEMERAL_LEN])?;
+    let shared = keypair.shared_point(&ephemeral);
+    let mut encoded = [0u8;EPHEMERAL_LEN];
+    encoded.copy_from_slice(&ciphertext[..EPHEMERAL_LEN]);
+    let cipher = derive_cipher(&shared, &encoded, &keypair.public)?;
+    let nonce = [0u8;12];
+    cipher.decrypt(GenericArray::from_slice(&nonce), Payload { msg: &ciphertext[EPHEMERAL_LEN..], aad })
+        .map_err(|_| Error::Decode("ecies authentication failed".to_owned()))
+}
+
+/// key = HKDF-SHA256(salt = E || P, ikm = shared point, info)
+fn derive_cipher(shared: &GE, ephemeral: &[u8;33], public: &GE) -> Result<ChaCha20Poly1305, Error> {
+    let salt = [&ephemeral[..], &point_bytes(public)[..]].concat();
+    let mut ikm = point_bytes(shared);
+    let hk = Hkdf::<Sha256>::new(Some(&salt), &ikm);
+    let mut key = [0u8;32];
+    let result = hk.expand(HKDF_INFO, &mut key);
+    for b in ikm.iter_mut() {
+        *b = 0;
+    }
+    result.map_err(|_| Error::InvalidParams("hkdf expand failed".to_owned()))?;
+    let cipher = ChaCha20Poly1305::new(GenericArray::from_slice(&key));
+    for b in key.iter_mut() {
+        *b = 0;
+    }
+    Ok(cipher)
+}
+
+
+#[cfg(test)]
+mod Test {
+    use crate::ecies::*;
+
+    #[test]
+    fn test_encrypt_decrypt() {
+        let receiver = KeyPair::new();
+        let ciphertext = encrypt(&receiver.public, b"secret share", b"aad").unwrap();
+        assert_eq!(decrypt(&receiver, &ciphertext, b"aad").unwrap(), b"secret share".to_vec());
+        assert!(decrypt(&receiver, &ciphertext, b"other aad").is_err());
+        assert!(decrypt(&KeyPair::new(), &ciphertext, b"aad").is_err());
+        let mut broken = ciphertext.clone();
+        broken[40] ^= 1;
+        assert!(decrypt(&receiver, &broken, b"aad").is_err());
+    }
+}
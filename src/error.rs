@@ -0,0 +1,55 @@
+use std::fmt;
+
+
+/// error of all protocols
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    /// not on curve or broken encoding (hex of bytes)
+    InvalidPoint(String),
+    /// out of range or broken scalar
+    InvalidScalar(String),
+    /// list length do not match
+    LengthMismatch { name: &'static str, expected: usize, actual: usize },
+    /// dealer's share or commitment do not match with vss
+    VssValidation { party_index: usize },
+    /// revealed value do not match with commitment
+    Commitment { party_index: Vec<usize> },
+    /// partial signature do not match with public share
+    InvalidShare { party_index: Vec<usize> },
+    /// dealer's proof of knowledge is missing or broken
+    InvalidProof { party_index: Vec<usize> },
+    /// public key prefix is not 0x02~0x0a
+    UnknownKeyPrefix(Option<u8>),
+    /// signature equation do not hold
+    InvalidSignature(&'static str),
+    /// wrong params or protocol state
+    InvalidParams(String),
+    /// broken or unsupported serialized data
+    Decode(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::InvalidPoint(hex_bytes) => write!(f, "invalid pk point: {}", hex_bytes),
+            Error::InvalidScalar(reason) => write!(f, "invalid scalar: {}", reason),
+            Error::LengthMismatch { name, expected, actual } =>
+                write!(f, "not correct {} length: expected={} actual={}", name, expected, actual),
+            Error::VssValidation { party_index } =>
+                write!(f, "failed vss validation check: idx={}", party_index),
+            Error::Commitment { party_index } =>
+                write!(f, "commitment check failed: idx={:?}", party_index),
+            Error::InvalidShare { party_index } =>
+                write!(f, "validate share public failed idx={:?}", party_index),
+            Error::InvalidProof { party_index } =>
+                write!(f, "proof of knowledge check failed: idx={:?}", party_index),
+            Error::UnknownKeyPrefix(Some(prefix)) => write!(f, "unknown public key prefix: {}", prefix),
+            Error::UnknownKeyPrefix(None) => write!(f, "empty public key"),
+            Error::InvalidSignature(reason) => write!(f, "invalid signature: {}", reason),
+            Error::InvalidParams(reason) => write!(f, "{}", reason),
+            Error::Decode(reason) => write!(f, "decode failed: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
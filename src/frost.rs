@@ -0,0 +1,190 @@
+use crate::error::Error;
+use crate::hashing::{frost_binding_factor, frost_challenge};
+use crate::secret::{SecretScalar, Wipe};
+use crate::threshold::{lagrange_coefficient, public_share};
+use emerald_city::curv::cryptographic_primitives::secret_sharing::feldman_vss::VerifiableSS;
+use emerald_city::curv::elliptic::curves::secp256_k1::{FE, GE};
+use emerald_city::curv::elliptic::curves::traits::{ECPoint, ECScalar};
+
+/// preprocessed nonce pair (d_i, e_i) and commitments (D_i, E_i)
+/// not Clone, one secret nonce signs only one session
+pub struct FrostNonce {
+    secret: (SecretScalar, SecretScalar),
+    pub public: (GE, GE),
+}
+
+impl FrostNonce {
+    /// generate random nonce pair (d, e) and (D, E)
+    pub fn new() -> Self {
+        let g: GE = ECPoint::generator();
+        let d = SecretScalar::new(ECScalar::new_random());
+        let e = SecretScalar::new(ECScalar::new_random());
+        let public = (d.scalar_mul(&g), e.scalar_mul(&g));
+        FrostNonce {secret: (d, e), public}
+    }
+
+    /// wipe secret nonce, do not sign with it again
+    pub fn wipe(&mut self) {
+        self.secret.0.wipe();
+        self.secret.1.wipe();
+    }
+}
+
+
+/// FROST signing session over shares of keygen_t_n_parties
+/// signature is (sigma, R) verified by verify_threshold_signature as (sigma, V=R, Frost)
+pub struct FrostSession {
+    pub Y: GE,
+    pub message: Vec<u8>,
+    pub parties_index: Vec<usize>,
+    pub nonces: Vec<(GE, GE)>,
+    pub binding: Vec<FE>,
+    pub r_tag: GE,
+    pub e: FE,
+}
+
+impl FrostSession {
+    /// t: threshold of keygen_t_n_parties
+    /// Y: group key
+    /// parties_index: signer's index (ex. [0, 1, 3]), t+1 or more
+    /// nonces: signer's published (D_i, E_i), same order as parties_index
+    pub fn new(t: usize, Y: &GE, message: &[u8], parties_index: &[usize], nonces: &[(GE, GE)])
+        -> Result<FrostSession, Error> {
+        if parties_index.len() != nonces.len() {
+            return Err(Error::LengthMismatch {
+                name: "nonces", expected: parties_index.len(), actual: nonces.len() });
+        } else if parties_index.len() < t + 1 {
+            return Err(Error::InvalidParams(
+                format!("need t+1={} signers but got {}", t + 1, parties_index.len())));
+        }
+        for (i, index) in parties_index.iter().enumerate() {
+            if parties_index[..i].contains(index) {
+                return Err(Error::InvalidParams(format!("duplicate party index={}", index)));
+            }
+        }
+        let binding = binding_factors(message, parties_index, nonces);
+        // R = sum of D_i + rho_i*E_i
+        let r_tag = {
+            let mut iter = nonces.iter().zip(binding.iter())
+                .map(|((d, e), rho)| d + &e.scalar_mul(&rho.get_element()));
+            let head = iter.next().unwrap();
+            iter.fold(head, |acc, r| acc + r)
+        };
+        let e = frost_challenge(&r_tag, Y, message);
+        Ok(FrostSession {
+            Y: Y.clone(),
+            message: message.to_vec(),
+            parties_index: parties_index.to_vec(),
+            nonces: nonces.to_vec(),
+            binding,
+            r_tag,
+            e,
+        })
+    }
+
+    fn position(&self, index: usize) -> Result<usize, Error> {
+        self.parties_index.iter().position(|i| *i == index)
+            .ok_or(Error::InvalidParams(format!("not found index={} in parties_index", index)))
+    }
+
+    /// z_i = d_i + e_i*rho_i + lambda_i*x_i*c
+    /// share: x_i of keygen_t_n_parties
+    /// nonce is moved and wiped on return
+    pub fn partial_sign(&self, share: &SecretScalar, my_index: usize, nonce: FrostNonce) -> Result<FE, Error> {
+        let position = self.position(my_index)?;
+        if self.nonces[position] != nonce.public {
+            return Err(Error::InvalidParams("your nonce is not found in nonces".to_owned()));
+        }
+        let lambda = lagrange_coefficient(my_index, &self.parties_index);
+        let (d, e) = &nonce.secret;
+        let mut k = e.mul_add_secret(&self.binding[position], d);
+        let z = share.mul_add(&(lambda * self.e.clone()), &k);
+        k.wipe();
+        Ok(z)
+    }
+
+    /// z_i*G == D_i + rho_i*E_i + c*lambda_i*Y_i
+    pub fn verify_share(&self, index: usize, gamma: &FE, public_share: &GE) -> bool {
+        let position = match self.position(index) {
+            Ok(position) => position,
+            Err(_) => return false,
+        };
+        let g: GE = ECPoint::generator();
+        let (d, e) = &self.nonces[position];
+        let lambda = lagrange_coefficient(index, &self.parties_index);
+        let expected = d + &e.scalar_mul(&self.binding[position].get_element())
+            + public_share.scalar_mul(&(lambda * self.e.clone()).get_element());
+        g.scalar_mul(&gamma.get_element()) == expected
+    }
+
+    /// verify all z_i with dealer's vss commitments and return sigma
+    /// error contains failed party index
+    pub fn aggregate(&self, gammas: &[FE], vss_points: &[VerifiableSS]) -> Result<FE, Error> {
+        if gammas.len() != self.parties_index.len() {
+            return Err(Error::LengthMismatch {
+                name: "gammas", expected: self.parties_index.len(), actual: gammas.len() });
+        }
+        let mut failed = Vec::new();
+        for (index, gamma) in self.parties_index.iter().zip(gammas.iter()) {
+            let public = public_share(vss_points, *index)?;
+            if !self.verify_share(*index, gamma, &public) {
+                failed.push(*index);
+            }
+        }
+        if 0 < failed.len() {
+            return Err(Error::InvalidShare { party_index: failed });
+        }
+        let mut iter = gammas.iter();
+        let head = iter.next().unwrap().clone();
+        Ok(iter.fold(head, |acc, z| acc + z))
+    }
+
+    /// R of signature, used as V with SigVersion::Frost
+    pub fn R(&self) -> GE {
+        self.r_tag.clone()
+    }
+}
+
+/// rho_i of each signer, see hashing::frost_binding_factor
+pub fn binding_factors(message: &[u8], parties_index: &[usize], nonces: &[(GE, GE)]) -> Vec<FE> {
+    parties_index.iter()
+        .map(|index| frost_binding_factor(*index, parties_index, nonces, message))
+        .collect()
+}
+
+
+#[cfg(test)]
+mod Test {
+    use crate::error::Error;
+    use crate::frost::*;
+    use crate::hashing::SigVersion;
+    use crate::testutils::keygen;
+    use crate::threshold::*;
+
+    #[test]
+    fn test_frost_2_of_5_with_3_signers() {
+        let (shares, vss_points, Y) = keygen(2, 5);
+
+        let parties_index = vec![0, 2, 4];
+        let nonces: Vec<FrostNonce> = parties_index.iter().map(|_| FrostNonce::new()).collect();
+        let public_nonces: Vec<(GE, GE)> = nonces.iter().map(|n| n.public.clone()).collect();
+        let msg = b"hello frost";
+        let session = FrostSession::new(2, &Y, msg, &parties_index, &public_nonces).unwrap();
+        let gammas: Vec<FE> = parties_index.iter().zip(nonces.into_iter())
+            .map(|(i, nonce)| session.partial_sign(&SecretScalar::new(shares[*i].clone()), *i, nonce).unwrap())
+            .collect();
+        let sigma = session.aggregate(&gammas, &vss_points).unwrap();
+        assert!(verify_threshold_signature(sigma.clone(), &Y, &session.R(), msg, SigVersion::Frost));
+        // FROST challenge is not the threshold challenge
+        assert!(!verify_threshold_signature(sigma, &Y, &session.R(), msg, SigVersion::Tagged));
+
+        // broken share is identified
+        let mut broken = gammas.clone();
+        broken[1] = broken[0].clone();
+        assert_eq!(session.aggregate(&broken, &vss_points).err().unwrap(),
+                   Error::InvalidShare { party_index: vec![2] });
+
+        // t signers can't open a session
+        assert!(FrostSession::new(2, &Y, msg, &parties_index[..2], &public_nonces[..2]).is_err());
+    }
+}
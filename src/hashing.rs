@@ -0,0 +1,171 @@
+use crate::bip340::tagged_hash;
+use crate::error::Error;
+use crate::utils::{bigint2bytes, point2bytes, KeyType};
+use emerald_city::curv::elliptic::curves::secp256_k1::{FE, GE};
+use emerald_city::curv::elliptic::curves::traits::ECScalar;
+use emerald_city::curv::arithmetic::num_bigint::BigInt;
+
+
+/// one tag for one purpose, inputs are fixed length encodings
+/// point: 33bytes compressed, scalar: 32bytes, index: 4bytes, message is always last
+pub const TAG_KEYAGG_LIST: &str = "MultiPartySchnorr/keyagg/list";
+pub const TAG_KEYAGG_COEF: &str = "MultiPartySchnorr/keyagg/coef";
+pub const TAG_NONCE_MUSIG2: &str = "MultiPartySchnorr/nonce/musig2";
+pub const TAG_NONCE_FROST: &str = "MultiPartySchnorr/nonce/frost";
+pub const TAG_CHALLENGE: &str = "MultiPartySchnorr/challenge";
+pub const TAG_CHALLENGE_FROST: &str = "MultiPartySchnorr/challenge/frost";
+pub const TAG_COMMITMENT: &str = "MultiPartySchnorr/commitment";
+pub const TAG_KEYGEN_POK: &str = "MultiPartySchnorr/keygen/pok";
+pub const TAG_TWEAK: &str = "MultiPartySchnorr/tweak";
+pub const TAG_NONCE_SINGLE: &str = "MultiPartySchnorr/single/nonce";
+pub const TAG_NONCE_AUX: &str = "MultiPartySchnorr/single/aux";
+pub const TAG_NONCE_ADAPTOR: &str = "MultiPartySchnorr/adaptor/nonce";
+pub const TAG_BIP32_CHAINCODE: &str = "MultiPartySchnorr/bip32/chaincode";
+
+/// first byte of versioned r, signature hashed by tagged hash
+pub const SIGNATURE_VERSION: u8 = 1;
+/// first byte of versioned R of FROST, threshold key only
+pub const FROST_SIGNATURE_VERSION: u8 = 2;
+
+/// Legacy: HSha256 over BigInt, r is R.x(32b) or V(33b)
+/// Tagged: tagged hash over bytes, r is SIGNATURE_VERSION || R.x or V
+/// Frost: FROST challenge tag, r is FROST_SIGNATURE_VERSION || R(33b)
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum SigVersion {
+    Legacy,
+    Tagged,
+    Frost,
+}
+
+
+pub fn point_bytes(point: &GE) -> [u8;33] {
+    point2bytes(point, KeyType::SingleSig)
+}
+
+pub fn scalar_bytes(scalar: &FE) -> [u8;32] {
+    bigint2bytes(&scalar.to_big_int()).unwrap()
+}
+
+pub fn index_bytes(index: usize) -> [u8;4] {
+    (index as u32).to_be_bytes()
+}
+
+pub fn hash2scalar(hash: &[u8;32]) -> FE {
+    ECScalar::from(&BigInt::from_bytes_be(hash))
+}
+
+/// L = H_list(P_1 || .. || P_n)
+pub fn keyagg_list_hash(pks: &[GE]) -> [u8;32] {
+    let encoded: Vec<[u8;33]> = pks.iter().map(point_bytes).collect();
+    let inputs: Vec<&[u8]> = encoded.iter().map(|p| &p[..]).collect();
+    tagged_hash(TAG_KEYAGG_LIST, &inputs)
+}
+
+/// a_i = H_coef(L || P_i)
+pub fn keyagg_coefficient(list_hash: &[u8;32], pk: &GE) -> FE {
+    hash2scalar(&tagged_hash(TAG_KEYAGG_COEF, &[list_hash, &point_bytes(pk)]))
+}
+
+/// b = H_nonce(R1 || R2 || apk || message)
+pub fn musig2_nonce_coefficient(r1: &GE, r2: &GE, apk: &GE, message: &[u8]) -> FE {
+    hash2scalar(&tagged_hash(TAG_NONCE_MUSIG2, &[
+        &point_bytes(r1), &point_bytes(r2), &point_bytes(apk), message]))
+}
+
+/// rho_i = H_nonce(i || n || B || message), B = list of (j || D_j || E_j)
+pub fn frost_binding_factor(index: usize, parties_index: &[usize], nonces: &[(GE, GE)], message: &[u8]) -> FE {
+    let mut encoded = Vec::with_capacity(8 + parties_index.len() * 70 + message.len());
+    encoded.extend_from_slice(&index_bytes(index));
+    encoded.extend_from_slice(&index_bytes(parties_index.len()));
+    for (j, (d, e)) in parties_index.iter().zip(nonces.iter()) {
+        encoded.extend_from_slice(&index_bytes(*j));
+        encoded.extend_from_slice(&point_bytes(d));
+        encoded.extend_from_slice(&point_bytes(e));
+    }
+    encoded.extend_from_slice(message);
+    hash2scalar(&tagged_hash(TAG_NONCE_FROST, &[&encoded]))
+}
+
+/// e = H_challenge(r || P || message)
+/// r: R.x(32b) of single and aggregate, V(33b) of threshold
+/// P: public key with key type prefix (0x02, 0x05 or 0x08)
+pub fn challenge(r: &[u8], public: &GE, key_type: KeyType, message: &[u8]) -> FE {
+    hash2scalar(&tagged_hash(TAG_CHALLENGE, &[r, &point2bytes(public, key_type), message]))
+}
+
+/// c = H_frost(R || Y || message), Y with threshold prefix 0x08
+pub fn frost_challenge(r: &GE, Y: &GE, message: &[u8]) -> FE {
+    hash2scalar(&tagged_hash(TAG_CHALLENGE_FROST, &[
+        &point_bytes(r), &point2bytes(Y, KeyType::ThresholdSig), message]))
+}
+
+/// c = H_pok(index || A_i0 || R || context), context is last like message
+pub fn keygen_pok_challenge(index: usize, public: &GE, r: &GE, context: &[u8]) -> FE {
+    hash2scalar(&tagged_hash(TAG_KEYGEN_POK, &[
+        &index_bytes(index), &point_bytes(public), &point_bytes(r), context]))
+}
+
+/// t = H_tweak(P || commitment), P with key type prefix like challenge
+pub fn tweak_scalar(public: &GE, key_type: KeyType, commitment: &[u8]) -> FE {
+    hash2scalar(&tagged_hash(TAG_TWEAK, &[&point2bytes(public, key_type), commitment]))
+}
+
+/// com = H_commitment(R || blind_factor)
+pub fn commitment(point: &GE, blind_factor: &BigInt) -> Result<BigInt, Error> {
+    let hash = tagged_hash(TAG_COMMITMENT, &[&point_bytes(point), &bigint2bytes(blind_factor)?]);
+    Ok(BigInt::from_bytes_be(&hash))
+}
+
+/// add SIGNATURE_VERSION to r
+pub fn encode_r(r: &[u8]) -> Vec<u8> {
+    [&[SIGNATURE_VERSION][..], r].concat()
+}
+
+/// add FROST_SIGNATURE_VERSION to R
+pub fn encode_frost_r(r: &[u8;33]) -> Vec<u8> {
+    [&[FROST_SIGNATURE_VERSION][..], r].concat()
+}
+
+/// r of legacy_len is Legacy, SIGNATURE_VERSION || r is Tagged
+/// FROST_SIGNATURE_VERSION || R is Frost, only for 33bytes V
+pub fn decode_r(r: &[u8], legacy_len: usize) -> Result<(SigVersion, &[u8]), Error> {
+    if r.len() == legacy_len {
+        Ok((SigVersion::Legacy, r))
+    } else if r.len() == legacy_len + 1 && r[0] == SIGNATURE_VERSION {
+        Ok((SigVersion::Tagged, &r[1..]))
+    } else if r.len() == 34 && legacy_len == 33 && r[0] == FROST_SIGNATURE_VERSION {
+        Ok((SigVersion::Frost, &r[1..]))
+    } else if r.len() == legacy_len + 1 {
+        Err(Error::InvalidSignature("unknown signature version"))
+    } else {
+        Err(Error::LengthMismatch { name: "r", expected: legacy_len + 1, actual: r.len() })
+    }
+}
+
+
+#[cfg(test)]
+mod Test {
+    use crate::hashing::*;
+    use crate::keypair::KeyPair;
+
+    #[test]
+    fn test_versioned_r() {
+        let r = [7u8;32];
+        let encoded = encode_r(&r);
+        assert_eq!(decode_r(&encoded, 32).unwrap(), (SigVersion::Tagged, &r[..]));
+        assert_eq!(decode_r(&r, 32).unwrap(), (SigVersion::Legacy, &r[..]));
+        assert!(decode_r(&[0u8;33], 32).is_err());
+        let v = [3u8;33];
+        assert_eq!(decode_r(&encode_frost_r(&v), 33).unwrap(), (SigVersion::Frost, &v[..]));
+        assert!(decode_r(&[FROST_SIGNATURE_VERSION;33], 32).is_err());
+        assert!(decode_r(&[1u8;31], 32).is_err());
+    }
+
+    #[test]
+    fn test_challenge_bound_to_key_type() {
+        let public = KeyPair::new().public;
+        let single = challenge(&[1u8;32], &public, KeyType::SingleSig, b"msg");
+        let aggregate = challenge(&[1u8;32], &public, KeyType::AggregateSig, b"msg");
+        assert!(single != aggregate);
+    }
+}
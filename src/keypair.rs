@@ -0,0 +1,148 @@
+use crate::adaptor::{adaptor_challenge, PreSignature};
+use crate::bip340::{negate_scalar, normalize_even_y, tagged_hash};
+use crate::error::Error;
+use crate::hashing::{challenge, point_bytes, SigVersion, TAG_NONCE_ADAPTOR, TAG_NONCE_AUX, TAG_NONCE_SINGLE};
+use crate::secret::{SecretScalar, Wipe};
+use crate::tweak::tweak_public_key;
+use crate::utils::{bigint2bytes, point2bytes, KeyType};
+use emerald_city::curv::elliptic::curves::secp256_k1::{FE, GE};
+use emerald_city::curv::elliptic::curves::traits::{ECPoint, ECScalar};
+use emerald_city::curv::arithmetic::num_bigint::BigInt;
+
+
+pub struct KeyPair {
+    pub secret: SecretScalar,
+    pub public: GE,
+}
+
+impl Clone for KeyPair {
+    /// secret is copied into new locked memory
+    fn clone(&self) -> Self {
+        KeyPair {secret: self.secret.clone(), public: self.public.clone()}
+    }
+}
+
+impl KeyPair {
+    /// generate random keypair
+    pub fn new() -> Self {
+        let ec_point: GE = ECPoint::generator();
+        let secret: FE = ECScalar::new_random();
+        let public: GE = ec_point.scalar_mul(&secret.get_element());
+        KeyPair {secret: SecretScalar::new(secret), public}
+    }
+
+    /// generate keypair from secret key
+    pub fn from_secret_key(secret: &[u8]) -> Self {
+        let ec_point: GE = ECPoint::generator();
+        let secret: FE = ECScalar::from(&BigInt::from_bytes_be(secret));
+        let public: GE = ec_point.scalar_mul(&secret.get_element());
+        KeyPair {secret: SecretScalar::new(secret), public}
+    }
+
+    /// hedged deterministic nonce like BIP-340
+    /// k = H_nonce(bytes(x) xor H_aux(aux_rand) || P || message)
+    /// same aux_rand reproduces same nonce, random aux_rand protects from fault attacks
+    pub fn single_nonce(&self, message: &[u8], aux_rand: &[u8;32]) -> Result<FE, Error> {
+        self.hedged_nonce(TAG_NONCE_SINGLE, &[message], aux_rand)
+    }
+
+    /// hedged nonce of tag, inputs are hashed after bytes(x) xor H_aux(aux_rand) || P
+    fn hedged_nonce(&self, tag: &str, inputs: &[&[u8]], aux_rand: &[u8;32]) -> Result<FE, Error> {
+        let mut t = self.secret.to_bytes();
+        let aux_hash = tagged_hash(TAG_NONCE_AUX, &[aux_rand]);
+        for (t_i, a_i) in t.iter_mut().zip(aux_hash.iter()) {
+            *t_i ^= a_i;
+        }
+        let public = point2bytes(&self.public, KeyType::SingleSig);
+        let mut hash_inputs: Vec<&[u8]> = vec![&t[..], &public[..]];
+        hash_inputs.extend_from_slice(inputs);
+        let rand = tagged_hash(tag, &hash_inputs);
+        for t_i in t.iter_mut() {
+            *t_i = 0;
+        }
+        let k: FE = ECScalar::from(&BigInt::from_bytes_be(&rand));
+        if k == FE::zero() {
+            return Err(Error::InvalidScalar("nonce k is zero".to_owned()));
+        }
+        Ok(k)
+    }
+
+    /// get signature from single signer
+    /// aux_rand: 32bytes fresh randomness, fixed value only for test vectors
+    /// return R.x and s
+    pub fn single_sign(&self, message: &[u8], aux_rand: &[u8;32]) -> Result<(BigInt, BigInt), Error> {
+        let base_point: GE = ECPoint::generator();
+        let ephemeral_private_key = self.single_nonce(message, aux_rand)?;
+        let ephemeral_public_key = base_point.scalar_mul(&ephemeral_private_key.get_element());
+        // R of even Y, verifier can lift R.x for batch verification
+        let (ephemeral_private_key, ephemeral_public_key) =
+            normalize_even_y(&ephemeral_private_key, &ephemeral_public_key);
+        // compute c = H_challenge(R.x || P || message)
+        let R = ephemeral_public_key.x_coor().unwrap();
+        let c_fe = challenge(&bigint2bytes(&R)?, &self.public, KeyType::SingleSig, message);
+        // sign
+        let s_fe = self.secret.mul_add(&c_fe, &ephemeral_private_key);
+        Ok((R, s_fe.to_big_int()))
+    }
+
+    /// adaptor pre-signature of adaptor point T, nonce of final signature is R+T
+    /// k is hashed with T by other tag, never same as nonce of single_sign
+    /// k is negated when R+T has odd Y, see PreSignature
+    pub fn single_pre_sign(&self, T: &GE, message: &[u8], aux_rand: &[u8;32]) -> Result<PreSignature, Error> {
+        let base_point: GE = ECPoint::generator();
+        let mut k = self.hedged_nonce(TAG_NONCE_ADAPTOR, &[&point_bytes(T), message], aux_rand)?;
+        let R = base_point.scalar_mul(&k.get_element());
+        let mut pre_sig = PreSignature { R, T: T.clone(), s: FE::zero() };
+        if pre_sig.is_negated() {
+            let negated = negate_scalar(&k);
+            k.wipe();
+            k = negated;
+        }
+        let c = adaptor_challenge(&pre_sig.r_hat(), &self.public, message, false, SigVersion::Tagged);
+        pre_sig.s = self.secret.mul_add(&c, &k);
+        k.wipe();
+        Ok(pre_sig)
+    }
+
+    /// Taproot-style tweaked keypair, secret is x + t and public is Q = P + t*G
+    pub fn tweak(&self, commitment: &[u8]) -> Result<KeyPair, Error> {
+        let (public, t) = tweak_public_key(&self.public, KeyType::SingleSig, commitment)?;
+        let secret = SecretScalar::new(self.secret.add(&t));
+        Ok(KeyPair {secret, public})
+    }
+
+    /// overwrite secret by zero, clones keep their own copy of secret
+    pub fn wipe(&mut self) {
+        self.secret.wipe();
+    }
+
+    /// get shared point by multiple with public key
+    /// note: do not forget to pass through a hash function
+    pub fn shared_point(&self, public: &GE) -> GE {
+        self.secret.scalar_mul(public)
+    }
+}
+
+
+#[cfg(test)]
+mod Test {
+    use crate::aggregate::verify_aggregate_signature;
+    use crate::hashing::SigVersion;
+    use crate::keypair::KeyPair;
+
+    #[test]
+    fn test_single_sign_hedged_nonce() {
+        let keypair = KeyPair::new();
+        let aux_rand = [7u8;32];
+        let (r1, s1) = keypair.single_sign(b"abc", &aux_rand).unwrap();
+        let (r2, s2) = keypair.single_sign(b"abc", &aux_rand).unwrap();
+        assert!(r1 == r2 && s1 == s2);
+        assert!(verify_aggregate_signature(&s1, &r1, &keypair.public, b"abc", false, SigVersion::Tagged).is_ok());
+        // leading zero bytes are not dropped
+        let (r3, _) = keypair.single_sign(b"\x00abc", &aux_rand).unwrap();
+        assert!(r1 != r3);
+        // other aux_rand is other nonce
+        let (r4, _) = keypair.single_sign(b"abc", &[8u8;32]).unwrap();
+        assert!(r1 != r4);
+    }
+}
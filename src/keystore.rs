@@ -0,0 +1,607 @@
+use crate::aggregate::{key_aggregation_n, key_aggregation_coefficients, verify_aggregate_signature};
+use crate::bip340::CURVE_ORDER;
+use crate::error::Error;
+use crate::hashing::SigVersion;
+use crate::keypair::KeyPair;
+use crate::secret::SecretScalar;
+use crate::threshold::{public_share, sum_public_points};
+use crate::utils::{bigint2bytes, bytes2point_inner, decode_public_bytes, point2bytes, KeyType};
+use emerald_city::curv::cryptographic_primitives::secret_sharing::feldman_vss::{
+    VerifiableSS, ShamirSecretSharing,
+};
+use emerald_city::curv::elliptic::curves::secp256_k1::{FE, GE};
+use emerald_city::curv::elliptic::curves::traits::{ECPoint, ECScalar};
+use emerald_city::curv::arithmetic::num_bigint::BigInt;
+use serde_json::Value;
+
+/// format version, first byte of binary and "version" of json
+pub const KEYSTORE_VERSION: u8 = 1;
+
+const KIND_SINGLE: u8 = 1;
+const KIND_AGGREGATE: u8 = 2;
+const KIND_THRESHOLD: u8 = 3;
+const KIND_DESCRIPTOR: u8 = 4;
+
+
+/// n of n signer's key and all signer's public keys
+pub struct AggregateKey {
+    pub keypair: KeyPair,
+    pub signers: Vec<GE>,
+    pub apk: GE,
+}
+
+impl AggregateKey {
+    pub fn new(keypair: &KeyPair, signers: &[GE]) -> Result<AggregateKey, Error> {
+        let (apk, _) = key_aggregation_n(signers, &keypair.public)?;
+        Ok(AggregateKey { keypair: keypair.clone(), signers: signers.to_vec(), apk })
+    }
+}
+
+/// n of n key described by public keys only, for auditors and verifiers
+/// coefficients are same order as signers
+pub struct AggregateKeyDescriptor {
+    pub signers: Vec<GE>,
+    pub coefficients: Vec<FE>,
+    pub apk: GE,
+}
+
+impl AggregateKeyDescriptor {
+    pub fn new(signers: &[GE]) -> Result<AggregateKeyDescriptor, Error> {
+        let (apk, coefficients) = key_aggregation_coefficients(signers)?;
+        Ok(AggregateKeyDescriptor { signers: signers.to_vec(), coefficients, apk })
+    }
+
+    /// 1 of 1 is SingleSig, others are AggregateSig
+    pub fn key_type(&self) -> KeyType {
+        if 1 < self.signers.len() { KeyType::AggregateSig } else { KeyType::SingleSig }
+    }
+
+    /// apk with 0x05 0x06 prefix (0x02 0x03 if 1 of 1)
+    pub fn apk_bytes(&self) -> [u8;33] {
+        point2bytes(&self.apk, self.key_type())
+    }
+
+    /// coefficient of public, None if not a signer
+    pub fn coefficient(&self, public: &GE) -> Option<&FE> {
+        self.signers.iter().position(|p| p == public).map(|i| &self.coefficients[i])
+    }
+
+    /// check published apk (with prefix) is made from these signers
+    pub fn check_apk(&self, apk: &[u8]) -> Result<(), Error> {
+        let (key_type, _) = decode_public_bytes(apk)?;
+        if key_type != self.key_type() || bytes2point_inner(apk)? != self.apk {
+            return Err(Error::InvalidParams("apk do not match with signers".to_owned()));
+        }
+        Ok(())
+    }
+
+    /// verify signature (s, R.x) made by exactly these signers
+    pub fn verify(&self, signature: &BigInt, r_x: &BigInt, message: &[u8], version: SigVersion) -> Result<(), Error> {
+        let is_musig = self.key_type() == KeyType::AggregateSig;
+        verify_aggregate_signature(signature, r_x, &self.apk, message, is_musig, version)
+    }
+}
+
+/// my result of keygen_t_n_parties
+/// parties_index and my_index are same as user input (start from 0)
+pub struct ThresholdShare {
+    pub t: usize,
+    pub n: usize,
+    pub my_index: usize,
+    pub parties_index: Vec<usize>,
+    pub share: SecretScalar,
+    pub Y: GE,
+    pub vss_points: Vec<VerifiableSS>,
+}
+
+impl ThresholdShare {
+    /// stored data is consistent with each other
+    /// Y is sum of dealer's constant terms and x_i*G is my public share
+    pub fn check_consistency(&self) -> Result<(), Error> {
+        if self.t >= self.n {
+            return Err(Error::InvalidParams("require \"t < n\"".to_owned()));
+        } else if self.vss_points.len() < 1 || self.n < self.vss_points.len() {
+            // less than n when generated by QUAL dealers or refreshed
+            return Err(Error::LengthMismatch {
+                name: "vss_points", expected: self.n, actual: self.vss_points.len() });
+        } else if self.parties_index.len() != self.n {
+            return Err(Error::LengthMismatch {
+                name: "parties_index", expected: self.n, actual: self.parties_index.len() });
+        } else if self.n <= self.my_index {
+            return Err(Error::InvalidParams(format!("my_index={} is out of range", self.my_index)));
+        }
+        for (dealer, vss) in self.vss_points.iter().enumerate() {
+            if vss.commitments.len() != self.t + 1 {
+                return Err(Error::VssValidation { party_index: dealer });
+            }
+        }
+        let constants: Vec<GE> = self.vss_points.iter()
+            .map(|vss| vss.commitments[0].clone())
+            .collect();
+        if sum_public_points(&constants)? != self.Y {
+            return Err(Error::InvalidParams("group key do not match with vss commitments".to_owned()));
+        }
+        let g: GE = ECPoint::generator();
+        let position = self.parties_index[self.my_index];
+        if self.share.scalar_mul(&g) != public_share(&self.vss_points, position)? {
+            return Err(Error::VssValidation { party_index: self.my_index });
+        }
+        Ok(())
+    }
+}
+
+
+/// binary: version(1) | kind(1) | secret(32)
+pub fn single_to_bytes(keypair: &KeyPair) -> Vec<u8> {
+    let mut writer = Writer::new(KIND_SINGLE);
+    writer.secret(&keypair.secret);
+    writer.finish()
+}
+
+pub fn single_from_bytes(bytes: &[u8]) -> Result<KeyPair, Error> {
+    let mut reader = Reader::new(bytes, KIND_SINGLE)?;
+    let secret = reader.scalar()?;
+    reader.finish()?;
+    Ok(keypair_from_scalar(&secret))
+}
+
+/// binary: version(1) | kind(1) | secret(32) | apk(33) | len(4) | signers(33*len)
+pub fn aggregate_to_bytes(key: &AggregateKey) -> Vec<u8> {
+    let mut writer = Writer::new(KIND_AGGREGATE);
+    writer.secret(&key.keypair.secret);
+    writer.point(&key.apk);
+    writer.length(key.signers.len());
+    for signer in key.signers.iter() {
+        writer.point(signer);
+    }
+    writer.finish()
+}
+
+pub fn aggregate_from_bytes(bytes: &[u8]) -> Result<AggregateKey, Error> {
+    let mut reader = Reader::new(bytes, KIND_AGGREGATE)?;
+    let secret = reader.scalar()?;
+    let apk = reader.point()?;
+    let len = reader.count(33)?;
+    let mut signers = Vec::with_capacity(len);
+    for _ in 0..len {
+        signers.push(reader.point()?);
+    }
+    reader.finish()?;
+    check_aggregate(&keypair_from_scalar(&secret), &signers, &apk)
+}
+
+/// binary: version(1) | kind(1) | apk(33) | len(4) | signers(33*len)
+pub fn descriptor_to_bytes(key: &AggregateKeyDescriptor) -> Vec<u8> {
+    let mut writer = Writer::new(KIND_DESCRIPTOR);
+    writer.point(&key.apk);
+    writer.length(key.signers.len());
+    for signer in key.signers.iter() {
+        writer.point(signer);
+    }
+    writer.finish()
+}
+
+pub fn descriptor_from_bytes(bytes: &[u8]) -> Result<AggregateKeyDescriptor, Error> {
+    let mut reader = Reader::new(bytes, KIND_DESCRIPTOR)?;
+    let apk = reader.point()?;
+    let len = reader.count(33)?;
+    let mut signers = Vec::with_capacity(len);
+    for _ in 0..len {
+        signers.push(reader.point()?);
+    }
+    reader.finish()?;
+    check_descriptor(&signers, &apk)
+}
+
+/// binary: version(1) | kind(1) | t(4) | n(4) | my_index(4) | parties_index(4*n)
+///   | share(32) | Y(33) | len(4) | vss_points(33*(t+1)*len)
+/// len is number of QUAL dealers, n or less
+pub fn threshold_to_bytes(key: &ThresholdShare) -> Vec<u8> {
+    let mut writer = Writer::new(KIND_THRESHOLD);
+    writer.length(key.t);
+    writer.length(key.n);
+    writer.length(key.my_index);
+    for index in key.parties_index.iter() {
+        writer.length(*index);
+    }
+    writer.secret(&key.share);
+    writer.point(&key.Y);
+    writer.length(key.vss_points.len());
+    for vss in key.vss_points.iter() {
+        for point in vss.commitments.iter() {
+            writer.point(point);
+        }
+    }
+    writer.finish()
+}
+
+pub fn threshold_from_bytes(bytes: &[u8]) -> Result<ThresholdShare, Error> {
+    let mut reader = Reader::new(bytes, KIND_THRESHOLD)?;
+    let t = reader.length()?;
+    let n = reader.length()?;
+    if t >= n {
+        return Err(Error::InvalidParams("require \"t < n\"".to_owned()));
+    }
+    let my_index = reader.length()?;
+    reader.check_left(n, 4)?;
+    let mut parties_index = Vec::with_capacity(n);
+    for _ in 0..n {
+        parties_index.push(reader.length()?);
+    }
+    let share = SecretScalar::new(reader.scalar()?);
+    let Y = reader.point()?;
+    let len = reader.length()?;
+    if len < 1 || n < len {
+        return Err(decode_error("vss_points"));
+    }
+    reader.check_left(len, 33 * (t + 1))?;
+    let mut vss_points = Vec::with_capacity(len);
+    for _ in 0..len {
+        let mut commitments = Vec::with_capacity(t + 1);
+        for _ in 0..(t + 1) {
+            commitments.push(reader.point()?);
+        }
+        vss_points.push(make_vss(t, n, commitments));
+    }
+    reader.finish()?;
+    let key = ThresholdShare { t, n, my_index, parties_index, share, Y, vss_points };
+    key.check_consistency()?;
+    Ok(key)
+}
+
+
+pub fn single_to_json(keypair: &KeyPair) -> String {
+    json!({
+        "version": KEYSTORE_VERSION,
+        "type": "single",
+        "secret": secret2hex(&keypair.secret),
+    }).to_string()
+}
+
+pub fn single_from_json(data: &str) -> Result<KeyPair, Error> {
+    let value = parse_json(data, "single")?;
+    let secret = hex2scalar(json_str(&value, "secret")?)?;
+    Ok(keypair_from_scalar(&secret))
+}
+
+pub fn aggregate_to_json(key: &AggregateKey) -> String {
+    json!({
+        "version": KEYSTORE_VERSION,
+        "type": "aggregate",
+        "secret": secret2hex(&key.keypair.secret),
+        "apk": hex::encode(&point2bytes(&key.apk, KeyType::AggregateSig)[..]),
+        "signers": key.signers.iter()
+            .map(|p| hex::encode(&point2bytes(p, KeyType::SingleSig)[..]))
+            .collect::<Vec<String>>(),
+    }).to_string()
+}
+
+pub fn aggregate_from_json(data: &str) -> Result<AggregateKey, Error> {
+    let value = parse_json(data, "aggregate")?;
+    let secret = hex2scalar(json_str(&value, "secret")?)?;
+    let apk = hex2point(json_str(&value, "apk")?)?;
+    let mut signers = Vec::new();
+    for signer in json_array(&value, "signers")? {
+        signers.push(hex2point(signer.as_str().ok_or(decode_error("signers"))?)?);
+    }
+    check_aggregate(&keypair_from_scalar(&secret), &signers, &apk)
+}
+
+pub fn descriptor_to_json(key: &AggregateKeyDescriptor) -> String {
+    json!({
+        "version": KEYSTORE_VERSION,
+        "type": "descriptor",
+        "apk": hex::encode(&key.apk_bytes()[..]),
+        "signers": key.signers.iter()
+            .map(|p| hex::encode(&point2bytes(p, KeyType::SingleSig)[..]))
+            .collect::<Vec<String>>(),
+        "coefficients": key.coefficients.iter()
+            .map(scalar2hex)
+            .collect::<Vec<String>>(),
+    }).to_string()
+}
+
+/// coefficients are recomputed from signers, stored ones are only for reading
+pub fn descriptor_from_json(data: &str) -> Result<AggregateKeyDescriptor, Error> {
+    let value = parse_json(data, "descriptor")?;
+    let apk = hex2point(json_str(&value, "apk")?)?;
+    let mut signers = Vec::new();
+    for signer in json_array(&value, "signers")? {
+        signers.push(hex2point(signer.as_str().ok_or(decode_error("signers"))?)?);
+    }
+    check_descriptor(&signers, &apk)
+}
+
+pub fn threshold_to_json(key: &ThresholdShare) -> String {
+    json!({
+        "version": KEYSTORE_VERSION,
+        "type": "threshold",
+        "t": key.t,
+        "n": key.n,
+        "my_index": key.my_index,
+        "parties_index": key.parties_index,
+        "share": secret2hex(&key.share),
+        "Y": hex::encode(&point2bytes(&key.Y, KeyType::ThresholdSig)[..]),
+        "vss_points": key.vss_points.iter()
+            .map(|vss| vss.commitments.iter()
+                .map(|p| hex::encode(&point2bytes(p, KeyType::SingleSig)[..]))
+                .collect::<Vec<String>>())
+            .collect::<Vec<Vec<String>>>(),
+    }).to_string()
+}
+
+pub fn threshold_from_json(data: &str) -> Result<ThresholdShare, Error> {
+    let value = parse_json(data, "threshold")?;
+    let t = json_usize(&value, "t")?;
+    let n = json_usize(&value, "n")?;
+    let my_index = json_usize(&value, "my_index")?;
+    let mut parties_index = Vec::new();
+    for index in json_array(&value, "parties_index")? {
+        parties_index.push(index.as_u64().ok_or(decode_error("parties_index"))? as usize);
+    }
+    let share = SecretScalar::new(hex2scalar(json_str(&value, "share")?)?);
+    let Y = hex2point(json_str(&value, "Y")?)?;
+    let mut vss_points = Vec::new();
+    for vss in json_array(&value, "vss_points")? {
+        let mut commitments = Vec::new();
+        for point in vss.as_array().ok_or(decode_error("vss_points"))? {
+            commitments.push(hex2point(point.as_str().ok_or(decode_error("vss_points"))?)?);
+        }
+        vss_points.push(make_vss(t, n, commitments));
+    }
+    let key = ThresholdShare { t, n, my_index, parties_index, share, Y, vss_points };
+    key.check_consistency()?;
+    Ok(key)
+}
+
+
+fn decode_error(reason: &str) -> Error {
+    Error::Decode(format!("broken {}", reason))
+}
+
+fn keypair_from_scalar(secret: &FE) -> KeyPair {
+    let g: GE = ECPoint::generator();
+    KeyPair { secret: SecretScalar::new(secret.clone()), public: g.scalar_mul(&secret.get_element()) }
+}
+
+fn check_aggregate(keypair: &KeyPair, signers: &[GE], apk: &GE) -> Result<AggregateKey, Error> {
+    let key = AggregateKey::new(keypair, signers)?;
+    if &key.apk != apk {
+        return Err(Error::InvalidParams("apk do not match with signers".to_owned()));
+    }
+    Ok(key)
+}
+
+fn check_descriptor(signers: &[GE], apk: &GE) -> Result<AggregateKeyDescriptor, Error> {
+    let key = AggregateKeyDescriptor::new(signers)?;
+    if &key.apk != apk {
+        return Err(Error::InvalidParams("apk do not match with signers".to_owned()));
+    }
+    Ok(key)
+}
+
+fn make_vss(t: usize, n: usize, commitments: Vec<GE>) -> VerifiableSS {
+    VerifiableSS {
+        parameters: ShamirSecretSharing { threshold: t, share_count: n },
+        commitments,
+    }
+}
+
+/// 32bytes big endian, zero or not less than curve order is rejected
+fn bytes2scalar(bytes: &[u8]) -> Result<FE, Error> {
+    if bytes.len() != 32 {
+        return Err(Error::LengthMismatch { name: "scalar", expected: 32, actual: bytes.len() });
+    } else if CURVE_ORDER[..] <= *bytes || bytes.iter().all(|b| *b == 0) {
+        return Err(Error::InvalidScalar("out of curve order".to_owned()));
+    }
+    Ok(ECScalar::from(&BigInt::from_bytes_be(bytes)))
+}
+
+fn scalar2hex(scalar: &FE) -> String {
+    hex::encode(&bigint2bytes(&scalar.to_big_int()).unwrap())
+}
+
+fn secret2hex(secret: &SecretScalar) -> String {
+    let mut bytes = secret.to_bytes();
+    let encoded = hex::encode(&bytes);
+    for b in bytes.iter_mut() {
+        *b = 0;
+    }
+    encoded
+}
+
+fn hex2scalar(data: &str) -> Result<FE, Error> {
+    bytes2scalar(&hex::decode(data).map_err(|_| decode_error("hex"))?)
+}
+
+fn hex2point(data: &str) -> Result<GE, Error> {
+    bytes2point_inner(&hex::decode(data).map_err(|_| decode_error("hex"))?)
+}
+
+fn parse_json(data: &str, kind: &str) -> Result<Value, Error> {
+    let value: Value = serde_json::from_str(data).map_err(|err| Error::Decode(err.to_string()))?;
+    match value.get("version").and_then(|v| v.as_u64()) {
+        Some(version) if version == KEYSTORE_VERSION as u64 => (),
+        _ => return Err(Error::Decode("unsupported version".to_owned())),
+    }
+    if json_str(&value, "type")? != kind {
+        return Err(Error::Decode(format!("type is not {}", kind)));
+    }
+    Ok(value)
+}
+
+fn json_str<'a>(value: &'a Value, name: &str) -> Result<&'a str, Error> {
+    value.get(name).and_then(|v| v.as_str()).ok_or(decode_error(name))
+}
+
+fn json_usize(value: &Value, name: &str) -> Result<usize, Error> {
+    value.get(name).and_then(|v| v.as_u64()).map(|v| v as usize).ok_or(decode_error(name))
+}
+
+fn json_array<'a>(value: &'a Value, name: &str) -> Result<&'a Vec<Value>, Error> {
+    value.get(name).and_then(|v| v.as_array()).ok_or(decode_error(name))
+}
+
+
+struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    fn new(kind: u8) -> Writer {
+        Writer { bytes: vec![KEYSTORE_VERSION, kind] }
+    }
+
+    fn length(&mut self, len: usize) {
+        self.bytes.extend_from_slice(&(len as u32).to_be_bytes());
+    }
+
+    fn scalar(&mut self, scalar: &FE) {
+        self.bytes.extend_from_slice(&bigint2bytes(&scalar.to_big_int()).unwrap());
+    }
+
+    fn secret(&mut self, secret: &SecretScalar) {
+        let mut bytes = secret.to_bytes();
+        self.bytes.extend_from_slice(&bytes);
+        for b in bytes.iter_mut() {
+            *b = 0;
+        }
+    }
+
+    fn point(&mut self, point: &GE) {
+        self.bytes.extend_from_slice(&point2bytes(point, KeyType::SingleSig));
+    }
+
+    fn finish(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8], kind: u8) -> Result<Reader<'a>, Error> {
+        match bytes.get(0..2) {
+            Some(&[version, _]) if version != KEYSTORE_VERSION =>
+                Err(Error::Decode(format!("unsupported version={}", version))),
+            Some(&[_, stored]) if stored != kind =>
+                Err(Error::Decode(format!("unexpected key kind={}", stored))),
+            Some(_) => Ok(Reader { bytes: &bytes[2..] }),
+            None => Err(decode_error("header")),
+        }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if self.bytes.len() < len {
+            return Err(Error::Decode("unexpected end of data".to_owned()));
+        }
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn length(&mut self) -> Result<usize, Error> {
+        let mut buf = [0u8;4];
+        buf.copy_from_slice(self.take(4)?);
+        Ok(u32::from_be_bytes(buf) as usize)
+    }
+
+    /// length of following entries, error if they are longer than left data
+    fn count(&mut self, entry_size: usize) -> Result<usize, Error> {
+        let len = self.length()?;
+        self.check_left(len, entry_size)?;
+        Ok(len)
+    }
+
+    /// check len entries of entry_size are left before allocation
+    fn check_left(&self, len: usize, entry_size: usize) -> Result<(), Error> {
+        match len.checked_mul(entry_size) {
+            Some(size) if size <= self.bytes.len() => Ok(()),
+            _ => Err(Error::Decode("length is longer than data".to_owned())),
+        }
+    }
+
+    fn scalar(&mut self) -> Result<FE, Error> {
+        bytes2scalar(self.take(32)?)
+    }
+
+    fn point(&mut self) -> Result<GE, Error> {
+        bytes2point_inner(self.take(33)?)
+    }
+
+    fn finish(self) -> Result<(), Error> {
+        if self.bytes.len() != 0 {
+            return Err(Error::Decode("unexpected trailing data".to_owned()));
+        }
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod Test {
+    use crate::keystore::*;
+    use crate::testutils::keygen;
+
+    #[test]
+    fn test_threshold_share_roundtrip() {
+        let (t, n) = (1, 3);
+        let (shares, vss_points, Y) = keygen(t, n);
+        let key = ThresholdShare {
+            t, n, my_index: 1, parties_index: vec![0, 1, 2], share: SecretScalar::new(shares[1].clone()), Y, vss_points,
+        };
+        key.check_consistency().unwrap();
+
+        let restored = threshold_from_bytes(&threshold_to_bytes(&key)).unwrap();
+        assert!(restored.share.with(|x| x == &shares[1]) && restored.Y == key.Y);
+        let restored = threshold_from_json(&threshold_to_json(&key)).unwrap();
+        assert!(restored.share.with(|x| x == &shares[1]) && restored.Y == key.Y);
+
+        // commitments do not match with group key
+        let mut bytes = threshold_to_bytes(&key);
+        let len = bytes.len();
+        bytes[len - 33..].copy_from_slice(&point2bytes(&KeyPair::new().public, KeyType::SingleSig));
+        assert!(threshold_from_bytes(&bytes).is_err());
+        // unknown version
+        let mut bytes = threshold_to_bytes(&key);
+        bytes[0] = 0;
+        assert!(threshold_from_bytes(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_descriptor_matches_signer_key() {
+        let pairs: Vec<KeyPair> = (0..3).map(|_| KeyPair::new()).collect();
+        let signers: Vec<GE> = pairs.iter().map(|p| p.public.clone()).collect();
+        let key = AggregateKey::new(&pairs[2], &signers).unwrap();
+        let descriptor = AggregateKeyDescriptor::new(&signers).unwrap();
+        assert!(descriptor.apk == key.apk);
+        assert!(descriptor.check_apk(&point2bytes(&key.apk, KeyType::AggregateSig)).is_ok());
+        assert!(descriptor.check_apk(&point2bytes(&key.apk, KeyType::SingleSig)).is_err());
+
+        let restored = descriptor_from_bytes(&descriptor_to_bytes(&descriptor)).unwrap();
+        assert!(restored.apk == descriptor.apk && restored.coefficients == descriptor.coefficients);
+        let restored = descriptor_from_json(&descriptor_to_json(&descriptor)).unwrap();
+        assert!(restored.apk == descriptor.apk);
+        // another signer set
+        let other = AggregateKeyDescriptor::new(&signers[..2]).unwrap();
+        assert!(other.check_apk(&descriptor.apk_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_broken_length_is_decode_error() {
+        let is_decode_error = |err: Option<Error>| match err { Some(Error::Decode(_)) => true, _ => false };
+        let signers: Vec<GE> = (0..3).map(|_| KeyPair::new().public).collect();
+        let bytes = descriptor_to_bytes(&AggregateKeyDescriptor::new(&signers).unwrap());
+        // version(1) | kind(1) | apk(33) | len(4)
+        let mut oversized = bytes.clone();
+        oversized[35..39].copy_from_slice(&[0xff;4]);
+        assert!(is_decode_error(descriptor_from_bytes(&oversized).err()));
+        assert!(is_decode_error(descriptor_from_bytes(&bytes[..bytes.len() - 1]).err()));
+        // t=0, n=2^32-1 without parties_index
+        let mut threshold = vec![KEYSTORE_VERSION, KIND_THRESHOLD];
+        threshold.extend_from_slice(&[0, 0, 0, 0, 0xff, 0xff, 0xff, 0xff, 0, 0, 0, 0]);
+        assert!(is_decode_error(threshold_from_bytes(&threshold).err()));
+    }
+}
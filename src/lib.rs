@@ -18,16 +18,62 @@
 
 extern crate emerald_city;
 extern crate num_traits;
+#[cfg(feature = "python")]
 extern crate pyo3;
 extern crate num_cpus;
 extern crate threadpool;
 extern crate hex;
+extern crate sha2;
+extern crate hmac;
+extern crate ripemd160;
+extern crate bs58;
+extern crate hkdf;
+extern crate chacha20poly1305;
+#[cfg(target_os = "linux")]
+extern crate libc;
+#[macro_use]
+extern crate serde_json;
 
+pub mod error;
+pub mod hashing;
+pub mod utils;
+pub mod keypair;
+pub mod aggregate;
+pub mod musig2;
+pub mod threshold;
+pub mod frost;
+pub mod bip340;
+pub mod verifyutils;
+pub mod batchverify;
+pub mod keystore;
+pub mod secret;
+pub mod refresh;
+pub mod adaptor;
+pub mod tweak;
+pub mod bip32;
+pub mod ecies;
+pub mod blind;
+
+// python bindings
+#[cfg(feature = "python")]
 pub mod pykeypair;
+#[cfg(feature = "python")]
 pub mod pyagg;
+#[cfg(feature = "python")]
+pub mod pymusig2;
+#[cfg(feature = "python")]
 pub mod pythreshold;
-pub mod verifyutils;
+#[cfg(feature = "python")]
 pub mod modules;
+#[cfg(feature = "python")]
 pub mod pyo3utils;
+#[cfg(feature = "python")]
+pub mod pyerror;
+#[cfg(feature = "python")]
+pub mod pykeystore;
+#[cfg(feature = "python")]
+pub mod pybip32;
+#[cfg(feature = "python")]
+pub mod pyblind;
 #[cfg(test)]
-mod test;
+mod testutils;
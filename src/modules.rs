@@ -1,51 +1,148 @@
+use crate::adaptor::PreSignature;
+use crate::aggregate::{verify_aggregate_signature, sort_public_keys as sort_keys};
+use crate::batchverify::{batch_verify, batch_verify_find_invalid};
+use crate::bip340::verify_bip340_signature;
+use crate::error::Error;
+use crate::hashing::{decode_r, encode_r, SigVersion, SIGNATURE_VERSION};
+use crate::pyerror::add_exceptions;
+use crate::pybip32::{PyExtendedPrivKey, PyExtendedPubKey};
+use crate::pyblind::{PyBlindSigner, PyBlindUser};
 use crate::pykeypair::*;
+use crate::pykeystore::{PyAggregateKey, PyAggregateKeyDescriptor, PyThresholdShare};
 use crate::pyo3utils::*;
-use crate::pyagg::{PyAggregate,PyEphemeralKey,verify_aggregate_signature};
+use crate::pyagg::{PyAggregate,PyEphemeralKey};
+use crate::pymusig2::{PyMusig2Nonce,PyMusig2Session};
 use crate::pythreshold::*;
-use crate::verifyutils::verify_auto_signature;
+use crate::threshold::*;
+use crate::tweak::tweak_public_key as tweak_key;
+use crate::utils::*;
+use crate::verifyutils::{verify_auto_signature, verify_auto_signature_multi};
 use emerald_city::curv::elliptic::curves::secp256_k1::{FE, GE};
-use emerald_city::curv::elliptic::curves::traits::{ECPoint, ECScalar};
+use emerald_city::curv::elliptic::curves::traits::ECScalar;
 use emerald_city::curv::arithmetic::num_bigint::BigInt;
 use pyo3::prelude::*;
 use pyo3::wrap_pyfunction;
 use pyo3::types::{PyBytes, PyTuple};
 use pyo3::exceptions::ValueError;
-use threadpool::ThreadPool;
-use std::sync::mpsc::channel;
 
 
 /// verify_aggregate_sign(sig: bytes, R: bytes, apk: bytes, message: bytes, is_musig: bool = None) -> bool
 /// --
 ///
 /// verify aggregate signature (1of 1 and n of n)
-/// signature: [sig 32bytes]-[R 32bytes]
+/// signature: [sig 32bytes]-[R 33bytes] (legacy R is 32bytes)
 /// publicKey: [apk 33bytes]
 #[pyfunction]
 fn verify_aggregate_sign(_py: Python, sig: &PyBytes, R: &PyBytes, apk: &PyBytes, message: &PyBytes, is_musig: Option<bool>)
     -> PyResult<PyObject> {
     let sig = BigInt::from_bytes_be(sig.as_bytes());
-    let R = BigInt::from_bytes_be(R.as_bytes());
-    let is_musig = match is_musig {
-        Some(is_musig) => is_musig,
-        None => match decode_public_bytes(apk.as_bytes()) {
+    let (version, R) = decode_r(R.as_bytes(), 32)?;
+    let R = BigInt::from_bytes_be(R);
+    let is_musig = musig_bit(apk.as_bytes(), is_musig)?;
+    let apk = bytes2point(apk.as_bytes())?;
+    let message = message.as_bytes();
+    let is_verify = verify_aggregate_signature(&sig, &R, &apk, message, is_musig, version).is_ok();
+    Ok(is_verify.to_object(_py))
+}
+
+/// is_musig of caller or from prefix of apk
+fn musig_bit(apk: &[u8], is_musig: Option<bool>) -> PyResult<bool> {
+    match is_musig {
+        Some(is_musig) => Ok(is_musig),
+        None => match decode_public_bytes(apk) {
             Ok((key_type, _)) => match key_type {
-                PyKeyType::SingleSig => false,
-                PyKeyType::AggregateSig => true,
-                _ => return Err(ValueError::py_err("not found pubkey prefix"))
+                KeyType::SingleSig => Ok(false),
+                KeyType::AggregateSig => Ok(true),
+                KeyType::ThresholdSig => Err(Error::InvalidParams("threshold key has no musig bit".to_owned()).into()),
             },
-            Err(_) => return Err(ValueError::py_err("cannot find prefix and is_musig"))
+            Err(err) => Err(err.into())
         }
-    };
+    }
+}
+
+/// verify_adaptor_pre_sign(pre_sig: bytes, R: bytes, T: bytes, apk: bytes, message: bytes, is_musig: bool = None) -> bool
+/// --
+///
+/// verify adaptor pre-signature of get_adaptor_pre_sign or combine_pre_signature_parts
+/// R: 33bytes nonce without T
+#[pyfunction]
+fn verify_adaptor_pre_sign(pre_sig: &PyBytes, R: &PyBytes, T: &PyBytes, apk: &PyBytes, message: &PyBytes,
+                           is_musig: Option<bool>) -> PyResult<bool> {
+    let pre_sig = bytes2pre_signature(pre_sig, R, T)?;
+    let is_musig = musig_bit(apk.as_bytes(), is_musig)?;
     let apk = bytes2point(apk.as_bytes())?;
-    let message = message.as_bytes();
-    let is_verify = verify_aggregate_signature(&sig, &R, &apk, message, is_musig).is_ok();
-    Ok(is_verify.to_object(_py))
+    Ok(pre_sig.verify(&apk, message.as_bytes(), is_musig, SigVersion::Tagged).is_ok())
+}
+
+/// adapt_adaptor_sign(pre_sig: bytes, R: bytes, T: bytes, t: bytes) -> tuple
+/// --
+///
+/// complete pre-signature by adaptor secret t
+/// return versioned (R+T).x(33b) and s(32b), verified by verify_aggregate_sign
+#[pyfunction]
+fn adapt_adaptor_sign(_py: Python, pre_sig: &PyBytes, R: &PyBytes, T: &PyBytes, t: &PyBytes) -> PyResult<PyObject> {
+    let pre_sig = bytes2pre_signature(pre_sig, R, T)?;
+    let t: FE = ECScalar::from(&BigInt::from_bytes_be(t.as_bytes()));
+    let s = pre_sig.adapt(&t)?;
+    Ok(PyTuple::new(_py, &[
+        PyBytes::new(_py, &encode_r(&bigint2bytes(&pre_sig.R_x()).unwrap())),
+        PyBytes::new(_py, &bigint2bytes(&s.to_big_int()).unwrap()),
+    ]).to_object(_py))
+}
+
+/// extract_adaptor_secret(pre_sig: bytes, R: bytes, T: bytes, sig: bytes) -> bytes
+/// --
+///
+/// recover adaptor secret t from pre-signature and published signature
+#[pyfunction]
+fn extract_adaptor_secret(_py: Python, pre_sig: &PyBytes, R: &PyBytes, T: &PyBytes, sig: &PyBytes) -> PyResult<PyObject> {
+    let pre_sig = bytes2pre_signature(pre_sig, R, T)?;
+    let s: FE = ECScalar::from(&BigInt::from_bytes_be(sig.as_bytes()));
+    let t = pre_sig.extract(&s)?;
+    Ok(PyBytes::new(_py, &bigint2bytes(&t.to_big_int()).unwrap()).to_object(_py))
+}
+
+fn bytes2pre_signature(pre_sig: &PyBytes, R: &PyBytes, T: &PyBytes) -> PyResult<PreSignature> {
+    Ok(PreSignature {
+        R: bytes2point(R.as_bytes())?,
+        T: bytes2point(T.as_bytes())?,
+        s: ECScalar::from(&BigInt::from_bytes_be(pre_sig.as_bytes())),
+    })
+}
+
+/// tweak_public_key(public: bytes, commitment: bytes) -> tuple
+/// --
+///
+/// Taproot-style tweak Q = P + H_tweak(P || commitment)*G of single, aggregate and threshold key
+/// return Q(33b, same prefix type) and tweak t(32b)
+#[pyfunction]
+fn tweak_public_key(_py: Python, public: &PyBytes, commitment: &PyBytes) -> PyResult<PyObject> {
+    let (key_type, _prefix) = decode_public_bytes(public.as_bytes())?;
+    let public = bytes2point(public.as_bytes())?;
+    let (Q, t) = tweak_key(&public, key_type, commitment.as_bytes())?;
+    Ok(PyTuple::new(_py, &[
+        PyBytes::new(_py, &point2bytes(&Q, key_type)),
+        PyBytes::new(_py, &bigint2bytes(&t.to_big_int()).unwrap()),
+    ]).to_object(_py))
+}
+
+/// verify_bip340_sign(sig: bytes, pk: bytes, message: bytes) -> bool
+/// --
+///
+/// verify BIP-340 signature
+/// signature: [R.x 32bytes]-[s 32bytes]
+/// publicKey: [x-only 32bytes]
+#[pyfunction]
+fn verify_bip340_sign(sig: &PyBytes, pk: &PyBytes, message: &PyBytes) -> PyResult<bool> {
+    let verify = verify_bip340_signature(sig.as_bytes(), pk.as_bytes(), message.as_bytes());
+    Ok(verify.is_ok())
 }
 
 /// verify_auto(s: bytes, r: bytes, apk: bytes, message: bytes) -> bool
 /// --
 ///
 /// verify signature with detection of type (1 of 1, n of n and n of m)
+/// 32bytes apk is verified as BIP-340 x-only public key
 #[pyfunction]
 fn verify_auto(_py: Python, s: &PyBytes, r: &PyBytes, apk: &PyBytes, message: &PyBytes)
     -> PyResult<PyObject> {
@@ -55,7 +152,7 @@ fn verify_auto(_py: Python, s: &PyBytes, r: &PyBytes, apk: &PyBytes, message: &P
     let message = message.as_bytes();
     let is_verify = _py.allow_threads(move || {
         verify_auto_signature(s, r, apk, message)
-    }).map_err(|err| ValueError::py_err(err))?;
+    }).map_err(PyErr::from)?;
     Ok(is_verify.to_object(_py))
 }
 
@@ -68,18 +165,10 @@ fn verify_auto_multi(_py: Python, tasks: &PyAny, n_workers: usize, f_raise: bool
     -> PyResult<PyObject> {
     // verify by multi-threading
     let tasks: Vec<(Vec<u8>, Vec<u8>, Vec<u8>, Vec<u8>)> = tasks.extract()?;
-    let pool = ThreadPool::new(n_workers);
-    let (tx, rx) = channel();
-    let n_jobs = tasks.len();
-    for (s, r, apk, message) in tasks {
-        let tx = tx.clone();
-        pool.execute(move || {
-            tx.send(verify_auto_signature(&s, &r, &apk, &message)).unwrap()
-        });
-    };
     let exception = _py.allow_threads(move || {
-        let mut response = Vec::with_capacity(n_jobs);
-        for result in rx.iter().take(n_jobs) {
+        let results = verify_auto_signature_multi(tasks, n_workers);
+        let mut response = Vec::with_capacity(results.len());
+        for result in results {
             let is_verify = match result {
                 Ok(is_verify) => is_verify,
                 Err(err) => {
@@ -95,21 +184,62 @@ fn verify_auto_multi(_py: Python, tasks: &PyAny, n_workers: usize, f_raise: bool
     });
     match exception {
         Ok(response) => Ok(response.to_object(_py)),
-        Err(err) => Err(ValueError::py_err(err))
+        Err(err) => Err(err.into())
     }
 }
 
-/// summarize_public_points(signers: list) -> bytes
+/// verify_batch(tasks: list) -> bool
+/// --
+///
+/// verify many signature at once by random linear combination
+/// tasks: list of (s, r, apk, message) same as verify_auto
+/// note: Tagged 1 of 1 and n of n are batched with even Y R, only Legacy ones are verified one by one
+#[pyfunction]
+fn verify_batch(_py: Python, tasks: &PyAny) -> PyResult<bool> {
+    let tasks: Vec<(Vec<u8>, Vec<u8>, Vec<u8>, Vec<u8>)> = tasks.extract()?;
+    Ok(_py.allow_threads(move || batch_verify(&tasks)))
+}
+
+/// verify_batch_find_invalid(tasks: list) -> list
+/// --
+///
+/// batch verify and find invalid signature indexes by bisection
+/// return empty list if all signature are valid
+#[pyfunction]
+fn verify_batch_find_invalid(_py: Python, tasks: &PyAny) -> PyResult<Vec<usize>> {
+    let tasks: Vec<(Vec<u8>, Vec<u8>, Vec<u8>, Vec<u8>)> = tasks.extract()?;
+    Ok(_py.allow_threads(move || batch_verify_find_invalid(&tasks)))
+}
+
+/// sort_public_keys(signers: list) -> list
+/// --
+///
+/// KeySort, lexicographic order of compressed public keys (same as BIP-327)
+/// apk is computed over sorted keys, so sorting is optional for signers
+#[pyfunction]
+fn sort_public_keys(_py: Python, signers: &PyAny) -> PyResult<PyObject> {
+    let signers = pylist2points(signers)?;
+    let sorted: Vec<&PyBytes> = sort_keys(&signers).iter()
+        .map(|p| PyBytes::new(_py, &point2bytes(p, KeyType::SingleSig)))
+        .collect();
+    Ok(sorted.to_object(_py))
+}
+
+/// summarize_public_points(signers: list, proofs: list, context: bytes, qual: list = None) -> bytes
 /// --
 ///
 /// return sum of public points with prefix +6
+/// proofs: all dealer's get_proof, raise InvalidProofError if missing or broken
+/// qual: sum QUAL dealers only, all if None
 /// used for threshold-signature
 #[pyfunction]
-fn summarize_public_points(_py: Python, signers: &PyAny) -> PyResult<PyObject> {
+fn summarize_public_points(_py: Python, signers: &PyAny, proofs: &PyAny, context: &PyBytes, qual: Option<Vec<usize>>)
+    -> PyResult<PyObject> {
     let signers = pylist2points(&signers)?;
-    let sum = sum_public_points(&signers)?;
-    let mut sum = sum.get_element().serialize();
-    sum[0] += 6;  // 0x02 0x03 0x04 => 0x08 0x09 0x0a
+    let proofs = pylist2proofs(proofs)?;
+    let qual = qual.unwrap_or((0..signers.len()).collect());
+    let sum = group_public_key(&signers, &proofs, context.as_bytes(), &qual).map_err(PyErr::from)?;
+    let sum = point2bytes(&sum, KeyType::ThresholdSig);  // 0x02 0x03 0x04 => 0x08 0x09 0x0a
     Ok(PyBytes::new(_py, &sum).to_object(_py))
 }
 
@@ -117,6 +247,7 @@ fn summarize_public_points(_py: Python, signers: &PyAny) -> PyResult<PyObject> {
 /// --
 ///
 /// return e and gamma
+/// V: versioned 34bytes (legacy V is 33bytes)
 /// used for threshold-signature
 #[pyfunction]
 fn get_local_signature(_py: Python, share: &PyBytes, eph_share: &PyBytes, Y: &PyBytes, V: &PyBytes, message: &PyBytes)
@@ -124,9 +255,10 @@ fn get_local_signature(_py: Python, share: &PyBytes, eph_share: &PyBytes, Y: &Py
     let share: FE = ECScalar::from(&BigInt::from_bytes_be(share.as_bytes()));
     let eph_share: FE = ECScalar::from(&BigInt::from_bytes_be(eph_share.as_bytes()));
     let Y: GE = bytes2point(Y.as_bytes())?;  // sharedKey
-    let V: GE = bytes2point(V.as_bytes())?;  // eph sharedKey
+    let (version, V) = decode_r(V.as_bytes(), 33)?;
+    let V: GE = bytes2point(V)?;  // eph sharedKey
     let message = message.as_bytes();
-    let (e, gamma_i) = compute_local_signature(&share, &eph_share, &Y, &V, message);
+    let (e, gamma_i) = compute_local_signature(&share, &eph_share, &Y, &V, message, version);
     let e = bigint2bytes(&e.to_big_int()).unwrap();
     let gamma_i = bigint2bytes(&gamma_i.to_big_int()).unwrap();
     Ok(PyTuple::new(_py, &[
@@ -135,17 +267,20 @@ fn get_local_signature(_py: Python, share: &PyBytes, eph_share: &PyBytes, Y: &Py
     ]).to_object(_py))
 }
 
-/// summarize_local_signature(t: int, n: int, m: int, e: int, gammas: list, parties_index: list, vss_points: list, eph_vss_points: list) -> bytes
+/// summarize_local_signature(t: int, n: int, m: int, e: int, gammas: list, parties_index: list, vss_points: list, eph_vss_points: list, tweak: bytes = None) -> bytes
 /// --
 ///
 /// return sigma
+/// raise InvalidShareError, .party_index is list of all failed parties_index
 /// used for threshold-signature
+/// tweak: t of tweak_public_key(Y, commitment) when gammas are signed for Q
 #[pyfunction]
 fn summarize_local_signature(
     _py: Python, t: usize, n: usize, m: usize, e: &PyBytes, gammas: &PyAny,
-    parties_index: &PyAny, vss_points: &PyAny, eph_vss_points: &PyAny)
+    parties_index: &PyAny, vss_points: &PyAny, eph_vss_points: &PyAny, tweak: Option<&PyBytes>)
     -> PyResult<PyObject> {
     let e: FE = ECScalar::from(&BigInt::from_bytes_be(e.as_bytes()));
+    let tweak: Option<FE> = tweak.map(|t| ECScalar::from(&BigInt::from_bytes_be(t.as_bytes())));
     let gammas = pylist2bigint(gammas)?;
     if parties_index.is_none() {
         return Err(ValueError::py_err("parties_index is not None"));
@@ -153,12 +288,12 @@ fn summarize_local_signature(
     let parties_index: Vec<usize> = parties_index.extract()?;
     let vss_points = pylist2vss(t, n, vss_points)?;
     let eph_vss_points = pylist2vss(t, m, eph_vss_points)?;
-    match sum_local_signature(t, &e, &gammas, &parties_index, &vss_points, &eph_vss_points){
+    match sum_local_signature(t, &e, &gammas, &parties_index, &vss_points, &eph_vss_points, tweak.as_ref()){
         Ok(sigma) => {
             let sigma = bigint2bytes(&sigma.to_big_int()).unwrap();
             Ok(PyBytes::new(_py, &sigma).to_object(_py))
         },
-        Err(err) => Err(ValueError::py_err(err))
+        Err(err) => Err(err.into())
     }
 }
 
@@ -166,15 +301,16 @@ fn summarize_local_signature(
 /// --
 ///
 /// verify threshold signature
-/// signature: [sigma 32bytes]-[V 33bytes]
+/// signature: [sigma 32bytes]-[V 34bytes] (legacy V is 33bytes)
 /// publicLey: [Y 33bytes]
 #[pyfunction]
 fn verify_threshold_sign(sigma: &PyBytes, Y: &PyBytes, V: &PyBytes, message: &PyBytes)
     -> PyResult<bool> {
     let sigma = ECScalar::from(&BigInt::from_bytes_be(sigma.as_bytes()));
     let Y = bytes2point(Y.as_bytes())?;
-    let V = bytes2point(V.as_bytes())?;
-    let verify = verify_threshold_signature(sigma, &Y, &V, message.as_bytes());
+    let (version, V) = decode_r(V.as_bytes(), 33)?;
+    let V = bytes2point(V)?;
+    let verify = verify_threshold_signature(sigma, &Y, &V, message.as_bytes(), version);
     Ok(verify)
 }
 
@@ -183,13 +319,35 @@ pub fn multi_party_schnorr(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_class::<PyKeyPair>()?;
     m.add_class::<PyEphemeralKey>()?;
     m.add_class::<PyAggregate>()?;
+    m.add_class::<PyMusig2Nonce>()?;
+    m.add_class::<PyMusig2Session>()?;
     m.add_wrapped(wrap_pyfunction!(verify_aggregate_sign))?;
+    m.add_wrapped(wrap_pyfunction!(verify_bip340_sign))?;
     m.add_wrapped(wrap_pyfunction!(verify_auto))?;
     m.add_wrapped(wrap_pyfunction!(verify_auto_multi))?;
+    m.add_wrapped(wrap_pyfunction!(verify_batch))?;
+    m.add_wrapped(wrap_pyfunction!(verify_batch_find_invalid))?;
+    m.add_wrapped(wrap_pyfunction!(sort_public_keys))?;
+    m.add_wrapped(wrap_pyfunction!(verify_adaptor_pre_sign))?;
+    m.add_wrapped(wrap_pyfunction!(adapt_adaptor_sign))?;
+    m.add_wrapped(wrap_pyfunction!(extract_adaptor_secret))?;
+    m.add_wrapped(wrap_pyfunction!(tweak_public_key))?;
     m.add_class::<PyThresholdKey>()?;
+    m.add_class::<PyDealing>()?;
+    m.add_class::<PyFrostNonce>()?;
+    m.add_class::<PyFrostSession>()?;
+    m.add_class::<PyAggregateKey>()?;
+    m.add_class::<PyAggregateKeyDescriptor>()?;
+    m.add_class::<PyThresholdShare>()?;
+    m.add_class::<PyExtendedPrivKey>()?;
+    m.add_class::<PyExtendedPubKey>()?;
+    m.add_class::<PyBlindSigner>()?;
+    m.add_class::<PyBlindUser>()?;
     m.add_wrapped(wrap_pyfunction!(summarize_public_points))?;
     m.add_wrapped(wrap_pyfunction!(get_local_signature))?;
     m.add_wrapped(wrap_pyfunction!(summarize_local_signature))?;
     m.add_wrapped(wrap_pyfunction!(verify_threshold_sign))?;
+    m.add("SIGNATURE_VERSION", SIGNATURE_VERSION)?;
+    add_exceptions(_py, m)?;
     Ok(())
 }
@@ -0,0 +1,229 @@
+use crate::aggregate::key_aggregation_coefficients;
+use crate::bip340::{has_even_y, negate_point, negate_scalar};
+use crate::error::Error;
+use crate::keypair::KeyPair;
+use crate::secret::{SecretScalar, Wipe};
+use crate::hashing::{challenge, musig2_nonce_coefficient};
+use crate::utils::{KeyType, point2bytes, bigint2bytes};
+use emerald_city::curv::elliptic::curves::secp256_k1::{FE, GE};
+use emerald_city::curv::elliptic::curves::traits::{ECPoint, ECScalar};
+use emerald_city::curv::arithmetic::num_bigint::BigInt;
+
+
+/// MuSig2 nonce pair published before message is known
+/// not Clone, one secret nonce opens only one session
+pub struct Musig2Nonce {
+    secret: (SecretScalar, SecretScalar),
+    pub public: (GE, GE),
+}
+
+impl Musig2Nonce {
+    /// generate random nonce pair (k1, k2) and (R1, R2)
+    pub fn new() -> Self {
+        let g: GE = ECPoint::generator();
+        let k1 = SecretScalar::new(ECScalar::new_random());
+        let k2 = SecretScalar::new(ECScalar::new_random());
+        let public = (k1.scalar_mul(&g), k2.scalar_mul(&g));
+        Musig2Nonce {secret: (k1, k2), public}
+    }
+
+    /// wipe secret nonce, do not sign with it again
+    pub fn wipe(&mut self) {
+        self.secret.0.wipe();
+        self.secret.1.wipe();
+    }
+}
+
+/// signing session of one signer
+/// secret nonce is removed after partial signing
+pub struct Musig2Session {
+    pub keypair: KeyPair,
+    pub nonce: Option<Musig2Nonce>,
+    pub apk: GE,
+    pub hash: BigInt,
+    pub b: FE,
+    pub r_tag: GE,
+    pub message: Vec<u8>,
+    pub is_musig: bool,
+    /// all signer's public keys, public nonces and coefficients (same order)
+    pub signers: Vec<GE>,
+    pub nonces: Vec<(GE, GE)>,
+    pub coefficients: Vec<FE>,
+}
+
+impl Musig2Session {
+    /// start single round signing
+    /// signers: all signer's public keys, same order as key generation
+    /// nonces: all signer's public nonce pair
+    /// nonce is moved into session and wiped even on error
+    pub fn generate(signers: &[GE], nonces: &[(GE, GE)], keypair: &KeyPair, nonce: Musig2Nonce, message: &[u8])
+        -> Result<Musig2Session, Error> {
+        if signers.len() != nonces.len() {
+            return Err(Error::LengthMismatch {
+                name: "nonces", expected: signers.len(), actual: nonces.len() })
+        } else if signers.len() < 1 {
+            return Err(Error::InvalidParams("no signer found".to_owned()))
+        }
+        let is_musig = 1 < signers.len();
+        let (apk, coefficients) = key_aggregation_coefficients(signers)?;
+        // same key can be listed twice, find my position by the nonce
+        let position = signers.iter().zip(nonces.iter())
+            .position(|(public, points)| public == &keypair.public && points == &nonce.public)
+            .ok_or(Error::InvalidParams("your public nonce is not found in nonces".to_owned()))?;
+        let hash = coefficients[position].to_big_int();
+        let (r1, r2) = aggregate_nonces(nonces)?;
+        let b = nonce_coefficient(&r1, &r2, &apk, message);
+        // R = R1 + b*R2
+        let r_tag = r1 + r2.scalar_mul(&b.get_element());
+        Ok(Musig2Session {
+            keypair: keypair.clone(),
+            nonce: Some(nonce),
+            apk,
+            hash,
+            b,
+            r_tag,
+            message: message.to_vec(),
+            is_musig,
+            signers: signers.to_vec(),
+            nonces: nonces.to_vec(),
+            coefficients,
+        })
+    }
+
+    /// get partial signature s_i = k1 + b*k2 + c*a_i*x_i
+    /// k1 + b*k2 is negated when R has odd Y, so the signature is of R with even Y
+    /// the nonce is consumed, a second call fails
+    pub fn partial_sign(&mut self) -> Result<FE, Error> {
+        let nonce = self.nonce.take().ok_or(
+            Error::InvalidParams("nonce is already used".to_owned()))?;
+        let c_fe = self.challenge()?;
+        let a_fe: FE = ECScalar::from(&self.hash);
+        let (k1, k2) = &nonce.secret;
+        let mut k = k2.mul_add_secret(&self.b, k1);
+        if !has_even_y(&self.r_tag) {
+            let negated = negate_scalar(&k);
+            k.wipe();
+            k = negated;
+        }
+        let s_i = self.keypair.secret.mul_add(&(c_fe * a_fe), &k);
+        k.wipe();
+        Ok(s_i)
+    }
+
+    /// check partial signature of signer at index
+    /// s_i*G == R1_i + b*R2_i + c*a_i*X_i (R1_i + b*R2_i is negated when R has odd Y)
+    pub fn verify_partial_sign(&self, index: usize, s_i: &FE) -> bool {
+        let (public, (r1, r2), a_i) = match (
+            self.signers.get(index), self.nonces.get(index), self.coefficients.get(index)) {
+            (Some(public), Some(nonce), Some(a_i)) => (public, nonce, a_i),
+            _ => return false,
+        };
+        let c = match self.challenge() {
+            Ok(c) => c,
+            Err(_) => return false,
+        };
+        let r_i = r1.add_point(&r2.scalar_mul(&self.b.get_element()).get_element());
+        let r_i = if has_even_y(&self.r_tag) { r_i } else { negate_point(&r_i) };
+        let base_point: GE = ECPoint::generator();
+        let expected = r_i.add_point(&public.scalar_mul(&(c * a_i.clone()).get_element()).get_element());
+        base_point.scalar_mul(&s_i.get_element()) == expected
+    }
+
+    /// check all partial signatures (same order as signers) and return s
+    /// error contains indexes of faulty signers
+    pub fn combine_partial_signs(&self, parts: &[FE]) -> Result<FE, Error> {
+        if parts.len() != self.signers.len() {
+            return Err(Error::LengthMismatch {
+                name: "parts", expected: self.signers.len(), actual: parts.len() });
+        }
+        let failed: Vec<usize> = parts.iter()
+            .enumerate()
+            .filter(|(index, s_i)| !self.verify_partial_sign(*index, s_i))
+            .map(|(index, _)| index)
+            .collect();
+        if 0 < failed.len() {
+            return Err(Error::InvalidShare { party_index: failed });
+        }
+        aggregate_partial_signatures(parts)
+    }
+
+    /// get R point x coordinate
+    pub fn R(&self) -> BigInt {
+        self.r_tag.x_coor().unwrap()
+    }
+
+    // c = H_challenge(R.x || apk || message)
+    fn challenge(&self) -> Result<FE, Error> {
+        let r_x = bigint2bytes(&self.R())?;
+        Ok(challenge(&r_x, &self.apk, self.key_type(), &self.message))
+    }
+
+    /// wipe secret key, unused nonce is wiped on drop
+    pub fn wipe(&mut self) {
+        self.keypair.wipe();
+        self.nonce = None;
+    }
+
+    fn key_type(&self) -> KeyType {
+        if self.is_musig { KeyType::AggregateSig } else { KeyType::SingleSig }
+    }
+
+    /// get shared public key with 0x05 0x06 prefix when musig
+    pub fn apk_bytes(&self) -> [u8;33] {
+        point2bytes(&self.apk, self.key_type())
+    }
+}
+
+/// R1 = sum of R_i1, R2 = sum of R_i2
+pub fn aggregate_nonces(nonces: &[(GE, GE)]) -> Result<(GE, GE), Error> {
+    let mut iter = nonces.iter();
+    let head = iter.next().ok_or(
+        Error::InvalidParams("zero length nonces isn't allowed".to_owned()))?;
+    Ok(iter.fold(head.clone(), |(r1, r2), (n1, n2)| (r1 + n1, r2 + n2)))
+}
+
+/// b = H_nonce(R1 || R2 || apk || message)
+pub fn nonce_coefficient(r1: &GE, r2: &GE, apk: &GE, message: &[u8]) -> FE {
+    musig2_nonce_coefficient(r1, r2, apk, message)
+}
+
+/// s = sum of s_i
+pub fn aggregate_partial_signatures(parts: &[FE]) -> Result<FE, Error> {
+    let mut iter = parts.iter();
+    let head = iter.next().ok_or(
+        Error::InvalidParams("zero length partial signatures isn't allowed".to_owned()))?;
+    Ok(iter.fold(head.clone(), |acc, s| acc + s))
+}
+
+
+#[cfg(test)]
+mod Test {
+    use crate::aggregate::verify_aggregate_signature;
+    use crate::hashing::SigVersion;
+    use crate::keypair::KeyPair;
+    use crate::musig2::*;
+    use emerald_city::curv::elliptic::curves::traits::ECScalar;
+
+    #[test]
+    fn test_musig2_n_of_n() {
+        let pairs: Vec<KeyPair> = (0..3).map(|_| KeyPair::new()).collect();
+        let nonces: Vec<Musig2Nonce> = (0..3).map(|_| Musig2Nonce::new()).collect();
+        let signers: Vec<_> = pairs.iter().map(|p| p.public.clone()).collect();
+        let public_nonces: Vec<_> = nonces.iter().map(|n| n.public.clone()).collect();
+        let msg = b"hello musig2";
+        let mut sessions: Vec<Musig2Session> = pairs.iter().zip(nonces.into_iter())
+            .map(|(p, n)| Musig2Session::generate(&signers, &public_nonces, p, n, msg).unwrap())
+            .collect();
+        let mut parts: Vec<_> = sessions.iter_mut().map(|s| s.partial_sign().unwrap()).collect();
+        assert!(sessions[0].partial_sign().is_err());
+        let session = &sessions[0];
+        assert!(session.verify_partial_sign(2, &parts[2]));
+        let s = session.combine_partial_signs(&parts).unwrap();
+        assert!(verify_aggregate_signature(&s.to_big_int(), &session.R(), &session.apk, msg, true, SigVersion::Tagged).is_ok());
+        // garbage from signer 1 is blamed
+        parts[1] = ECScalar::new_random();
+        assert!(!session.verify_partial_sign(1, &parts[1]));
+        assert_eq!(session.combine_partial_signs(&parts).err().unwrap(),
+                   crate::error::Error::InvalidShare { party_index: vec![1] });
+    }
+}
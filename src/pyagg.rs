@@ -1,40 +1,28 @@
-use crate::pyo3utils::{bytes2point, bigint2bytes};
+use crate::aggregate::{Aggregate, EphemeralKey};
+use crate::hashing::encode_r;
+use crate::pyo3utils::{bytes2point, pylist2points, pylist2bigint};
 use crate::pykeypair::*;
-use crate::verifyutils::*;
-use emerald_city::curv::cryptographic_primitives::commitments::{
-    hash_commitment::HashCommitment,
-    traits::Commitment,
-};
-use emerald_city::curv::cryptographic_primitives::hashing::{
-    hash_sha256::HSha256,
-    traits::Hash,
-};
+use crate::utils::{bigint2bytes, point2bytes, KeyType};
 use emerald_city::curv::elliptic::curves::secp256_k1::{FE, GE};
-use emerald_city::curv::elliptic::curves::traits::{ECPoint, ECScalar};
+use emerald_city::curv::elliptic::curves::traits::ECScalar;
 use emerald_city::curv::arithmetic::num_bigint::BigInt;
-use num_traits::{Zero, One};
 use pyo3::prelude::*;
-use pyo3::exceptions::ValueError;
-use pyo3::types::{PyBytes, PyType};
+use pyo3::types::{PyBytes, PyTuple, PyType};
 
 
 #[pyclass]
 #[derive(Clone)]
 pub struct PyEphemeralKey {
-    #[pyo3(get)]
-    pub keypair: PyKeyPair,
-    pub commitment: BigInt,
-    pub blind_factor: BigInt,
+    pub inner: EphemeralKey,
 }
 
 #[pymethods]
 impl PyEphemeralKey {
     #[new]
     fn new(py: Python) -> Self {
-        let keypair = generate_keypair(py);
-        let (commitment, blind_factor) = HashCommitment::create_commitment(
-            &keypair.public.bytes_compressed_to_big_int());
-        PyEphemeralKey {keypair, commitment, blind_factor}
+        // release GIL
+        let inner = py.allow_threads(move || EphemeralKey::new());
+        PyEphemeralKey {inner}
     }
 
     /// from_keypair(keypair: PyKeyPair) -> PyEphemeralKey
@@ -43,10 +31,13 @@ impl PyEphemeralKey {
     /// get ephemeral key from keypair
     #[classmethod]
     fn from_keypair(_cls: &PyType, keypair: &PyKeyPair) -> PyResult<PyEphemeralKey> {
-        let (commitment, blind_factor) = HashCommitment::create_commitment(
-            &keypair.public.bytes_compressed_to_big_int());
-        let keypair = keypair.clone();
-        Ok(PyEphemeralKey {keypair, commitment, blind_factor})
+        let inner = EphemeralKey::from_keypair(&keypair.inner);
+        Ok(PyEphemeralKey {inner})
+    }
+
+    #[getter]
+    fn keypair(&self) -> PyKeyPair {
+        PyKeyPair {inner: (*self.inner.keypair).clone()}
     }
 
     /// check_commitments() -> bool
@@ -54,97 +45,96 @@ impl PyEphemeralKey {
     ///
     /// check ephemeral commitments
     fn check_commitments(&self) -> bool {
-        ephemeral_test_com(&self.keypair.public, &self.blind_factor, &self.commitment)
+        self.inner.check_commitments()
+    }
+
+    /// get_commitment() -> tuple
+    /// --
+    ///
+    /// return commitment(32b) & blind_factor(32b)
+    /// publish commitment first, reveal R and blind_factor after all commitments received
+    fn get_commitment(&self, _py: Python) -> PyObject {
+        let (commitment, blind_factor) = self.inner.get_commitment();
+        PyTuple::new(_py, &[
+            PyBytes::new(_py, &bigint2bytes(&commitment).unwrap()),
+            PyBytes::new(_py, &bigint2bytes(&blind_factor).unwrap()),
+        ]).to_object(_py)
+    }
+
+    /// wipe() -> None
+    /// --
+    ///
+    /// wipe secret key and blind_factor, do not use this object again
+    fn wipe(&mut self) {
+        self.inner.wipe();
     }
 }
 
 #[pyclass]
 pub struct PyAggregate {
-    #[pyo3(get)]
-    pub keypair: PyKeyPair,
-    #[pyo3(get)]
-    pub eph: PyEphemeralKey,
-    pub apk: GE,
-    pub hash: BigInt,
-    pub r_tag: GE,
-    #[pyo3(get)]
-    pub is_musig: bool,
+    pub inner: Aggregate,
 }
 
 #[pymethods]
 impl PyAggregate {
 
-    /// generate(signers: list, ephemeral: list, keypair: PyKeyPair, eph: PyEphemeralKey) -> PyAggregate
+    /// generate(signers: list, ephemeral: list, commitments: list, blind_factors: list, keypair: PyKeyPair, eph: PyEphemeralKey) -> PyAggregate
     /// --
     ///
     /// get aggregate key
+    /// commitments and blind_factors: all signer's PyEphemeralKey.get_commitment()
+    /// apk does not depend on order of signers, same key can be listed twice
+    /// raise error with indexes when revealed ephemeral do not match with commitment
     #[classmethod]
-    fn generate(_cls: &PyType, signers: &PyAny, ephemeral: &PyAny, keypair: &PyKeyPair, eph: &PyEphemeralKey)
-        -> PyResult<PyAggregate> {
-        // check signature number
-        let signers: Vec<&[u8]> = signers.extract()?;
-        let ephemeral: Vec<&[u8]> = ephemeral.extract()?;
-        let keypair = keypair.clone();
-        let eph = eph.clone();
-        if signers.len() != ephemeral.len() {
-            return Err(ValueError::py_err(format!(
-                "signers={} ephemeral={}, different?", signers.len(), ephemeral.len())))
-        } else if signers.len() < 1 {
-            return Err(ValueError::py_err("no signer found"))
-        }
-        // compute apk
-        let is_musig = 1 < signers.len();
-        let mut party_index: Option<usize> = None;
-        let mut pks = Vec::with_capacity(signers.len());
-        for (index, key) in signers.into_iter().enumerate() {
-            let public = bytes2point(key)?;
-            if public == keypair.public {
-                party_index = Some(index)
-            }
-            pks.push(public);
-        };
-        let party_index = party_index.ok_or(
-            ValueError::py_err("not found your public key in signers"))?;
-        let (apk, hash) = key_aggregation_n(&pks, party_index);
-        // compute R' = R1+R2:
-        let mut points = Vec::with_capacity(ephemeral.len());
-        for eph in ephemeral.into_iter() {
-            let eph = bytes2point(eph)?;
-            points.push(eph);
-        };
-        // sum of ephemeral points
-        let r_hat = {
-            let mut iter = points.into_iter();
-            let head = iter.next().unwrap();
-            iter.fold(head, |a, b| a + b)
-        };
-        Ok(PyAggregate {keypair, eph, apk, hash, r_tag: r_hat, is_musig})
+    fn generate(_cls: &PyType, signers: &PyAny, ephemeral: &PyAny, commitments: &PyAny, blind_factors: &PyAny,
+                keypair: &PyKeyPair, eph: &PyEphemeralKey) -> PyResult<PyAggregate> {
+        let signers = pylist2points(signers)?;
+        let ephemeral = pylist2points(ephemeral)?;
+        let commitments: Vec<&[u8]> = commitments.extract()?;
+        let commitments: Vec<BigInt> = commitments.into_iter()
+            .map(|i| BigInt::from_bytes_be(i)).collect();
+        let blind_factors: Vec<&[u8]> = blind_factors.extract()?;
+        let blind_factors: Vec<BigInt> = blind_factors.into_iter()
+            .map(|i| BigInt::from_bytes_be(i)).collect();
+        let inner = Aggregate::generate(
+            &signers, &ephemeral, &commitments, &blind_factors, &keypair.inner, &eph.inner)
+            .map_err(PyErr::from)?;
+        Ok(PyAggregate {inner})
+    }
+
+    #[getter]
+    fn keypair(&self) -> PyKeyPair {
+        PyKeyPair {inner: self.inner.keypair.clone()}
+    }
+
+    #[getter]
+    fn eph(&self) -> PyEphemeralKey {
+        PyEphemeralKey {inner: self.inner.eph.clone()}
+    }
+
+    #[getter]
+    fn is_musig(&self) -> bool {
+        self.inner.is_musig
     }
 
     /// get_partial_sign(message: bytes) -> bytes
     /// --
     ///
     /// get partial signature of whole's
-    fn get_partial_sign(&self, _py: Python, message: &PyBytes) -> PyObject {
-        // compute c = H0(Rtag || apk || message)
-        let message = message.as_bytes();
-        let c = ephemeral_hash_0(&self.r_tag, &self.apk, message, self.is_musig);
-        // compute partial signature s_i
-        let c_fe: FE = ECScalar::from(&c);
-        let a_fe: FE = ECScalar::from(&self.hash);
-        let s_i = self.eph.keypair.secret.clone() + (c_fe * self.keypair.secret.clone() * a_fe);
+    fn get_partial_sign(&self, _py: Python, message: &PyBytes) -> PyResult<PyObject> {
+        let s_i = self.inner.partial_sign(message.as_bytes())?;
         // encode to bytes
         let s_i = bigint2bytes(&s_i.to_big_int()).unwrap();
-        PyBytes::new(_py, &s_i).to_object(_py)
+        Ok(PyBytes::new(_py, &s_i).to_object(_py))
     }
 
     /// R() -> bytes
     /// --
     ///
-    /// get R point
+    /// get versioned R.x (33b)
     fn R(&self, _py: Python) -> PyObject {
-        let int = self.r_tag.x_coor().unwrap();
-        let bytes = bigint2bytes(&int).unwrap();
+        let int = self.inner.R();
+        let bytes = encode_r(&bigint2bytes(&int).unwrap());
         PyBytes::new(_py, &bytes).to_object(_py)
     }
 
@@ -153,10 +143,7 @@ impl PyAggregate {
     ///
     /// get shared public key
     fn apk(&self, _py: Python) -> PyObject {
-        let mut bytes = self.apk.get_element().serialize();
-        if self.is_musig {
-            bytes[0] += 3; // 0x02 0x03 0x04 => 0x05 0x06 0x07
-        }
+        let bytes = self.inner.apk_bytes();
         PyBytes::new(_py, &bytes).to_object(_py)
     }
 
@@ -165,131 +152,95 @@ impl PyAggregate {
     ///
     /// return a signature + another signature
     fn add_signature_parts(&self, _py: Python,  s1: &PyBytes, s2: &PyBytes) -> PyObject {
-        let s1 = BigInt::from_bytes_be(s1.as_bytes());
-        let s2 = BigInt::from_bytes_be(s2.as_bytes());
-        let s1_fe: FE = ECScalar::from(&s1);
-        let s2_fe: FE = ECScalar::from(&s2);
-        let s1_plus_s2 = s1_fe.add(&s2_fe.get_element());
+        let s1: FE = ECScalar::from(&BigInt::from_bytes_be(s1.as_bytes()));
+        let s2: FE = ECScalar::from(&BigInt::from_bytes_be(s2.as_bytes()));
+        let s1_plus_s2 = Aggregate::add_signature_parts(&s1, &s2);
         let s = bigint2bytes(&s1_plus_s2.to_big_int()).unwrap();
         PyBytes::new(_py, &s).to_object(_py)
     }
-}
-
-
-/// generate aggregate Key
-fn key_aggregation_n(pks: &[GE], party_index: usize) -> (GE, BigInt) {
-    let bn_1 = BigInt::one();
-    let x_coor_vec: Vec<BigInt> = pks
-        .iter()
-        .map(|pk| pk.bytes_compressed_to_big_int())
-        .collect();
 
-    let hash_vec: Vec<BigInt> = x_coor_vec
-        .iter()
-        .map(|pk| {
-            let mut vec = Vec::new();
-            vec.push(&bn_1);
-            vec.push(pk);
-            for mpz in x_coor_vec.iter().take(pks.len()) {
-                vec.push(mpz);
-            }
-            HSha256::create_hash(&vec)
-        })
-        .collect();
-
-    let mut apk_vec: Vec<GE> = pks
-        .iter()
-        .zip(&hash_vec)
-        .map(|(pk, hash)| {
-            let hash_t: FE = ECScalar::from(&hash);
-            let pki: GE = pk.clone();
-            pki.scalar_mul(&hash_t.get_element())
-        })
-        .collect();
-
-    let pk1 = apk_vec.remove(0);
-    let sum = apk_vec
-        .iter()
-        .fold(pk1, |acc, pk| acc.add_point(&pk.get_element()));
-    // apk, hash
-    (sum, hash_vec[party_index].clone())
+    /// verify_partial_sign(index: int, s_i: bytes, message: bytes) -> bool
+    /// --
+    ///
+    /// check partial signature of signers[index] by its public key and revealed R_i
+    fn verify_partial_sign(&self, index: usize, s_i: &PyBytes, message: &PyBytes) -> bool {
+        let s_i: FE = ECScalar::from(&BigInt::from_bytes_be(s_i.as_bytes()));
+        self.inner.verify_partial_sign(index, &s_i, message.as_bytes())
     }
 
-
-// ephemeral commitments check
-fn ephemeral_test_com(r_to_test: &GE, blind_factor: &BigInt, comm: &BigInt) -> bool {
-    let computed_comm = &HashCommitment::create_commitment_with_user_defined_randomness(
-        &r_to_test.bytes_compressed_to_big_int(),
-        blind_factor,
-    );
-    computed_comm == comm
-}
-
-
-pub fn verify_aggregate_signature(signature: &BigInt, r_x: &BigInt, apk: &GE, message: &[u8], musig_bit: bool)
-    -> Result<(), String> {
-    let base_point: GE = ECPoint::generator();
-
-    let c = if musig_bit {
-        HSha256::create_hash(&[
-            &BigInt::zero(),
-            &r_x,
-            &apk.bytes_compressed_to_big_int(),
-            &BigInt::from_bytes_be(message),
-        ])
-    } else {
-        HSha256::create_hash(&[
-            r_x,
-            &apk.bytes_compressed_to_big_int(),
-            &BigInt::from_bytes_be(message),
-        ])
-    };
-
-    let signature_fe: FE = ECScalar::from(signature);
-    let sG = base_point.scalar_mul(&signature_fe.get_element());
-    let c: FE = ECScalar::from(&c);
-    let cY = apk.scalar_mul(&c.get_element());
-    let sG = sG.sub_point(&cY.get_element());
-    if sG.x_coor().unwrap() == *r_x {
-        Ok(())
-    } else {
-        Err(String::from("sG_x do not match with r_x"))
+    /// combine_signature_parts(parts: list, message: bytes) -> bytes
+    /// --
+    ///
+    /// check all partial signatures (same order as signers) and return signature
+    /// raise InvalidShareError with indexes of faulty signers
+    fn combine_signature_parts(&self, _py: Python, parts: &PyAny, message: &PyBytes) -> PyResult<PyObject> {
+        let parts = pylist2bigint(parts)?;
+        let s = self.inner.combine_partial_signs(&parts, message.as_bytes())?;
+        let s = bigint2bytes(&s.to_big_int()).unwrap();
+        Ok(PyBytes::new(_py, &s).to_object(_py))
     }
-}
-
 
-#[cfg(test)]
-mod Test {
-    use crate::pyo3utils::bytes2point_inner;
-    use emerald_city::curv::arithmetic::num_bigint::BigInt;
-    use pyagg::verify_aggregate_signature;
+    /// tweak(commitment: bytes) -> PyAggregate
+    /// --
+    ///
+    /// Taproot-style tweaked aggregate, apk is Q = apk + H_tweak(apk || commitment)*G
+    /// all signers tweak by same commitment, partial signatures are combined by combine_signature_parts
+    fn tweak(&self, commitment: &PyBytes) -> PyResult<PyAggregate> {
+        let inner = self.inner.tweak(commitment.as_bytes())?;
+        Ok(PyAggregate {inner})
+    }
 
-    #[test]
-    fn test_normal_single_sig() {
-        // let sk = b"\xb7\xe1Qb\x8a\xed*j\xbfqX\x80\x9c\xf4\xf3\xc7b\xe7\x16\x0f8\xb4\xdaV\xa7\x84\xd9\x04Q\x90\xcf\xef";
-        let pk = b"\x02\xdf\xf1\xd7\x7f*g\x1c_6\x187&\xdb#A\xbeX\xfe\xae\x1d\xa2\xde\xce\xd8C$\x0f{P+\xa6Y";
-        let msg = b"$?j\x88\x85\xa3\x08\xd3\x13\x19\x8a.\x03psD\xa4\t8\")\x9f1\xd0\x08.\xfa\x98\xecNl\x89";
-        let sig_a = b"*)\x8d\xac\xaeW9Z\x15\xd0y]\xdb\xfd\x1d\xcbVM\xa8+\x0f&\x9b\xc7\nt\xf8\"\x04)\xba\x1d";
-        let sig_b = b"\x1eQ\xa2,\xce\xc3U\x99\xb8\xf2f\x91\"\x81\xf86_\xfc-\x03Z#\x044\xa1\xa6M\xc5\x9fp\x13\xfd";
+    /// tweak_add(t: bytes) -> PyAggregate
+    /// --
+    ///
+    /// apk becomes apk + t*G by raw tweak (ex. tweak of PyExtendedPubKey.derive_path)
+    fn tweak_add(&self, t: &PyBytes) -> PyResult<PyAggregate> {
+        let t: FE = ECScalar::from(&BigInt::from_bytes_be(t.as_bytes()));
+        let inner = self.inner.tweak_add(&t)?;
+        Ok(PyAggregate {inner})
+    }
 
-        let pk = bytes2point_inner(pk).unwrap();
-        let sig_a = BigInt::from_bytes_be(sig_a);
-        let sig_b = BigInt::from_bytes_be(sig_b);
-        assert!(verify_aggregate_signature(&sig_b, &sig_a, &pk, msg, false).is_ok());
+    /// get_pre_partial_sign(message: bytes, T: bytes) -> bytes
+    /// --
+    ///
+    /// get adaptor pre-signature part, nonce of final signature is R+T
+    fn get_pre_partial_sign(&self, _py: Python, message: &PyBytes, T: &PyBytes) -> PyResult<PyObject> {
+        let T: GE = bytes2point(T.as_bytes())?;
+        let s_i = self.inner.pre_partial_sign(&T, message.as_bytes())?;
+        Ok(PyBytes::new(_py, &bigint2bytes(&s_i.to_big_int()).unwrap()).to_object(_py))
     }
 
+    /// verify_pre_partial_sign(index: int, s_i: bytes, T: bytes, message: bytes) -> bool
+    /// --
+    ///
+    /// check adaptor pre-signature part of signers[index]
+    fn verify_pre_partial_sign(&self, index: usize, s_i: &PyBytes, T: &PyBytes, message: &PyBytes) -> PyResult<bool> {
+        let s_i: FE = ECScalar::from(&BigInt::from_bytes_be(s_i.as_bytes()));
+        let T: GE = bytes2point(T.as_bytes())?;
+        Ok(self.inner.verify_pre_partial_sign(index, &s_i, &T, message.as_bytes()))
+    }
 
-    #[test]
-    fn test_zerofill_single_sig() {
-        // let sk = b"\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x01";
-        let pk = b"\x02y\xbef~\xf9\xdc\xbb\xacU\xa0b\x95\xce\x87\x0b\x07\x02\x9b\xfc\xdb-\xce(\xd9Y\xf2\x81[\x16\xf8\x17\x98";
-        let msg = b"\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00";
-        let sig_a = b"xz\x84\x8eq\x04=(\x0cPG\x0e\x8e\x152\xb2\xdd] \xee\x91*E\xdb\xdd+\xd1\xdf\xbf\x18~\xf6";  // r
-        let sig_b = b"p1\xa9\x881\x85\x9d\xc3M\xff\xee\xdd\xa8h1\x84,\xcd\x00y\xe1\xf9*\xf1w\xf7\xf2,\xc1\xdc\xed\x05";  // s
+    /// combine_pre_signature_parts(parts: list, T: bytes, message: bytes) -> tuple
+    /// --
+    ///
+    /// check all pre-signature parts (same order as signers)
+    /// return R(33b) without T and pre-signature s'(32b)
+    /// raise InvalidShareError with indexes of faulty signers
+    fn combine_pre_signature_parts(&self, _py: Python, parts: &PyAny, T: &PyBytes, message: &PyBytes) -> PyResult<PyObject> {
+        let parts = pylist2bigint(parts)?;
+        let T: GE = bytes2point(T.as_bytes())?;
+        let pre_sig = self.inner.combine_pre_partial_signs(&parts, &T, message.as_bytes())?;
+        Ok(PyTuple::new(_py, &[
+            PyBytes::new(_py, &point2bytes(&pre_sig.R, KeyType::SingleSig)),
+            PyBytes::new(_py, &bigint2bytes(&pre_sig.s.to_big_int()).unwrap()),
+        ]).to_object(_py))
+    }
 
-        let pk = bytes2point_inner(pk).unwrap();
-        let sig_a = BigInt::from_bytes_be(sig_a);
-        let sig_b = BigInt::from_bytes_be(sig_b);
-        assert!(verify_aggregate_signature(&sig_b, &sig_a, &pk, msg, false).is_ok());
+    /// wipe() -> None
+    /// --
+    ///
+    /// wipe secret key and ephemeral key, do not use this object again
+    fn wipe(&mut self) {
+        self.inner.wipe();
     }
 }
@@ -0,0 +1,210 @@
+use crate::bip32::*;
+use crate::pykeypair::PyKeyPair;
+use crate::pyo3utils::bytes2point;
+use crate::utils::{bigint2bytes, decode_public_bytes, point2bytes};
+use emerald_city::curv::elliptic::curves::traits::ECScalar;
+use pyo3::prelude::*;
+use pyo3::exceptions::ValueError;
+use pyo3::types::{PyBytes, PyTuple, PyType};
+
+
+#[pyclass]
+pub struct PyExtendedPrivKey {
+    pub inner: ExtendedPrivKey,
+}
+
+#[pymethods]
+impl PyExtendedPrivKey {
+    /// from_seed(seed: bytes) -> PyExtendedPrivKey
+    /// --
+    ///
+    /// BIP-32 master key from 16~64bytes seed
+    #[classmethod]
+    fn from_seed(_cls: &PyType, seed: &PyBytes) -> PyResult<PyExtendedPrivKey> {
+        let inner = ExtendedPrivKey::from_seed(seed.as_bytes())?;
+        Ok(PyExtendedPrivKey {inner})
+    }
+
+    /// from_xprv(xprv: str) -> PyExtendedPrivKey
+    /// --
+    ///
+    /// restore from base58check xprv
+    #[classmethod]
+    fn from_xprv(_cls: &PyType, xprv: &str) -> PyResult<PyExtendedPrivKey> {
+        let inner = ExtendedPrivKey::from_xprv(xprv)?;
+        Ok(PyExtendedPrivKey {inner})
+    }
+
+    /// to_xprv() -> str
+    /// --
+    ///
+    /// base58check xprv
+    fn to_xprv(&self) -> String {
+        self.inner.to_xprv()
+    }
+
+    #[getter]
+    fn keypair(&self) -> PyKeyPair {
+        PyKeyPair {inner: self.inner.keypair.clone()}
+    }
+
+    #[getter]
+    fn chain_code(&self, _py: Python) -> PyObject {
+        PyBytes::new(_py, &self.inner.chain_code).to_object(_py)
+    }
+
+    #[getter]
+    fn depth(&self) -> u8 {
+        self.inner.depth
+    }
+
+    #[getter]
+    fn child_number(&self) -> u32 {
+        self.inner.child_number
+    }
+
+    #[getter]
+    fn parent_fingerprint(&self, _py: Python) -> PyObject {
+        PyBytes::new(_py, &self.inner.parent_fingerprint).to_object(_py)
+    }
+
+    /// fingerprint() -> bytes
+    /// --
+    ///
+    /// first 4bytes of HASH160(public key)
+    fn fingerprint(&self, _py: Python) -> PyObject {
+        PyBytes::new(_py, &self.inner.fingerprint()).to_object(_py)
+    }
+
+    /// derive_child(index: int) -> PyExtendedPrivKey
+    /// --
+    ///
+    /// hardened when index >= 0x80000000
+    fn derive_child(&self, index: u32) -> PyResult<PyExtendedPrivKey> {
+        let inner = self.inner.derive_child(index)?;
+        Ok(PyExtendedPrivKey {inner})
+    }
+
+    /// derive_path(path: str) -> PyExtendedPrivKey
+    /// --
+    ///
+    /// path: ex. "m/44'/0'/0'/0/1"
+    fn derive_path(&self, path: &str) -> PyResult<PyExtendedPrivKey> {
+        let inner = self.inner.derive_path(&parse_path(path)?)?;
+        Ok(PyExtendedPrivKey {inner})
+    }
+
+    /// public() -> PyExtendedPubKey
+    /// --
+    ///
+    /// neutered extended key
+    fn public(&self) -> PyExtendedPubKey {
+        PyExtendedPubKey {inner: self.inner.public()}
+    }
+
+    /// wipe() -> None
+    /// --
+    ///
+    /// wipe secret key and chain code, do not use this object again
+    fn wipe(&mut self) {
+        self.inner.keypair.wipe();
+        self.inner.chain_code = [0u8;32];
+    }
+}
+
+
+#[pyclass]
+pub struct PyExtendedPubKey {
+    pub inner: ExtendedPubKey,
+}
+
+#[pymethods]
+impl PyExtendedPubKey {
+    /// from_group_key(public: bytes, chain_code: bytes = None) -> PyExtendedPubKey
+    /// --
+    ///
+    /// root of apk (0x05) or Y (0x08) to derive children without key generation
+    /// chain_code: 32bytes shared by signers, derived from public when None
+    #[classmethod]
+    fn from_group_key(_cls: &PyType, public: &PyBytes, chain_code: Option<&PyBytes>) -> PyResult<PyExtendedPubKey> {
+        let (key_type, _prefix) = decode_public_bytes(public.as_bytes())?;
+        let point = bytes2point(public.as_bytes())?;
+        let chain_code = match chain_code {
+            Some(chain_code) => {
+                let chain_code = chain_code.as_bytes();
+                if chain_code.len() != 32 {
+                    return Err(ValueError::py_err("chain_code is not 32bytes"));
+                }
+                let mut bytes = [0u8;32];
+                bytes.copy_from_slice(chain_code);
+                Some(bytes)
+            },
+            None => None,
+        };
+        let inner = ExtendedPubKey::from_group_key(&point, key_type, chain_code);
+        Ok(PyExtendedPubKey {inner})
+    }
+
+    /// from_xpub(xpub: str) -> PyExtendedPubKey
+    /// --
+    ///
+    /// restore from base58check xpub
+    #[classmethod]
+    fn from_xpub(_cls: &PyType, xpub: &str) -> PyResult<PyExtendedPubKey> {
+        let inner = ExtendedPubKey::from_xpub(xpub)?;
+        Ok(PyExtendedPubKey {inner})
+    }
+
+    /// to_xpub() -> str
+    /// --
+    ///
+    /// base58check xpub, group keys keep 0x05 or 0x08 prefix
+    fn to_xpub(&self) -> String {
+        self.inner.to_xpub()
+    }
+
+    #[getter]
+    fn public_key(&self, _py: Python) -> PyObject {
+        PyBytes::new(_py, &point2bytes(&self.inner.public, self.inner.key_type)).to_object(_py)
+    }
+
+    #[getter]
+    fn chain_code(&self, _py: Python) -> PyObject {
+        PyBytes::new(_py, &self.inner.chain_code).to_object(_py)
+    }
+
+    #[getter]
+    fn depth(&self) -> u8 {
+        self.inner.depth
+    }
+
+    #[getter]
+    fn child_number(&self) -> u32 {
+        self.inner.child_number
+    }
+
+    #[getter]
+    fn parent_fingerprint(&self, _py: Python) -> PyObject {
+        PyBytes::new(_py, &self.inner.parent_fingerprint).to_object(_py)
+    }
+
+    /// fingerprint() -> bytes
+    /// --
+    ///
+    /// first 4bytes of HASH160(public key)
+    fn fingerprint(&self, _py: Python) -> PyObject {
+        PyBytes::new(_py, &self.inner.fingerprint()).to_object(_py)
+    }
+
+    /// derive_path(path: str) -> tuple
+    /// --
+    ///
+    /// non-hardened derivation, return child PyExtendedPubKey and tweak(32b)
+    /// signers of group key sign for child by PyAggregate.tweak_add(tweak) or summarize_local_signature(..., tweak)
+    fn derive_path(&self, _py: Python, path: &str) -> PyResult<PyObject> {
+        let (child, tweak) = self.inner.derive_path(&parse_path(path)?)?;
+        let child = Py::new(_py, PyExtendedPubKey {inner: child})?;
+        let tweak = PyBytes::new(_py, &bigint2bytes(&tweak.to_big_int()).unwrap());
+        Ok(PyTuple::new(_py, &[child.to_object(_py), tweak.to_object(_py)]).to_object(_py))
+    }
+}
@@ -0,0 +1,102 @@
+use crate::blind::{BlindSigner, BlindUser};
+use crate::hashing::encode_r;
+use crate::pyo3utils::bytes2point;
+use crate::utils::{bigint2bytes, point2bytes, KeyType};
+use emerald_city::curv::elliptic::curves::secp256_k1::{FE, GE};
+use emerald_city::curv::elliptic::curves::traits::ECScalar;
+use emerald_city::curv::arithmetic::num_bigint::BigInt;
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyTuple, PyType};
+
+
+#[pyclass]
+pub struct PyBlindSigner {
+    pub inner: BlindSigner,
+}
+
+#[pymethods]
+impl PyBlindSigner {
+    /// commit() -> bytes
+    /// --
+    ///
+    /// open session and return R(33b)
+    /// raise error while a session of this key is open, concurrent sessions allow ROS attack
+    fn commit(&mut self, _py: Python) -> PyResult<PyObject> {
+        let R = self.inner.commit()?;
+        Ok(PyBytes::new(_py, &point2bytes(&R, KeyType::SingleSig)).to_object(_py))
+    }
+
+    /// sign(c: bytes) -> bytes
+    /// --
+    ///
+    /// return s(32b) for blinded challenge of PyBlindUser and close session
+    fn sign(&mut self, _py: Python, c: &PyBytes) -> PyResult<PyObject> {
+        let c: FE = ECScalar::from(&BigInt::from_bytes_be(c.as_bytes()));
+        let s = self.inner.sign(&c)?;
+        Ok(PyBytes::new(_py, &bigint2bytes(&s.to_big_int()).unwrap()).to_object(_py))
+    }
+
+    /// abort() -> None
+    /// --
+    ///
+    /// close my session without signing, nonce is wiped
+    /// session opened by another signer of the key is not closed
+    fn abort(&mut self) {
+        self.inner.abort();
+    }
+
+    #[getter]
+    fn is_open(&self) -> bool {
+        self.inner.is_open()
+    }
+
+    /// wipe() -> None
+    /// --
+    ///
+    /// wipe secret key and nonce, do not use this object again
+    fn wipe(&mut self) {
+        self.inner.abort();
+        self.inner.keypair.wipe();
+    }
+}
+
+
+#[pyclass]
+pub struct PyBlindUser {
+    pub inner: BlindUser,
+}
+
+#[pymethods]
+impl PyBlindUser {
+    /// generate(public: bytes, R: bytes, message: bytes) -> PyBlindUser
+    /// --
+    ///
+    /// blind signer's R by random alpha and beta, send challenge to signer
+    #[classmethod]
+    fn generate(_cls: &PyType, public: &PyBytes, R: &PyBytes, message: &PyBytes) -> PyResult<PyBlindUser> {
+        let public: GE = bytes2point(public.as_bytes())?;
+        let R: GE = bytes2point(R.as_bytes())?;
+        let inner = BlindUser::new(&public, &R, message.as_bytes())?;
+        Ok(PyBlindUser {inner})
+    }
+
+    /// blinded challenge c(32b)
+    #[getter]
+    fn challenge(&self, _py: Python) -> PyObject {
+        PyBytes::new(_py, &bigint2bytes(&self.inner.c.to_big_int()).unwrap()).to_object(_py)
+    }
+
+    /// unblind(s: bytes) -> tuple
+    /// --
+    ///
+    /// check signer's s and return versioned R.x(33b) and s(32b)
+    /// verified by verify_aggregate_sign with signer's public key
+    fn unblind(&self, _py: Python, s: &PyBytes) -> PyResult<PyObject> {
+        let s: FE = ECScalar::from(&BigInt::from_bytes_be(s.as_bytes()));
+        let (R, s) = self.inner.unblind(&s)?;
+        Ok(PyTuple::new(_py, &[
+            PyBytes::new(_py, &encode_r(&bigint2bytes(&R).unwrap())),
+            PyBytes::new(_py, &bigint2bytes(&s.to_big_int()).unwrap()),
+        ]).to_object(_py))
+    }
+}
@@ -0,0 +1,77 @@
+use crate::error::Error;
+use pyo3::prelude::*;
+use pyo3::create_exception;
+use pyo3::exceptions::ValueError;
+
+
+// all exceptions inherit ValueError, old `except ValueError` still works
+create_exception!(multi_party_schnorr, SchnorrError, ValueError);
+create_exception!(multi_party_schnorr, InvalidPointError, SchnorrError);
+create_exception!(multi_party_schnorr, InvalidScalarError, SchnorrError);
+create_exception!(multi_party_schnorr, LengthMismatchError, SchnorrError);
+create_exception!(multi_party_schnorr, VssValidationError, SchnorrError);
+create_exception!(multi_party_schnorr, CommitmentError, SchnorrError);
+create_exception!(multi_party_schnorr, InvalidShareError, SchnorrError);
+create_exception!(multi_party_schnorr, InvalidProofError, SchnorrError);
+create_exception!(multi_party_schnorr, UnknownKeyPrefixError, SchnorrError);
+create_exception!(multi_party_schnorr, InvalidSignatureError, SchnorrError);
+create_exception!(multi_party_schnorr, DecodeError, SchnorrError);
+
+
+/// exception with extra attributes (ex. `err.party_index`)
+fn with_attrs(py: Python, err: PyErr, attrs: &[(&str, PyObject)]) -> PyErr {
+    let instance = err.instance(py);
+    for (name, value) in attrs {
+        if let Err(err) = instance.setattr(py, *name, value) {
+            return err;
+        }
+    }
+    PyErr::from_instance(instance.as_ref(py))
+}
+
+impl From<Error> for PyErr {
+    fn from(err: Error) -> PyErr {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let message = err.to_string();
+        match err {
+            Error::InvalidPoint(_) => InvalidPointError::py_err(message),
+            Error::InvalidScalar(_) => InvalidScalarError::py_err(message),
+            Error::LengthMismatch { name, expected, actual } => with_attrs(
+                py, LengthMismatchError::py_err(message), &[
+                    ("name", name.to_object(py)),
+                    ("expected", expected.to_object(py)),
+                    ("actual", actual.to_object(py)),
+                ]),
+            Error::VssValidation { party_index } => with_attrs(
+                py, VssValidationError::py_err(message), &[("party_index", party_index.to_object(py))]),
+            Error::Commitment { party_index } => with_attrs(
+                py, CommitmentError::py_err(message), &[("party_index", party_index.to_object(py))]),
+            Error::InvalidShare { party_index } => with_attrs(
+                py, InvalidShareError::py_err(message), &[("party_index", party_index.to_object(py))]),
+            Error::InvalidProof { party_index } => with_attrs(
+                py, InvalidProofError::py_err(message), &[("party_index", party_index.to_object(py))]),
+            Error::UnknownKeyPrefix(prefix) => with_attrs(
+                py, UnknownKeyPrefixError::py_err(message), &[("prefix", prefix.to_object(py))]),
+            Error::InvalidSignature(_) => InvalidSignatureError::py_err(message),
+            Error::InvalidParams(_) => SchnorrError::py_err(message),
+            Error::Decode(_) => DecodeError::py_err(message),
+        }
+    }
+}
+
+/// register exception types to module
+pub fn add_exceptions(py: Python, m: &PyModule) -> PyResult<()> {
+    m.add("SchnorrError", py.get_type::<SchnorrError>())?;
+    m.add("InvalidPointError", py.get_type::<InvalidPointError>())?;
+    m.add("InvalidScalarError", py.get_type::<InvalidScalarError>())?;
+    m.add("LengthMismatchError", py.get_type::<LengthMismatchError>())?;
+    m.add("VssValidationError", py.get_type::<VssValidationError>())?;
+    m.add("CommitmentError", py.get_type::<CommitmentError>())?;
+    m.add("InvalidShareError", py.get_type::<InvalidShareError>())?;
+    m.add("InvalidProofError", py.get_type::<InvalidProofError>())?;
+    m.add("UnknownKeyPrefixError", py.get_type::<UnknownKeyPrefixError>())?;
+    m.add("InvalidSignatureError", py.get_type::<InvalidSignatureError>())?;
+    m.add("DecodeError", py.get_type::<DecodeError>())?;
+    Ok(())
+}
@@ -1,13 +1,16 @@
-use crate::pyo3utils::{bytes2point, bigint2bytes};
-use crate::verifyutils::*;
-use emerald_city::curv::cryptographic_primitives::hashing::{
-    hash_sha256::HSha256,
-    traits::Hash,
-};
-use emerald_city::curv::elliptic::curves::secp256_k1::{FE, GE};
+use crate::blind::BlindSigner;
+use crate::ecies;
+use crate::error::Error;
+use crate::hashing::encode_r;
+use crate::keypair::KeyPair;
+use crate::keystore::{single_to_bytes, single_from_bytes, single_to_json, single_from_json};
+use crate::pyblind::PyBlindSigner;
+use crate::pyo3utils::bytes2point;
+use crate::utils::{bigint2bytes, point2bytes, KeyType};
+use emerald_city::curv::arithmetic::traits::Samplable;
+use emerald_city::curv::elliptic::curves::secp256_k1::GE;
 use emerald_city::curv::elliptic::curves::traits::{ECPoint, ECScalar};
 use emerald_city::curv::arithmetic::num_bigint::BigInt;
-use num_traits::{Zero, One};
 use pyo3::prelude::*;
 use pyo3::types::{PyBytes, PyType, PyTuple};
 
@@ -15,8 +18,7 @@ use pyo3::types::{PyBytes, PyType, PyTuple};
 #[pyclass]
 #[derive(Clone)]
 pub struct PyKeyPair {
-    pub secret: FE,
-    pub public: GE,
+    pub inner: KeyPair,
 }
 
 #[pymethods]
@@ -32,11 +34,8 @@ impl PyKeyPair {
     /// generate keypair from secret key
     #[classmethod]
     fn from_secret_key(_cls: &PyType, secret: &PyBytes) -> PyResult<PyKeyPair> {
-        let secret = secret.as_bytes();
-        let ec_point: GE = ECPoint::generator();
-        let secret: FE = ECScalar::from(&BigInt::from_bytes_be(secret));
-        let public: GE = ec_point.scalar_mul(&secret.get_element());
-        Ok(PyKeyPair {secret, public})
+        let inner = KeyPair::from_secret_key(secret.as_bytes());
+        Ok(PyKeyPair {inner})
     }
 
     /// get_secret_key() -> bytes
@@ -44,8 +43,7 @@ impl PyKeyPair {
     ///
     /// get secret key
     fn get_secret_key(&self, _py: Python) -> PyObject {
-        let secret = self.secret.to_big_int();
-        let bytes = bigint2bytes(&secret).unwrap();
+        let bytes = self.inner.secret.to_bytes();
         PyBytes::new(_py, &bytes).to_object(_py)
     }
 
@@ -54,43 +52,69 @@ impl PyKeyPair {
     ///
     /// get public key
     fn get_public_key(&self, _py: Python) -> PyObject {
-        let public = self.public.get_element().serialize();
+        let public = self.inner.public.get_element().serialize();
         PyBytes::new(_py, &public).to_object(_py)
     }
 
-    /// get_single_sign(message: bytes) -> tuple
+    /// get_single_sign(message: bytes, aux_rand: bytes = None) -> tuple
     /// --
     ///
     /// get signature from single signer
-    /// return R(32b) and s(32b)
-    fn get_single_sign(&self, _py: Python, message: &PyBytes) -> PyObject {
-        let message = message.as_bytes();
-        let base_point: GE = ECPoint::generator();
-        let hash_private_key_message =
-            HSha256::create_hash(&[&self.secret.to_big_int(), &BigInt::from_bytes_be(message)]);
-        let ephemeral_private_key: FE = ECScalar::from(&hash_private_key_message);
-        let ephemeral_public_key = base_point.scalar_mul(&ephemeral_private_key.get_element());
-        //let (commitment, blind_factor) =
-        //    HashCommitment::create_commitment(&ephemeral_public_key.bytes_compressed_to_big_int());
-        // compute c = H0(Rtag || apk || message)
-        let c = ephemeral_hash_0(
-            &ephemeral_public_key,
-            &self.public,
-            message,
-            false,
-        );
-        // sign
-        let c_fe: FE = ECScalar::from(&c);
-        let a_fe: FE = ECScalar::from(&BigInt::one());
-        let s_fe = ephemeral_private_key.clone() + (c_fe * self.secret.clone() * a_fe);
-        let s_tag = s_fe.to_big_int();
-        // signature s:
-        let R = ephemeral_public_key.x_coor().unwrap();
-        let s = add_scalar_parts(s_tag, &BigInt::zero());
-        PyTuple::new(_py, &[
-            PyBytes::new(_py, &bigint2bytes(&R).unwrap()),
+    /// aux_rand: 32b randomness mixed in nonce, random if None
+    /// return versioned R.x(33b) and s(32b)
+    fn get_single_sign(&self, _py: Python, message: &PyBytes, aux_rand: Option<&PyBytes>) -> PyResult<PyObject> {
+        let aux_rand = aux_rand_bytes(aux_rand)?;
+        let (R, s) = self.inner.single_sign(message.as_bytes(), &aux_rand)?;
+        Ok(PyTuple::new(_py, &[
+            PyBytes::new(_py, &encode_r(&bigint2bytes(&R).unwrap())),
             PyBytes::new(_py, &bigint2bytes(&s).unwrap()),
-        ]).to_object(_py)
+        ]).to_object(_py))
+    }
+
+    /// get_adaptor_pre_sign(message: bytes, T: bytes, aux_rand: bytes = None) -> tuple
+    /// --
+    ///
+    /// get adaptor pre-signature, valid signature needs t of adaptor point T
+    /// return R(33b) without T and pre-signature s'(32b)
+    fn get_adaptor_pre_sign(&self, _py: Python, message: &PyBytes, T: &PyBytes, aux_rand: Option<&PyBytes>)
+        -> PyResult<PyObject> {
+        let aux_rand = aux_rand_bytes(aux_rand)?;
+        let T: GE = bytes2point(T.as_bytes())?;
+        let pre_sig = self.inner.single_pre_sign(&T, message.as_bytes(), &aux_rand)?;
+        Ok(PyTuple::new(_py, &[
+            PyBytes::new(_py, &point2bytes(&pre_sig.R, KeyType::SingleSig)),
+            PyBytes::new(_py, &bigint2bytes(&pre_sig.s.to_big_int()).unwrap()),
+        ]).to_object(_py))
+    }
+
+    /// tweak(commitment: bytes) -> PyKeyPair
+    /// --
+    ///
+    /// Taproot-style tweaked keypair, public key is Q = P + H_tweak(P || commitment)*G
+    fn tweak(&self, commitment: &PyBytes) -> PyResult<PyKeyPair> {
+        let inner = self.inner.tweak(commitment.as_bytes())?;
+        Ok(PyKeyPair {inner})
+    }
+
+    /// get_xonly_public_key() -> bytes
+    /// --
+    ///
+    /// get BIP-340 x-only public key (32b)
+    fn get_xonly_public_key(&self, _py: Python) -> PyObject {
+        let public = self.inner.bip340_public_key();
+        PyBytes::new(_py, &public).to_object(_py)
+    }
+
+    /// get_bip340_sign(message: bytes, aux_rand: bytes = None) -> bytes
+    /// --
+    ///
+    /// get BIP-340 signature from single signer
+    /// return R.x(32b) + s(32b)
+    fn get_bip340_sign(&self, _py: Python, message: &PyBytes, aux_rand: Option<&PyBytes>) -> PyResult<PyObject> {
+        let aux_rand = aux_rand_bytes(aux_rand)?;
+        let signature = self.inner.bip340_sign(message.as_bytes(), &aux_rand)
+            .map_err(PyErr::from)?;
+        Ok(PyBytes::new(_py, &signature).to_object(_py))
     }
 
     /// get_shared_point(public: bytes) -> bytes
@@ -100,18 +124,108 @@ impl PyKeyPair {
     fn get_shared_point(&self, _py: Python, public: &PyBytes) -> PyResult<PyObject> {
         // note: do not forget to pass through a hash function
         let public: GE = bytes2point(public.as_bytes())?;
-        let point: GE = public.scalar_mul(&self.secret.get_element());
+        let point = self.inner.shared_point(&public);
         let point = point.get_element().serialize();
         Ok(PyBytes::new(_py, &point).to_object(_py))
     }
+
+    /// encrypt(public: bytes, plaintext: bytes, aad: bytes = None) -> bytes
+    /// --
+    ///
+    /// ECIES to public key, ephemeral ECDH + HKDF-SHA256 + ChaCha20-Poly1305
+    /// return ephemeral(33b) + ciphertext + tag(16b), this keypair is not used
+    #[classmethod]
+    fn encrypt(_cls: &PyType, _py: Python, public: &PyBytes, plaintext: &PyBytes, aad: Option<&PyBytes>)
+        -> PyResult<PyObject> {
+        let public: GE = bytes2point(public.as_bytes())?;
+        let aad = aad.map(|a| a.as_bytes()).unwrap_or(&b""[..]);
+        let ciphertext = ecies::encrypt(&public, plaintext.as_bytes(), aad)?;
+        Ok(PyBytes::new(_py, &ciphertext).to_object(_py))
+    }
+
+    /// decrypt(ciphertext: bytes, aad: bytes = None) -> bytes
+    /// --
+    ///
+    /// decrypt ECIES ciphertext to my public key, raise DecodeError if broken
+    fn decrypt(&self, _py: Python, ciphertext: &PyBytes, aad: Option<&PyBytes>) -> PyResult<PyObject> {
+        let aad = aad.map(|a| a.as_bytes()).unwrap_or(&b""[..]);
+        let plaintext = ecies::decrypt(&self.inner, ciphertext.as_bytes(), aad)?;
+        Ok(PyBytes::new(_py, &plaintext).to_object(_py))
+    }
+
+    /// get_blind_signer() -> PyBlindSigner
+    /// --
+    ///
+    /// signer of blind Schnorr signature, one session at a time for all signers of this key
+    fn get_blind_signer(&self) -> PyBlindSigner {
+        PyBlindSigner {inner: BlindSigner::new(&self.inner)}
+    }
+
+    /// to_bytes() -> bytes
+    /// --
+    ///
+    /// versioned binary format of secret key
+    fn to_bytes(&self, _py: Python) -> PyObject {
+        PyBytes::new(_py, &single_to_bytes(&self.inner)).to_object(_py)
+    }
+
+    /// from_bytes(data: bytes) -> PyKeyPair
+    /// --
+    ///
+    /// restore keypair of to_bytes()
+    #[classmethod]
+    fn from_bytes(_cls: &PyType, data: &PyBytes) -> PyResult<PyKeyPair> {
+        let inner = single_from_bytes(data.as_bytes())?;
+        Ok(PyKeyPair {inner})
+    }
+
+    /// to_json() -> str
+    /// --
+    ///
+    /// versioned json format of secret key
+    fn to_json(&self) -> String {
+        single_to_json(&self.inner)
+    }
+
+    /// from_json(data: str) -> PyKeyPair
+    /// --
+    ///
+    /// restore keypair of to_json()
+    #[classmethod]
+    fn from_json(_cls: &PyType, data: &str) -> PyResult<PyKeyPair> {
+        let inner = single_from_json(data)?;
+        Ok(PyKeyPair {inner})
+    }
+
+    /// wipe() -> None
+    /// --
+    ///
+    /// wipe secret key, do not use this object again
+    fn wipe(&mut self) {
+        self.inner.wipe();
+    }
+}
+
+/// 32bytes aux_rand of caller or fresh random
+fn aux_rand_bytes(aux_rand: Option<&PyBytes>) -> PyResult<[u8;32]> {
+    match aux_rand {
+        Some(aux_rand) => {
+            let aux_rand = aux_rand.as_bytes();
+            if aux_rand.len() != 32 {
+                return Err(Error::LengthMismatch {
+                    name: "aux_rand", expected: 32, actual: aux_rand.len() }.into());
+            }
+            let mut bytes = [0u8;32];
+            bytes.copy_from_slice(aux_rand);
+            Ok(bytes)
+        },
+        None => Ok(bigint2bytes(&BigInt::sample(256)).unwrap()),
+    }
 }
 
 pub fn generate_keypair(_py: Python) -> PyKeyPair {
     // release GIL
     _py.allow_threads(move || {
-        let ec_point: GE = ECPoint::generator();
-        let secret: FE = ECScalar::new_random();
-        let public: GE = ec_point.scalar_mul(&secret.get_element());
-        PyKeyPair {secret, public}
+        PyKeyPair {inner: KeyPair::new()}
     })
 }
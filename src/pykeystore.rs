@@ -0,0 +1,451 @@
+use crate::hashing::decode_r;
+use crate::keystore::*;
+use crate::pykeypair::*;
+use crate::pyo3utils::*;
+use crate::pythreshold::PyThresholdKey;
+use crate::refresh::{
+    apply_refresh, apply_reshare, recover_share, recovery_contribution, recovery_dealing, refresh_dealing,
+    reshare_dealing,
+};
+use crate::secret::{SecretScalar, Wipe};
+use crate::utils::{bigint2bytes, point2bytes, KeyType};
+use emerald_city::curv::elliptic::curves::secp256_k1::GE;
+use emerald_city::curv::elliptic::curves::traits::ECScalar;
+use emerald_city::curv::arithmetic::num_bigint::BigInt;
+use pyo3::prelude::*;
+use pyo3::exceptions::ValueError;
+use pyo3::types::{PyBytes, PyTuple, PyType};
+
+
+#[pyclass]
+pub struct PyAggregateKey {
+    pub inner: AggregateKey,
+}
+
+#[pymethods]
+impl PyAggregateKey {
+    /// generate(keypair: PyKeyPair, signers: list) -> PyAggregateKey
+    /// --
+    ///
+    /// n of n key to store, signers are all signer's public keys
+    #[classmethod]
+    fn generate(_cls: &PyType, keypair: &PyKeyPair, signers: &PyAny) -> PyResult<PyAggregateKey> {
+        let signers = pylist2points(signers)?;
+        let inner = AggregateKey::new(&keypair.inner, &signers)?;
+        Ok(PyAggregateKey {inner})
+    }
+
+    #[getter]
+    fn keypair(&self) -> PyKeyPair {
+        PyKeyPair {inner: self.inner.keypair.clone()}
+    }
+
+    #[getter]
+    fn signers(&self, _py: Python) -> PyObject {
+        let signers: Vec<&PyBytes> = self.inner.signers.iter()
+            .map(|p| PyBytes::new(_py, &point2bytes(p, KeyType::SingleSig)))
+            .collect();
+        PyTuple::new(_py, &signers).to_object(_py)
+    }
+
+    #[getter]
+    fn apk(&self, _py: Python) -> PyObject {
+        PyBytes::new(_py, &point2bytes(&self.inner.apk, KeyType::AggregateSig)).to_object(_py)
+    }
+
+    /// to_bytes() -> bytes
+    /// --
+    ///
+    /// versioned binary format
+    fn to_bytes(&self, _py: Python) -> PyObject {
+        PyBytes::new(_py, &aggregate_to_bytes(&self.inner)).to_object(_py)
+    }
+
+    /// from_bytes(data: bytes) -> PyAggregateKey
+    /// --
+    ///
+    /// restore and check apk with signers
+    #[classmethod]
+    fn from_bytes(_cls: &PyType, data: &PyBytes) -> PyResult<PyAggregateKey> {
+        let inner = aggregate_from_bytes(data.as_bytes())?;
+        Ok(PyAggregateKey {inner})
+    }
+
+    /// to_json() -> str
+    /// --
+    ///
+    /// versioned json format
+    fn to_json(&self) -> String {
+        aggregate_to_json(&self.inner)
+    }
+
+    /// from_json(data: str) -> PyAggregateKey
+    /// --
+    ///
+    /// restore and check apk with signers
+    #[classmethod]
+    fn from_json(_cls: &PyType, data: &str) -> PyResult<PyAggregateKey> {
+        let inner = aggregate_from_json(data)?;
+        Ok(PyAggregateKey {inner})
+    }
+
+    /// wipe() -> None
+    /// --
+    ///
+    /// wipe secret key, do not use this object again
+    fn wipe(&mut self) {
+        self.inner.keypair.wipe();
+    }
+}
+
+
+#[pyclass]
+pub struct PyAggregateKeyDescriptor {
+    pub inner: AggregateKeyDescriptor,
+}
+
+#[pymethods]
+impl PyAggregateKeyDescriptor {
+    /// generate(signers: list) -> PyAggregateKeyDescriptor
+    /// --
+    ///
+    /// n of n key from public keys only, no secret key is required
+    #[classmethod]
+    fn generate(_cls: &PyType, signers: &PyAny) -> PyResult<PyAggregateKeyDescriptor> {
+        let signers = pylist2points(signers)?;
+        let inner = AggregateKeyDescriptor::new(&signers)?;
+        Ok(PyAggregateKeyDescriptor {inner})
+    }
+
+    #[getter]
+    fn signers(&self, _py: Python) -> PyObject {
+        let signers: Vec<&PyBytes> = self.inner.signers.iter()
+            .map(|p| PyBytes::new(_py, &point2bytes(p, KeyType::SingleSig)))
+            .collect();
+        PyTuple::new(_py, &signers).to_object(_py)
+    }
+
+    #[getter]
+    fn apk(&self, _py: Python) -> PyObject {
+        PyBytes::new(_py, &self.inner.apk_bytes()).to_object(_py)
+    }
+
+    #[getter]
+    fn coefficients(&self, _py: Python) -> PyObject {
+        let coefficients: Vec<&PyBytes> = self.inner.coefficients.iter()
+            .map(|a| PyBytes::new(_py, &bigint2bytes(&a.to_big_int()).unwrap()))
+            .collect();
+        PyTuple::new(_py, &coefficients).to_object(_py)
+    }
+
+    /// coefficient(public: bytes) -> bytes
+    /// --
+    ///
+    /// coefficient a_i of signer's public key
+    fn coefficient(&self, _py: Python, public: &PyBytes) -> PyResult<PyObject> {
+        let public: GE = bytes2point(public.as_bytes())?;
+        let a = self.inner.coefficient(&public).ok_or(
+            ValueError::py_err("not found public key in signers"))?;
+        Ok(PyBytes::new(_py, &bigint2bytes(&a.to_big_int()).unwrap()).to_object(_py))
+    }
+
+    /// check_apk(apk: bytes) -> bool
+    /// --
+    ///
+    /// check published apk (with prefix) is made from these signers
+    fn check_apk(&self, apk: &PyBytes) -> bool {
+        self.inner.check_apk(apk.as_bytes()).is_ok()
+    }
+
+    /// verify(sig: bytes, R: bytes, message: bytes) -> bool
+    /// --
+    ///
+    /// verify signature made by exactly these signers
+    /// R: versioned 33bytes (legacy R is 32bytes)
+    fn verify(&self, sig: &PyBytes, R: &PyBytes, message: &PyBytes) -> PyResult<bool> {
+        let (version, R) = decode_r(R.as_bytes(), 32)?;
+        let sig = BigInt::from_bytes_be(sig.as_bytes());
+        let R = BigInt::from_bytes_be(R);
+        Ok(self.inner.verify(&sig, &R, message.as_bytes(), version).is_ok())
+    }
+
+    /// to_bytes() -> bytes
+    /// --
+    ///
+    /// versioned binary format
+    fn to_bytes(&self, _py: Python) -> PyObject {
+        PyBytes::new(_py, &descriptor_to_bytes(&self.inner)).to_object(_py)
+    }
+
+    /// from_bytes(data: bytes) -> PyAggregateKeyDescriptor
+    /// --
+    ///
+    /// restore and check apk with signers
+    #[classmethod]
+    fn from_bytes(_cls: &PyType, data: &PyBytes) -> PyResult<PyAggregateKeyDescriptor> {
+        let inner = descriptor_from_bytes(data.as_bytes())?;
+        Ok(PyAggregateKeyDescriptor {inner})
+    }
+
+    /// to_json() -> str
+    /// --
+    ///
+    /// versioned json format
+    fn to_json(&self) -> String {
+        descriptor_to_json(&self.inner)
+    }
+
+    /// from_json(data: str) -> PyAggregateKeyDescriptor
+    /// --
+    ///
+    /// restore and check apk with signers
+    #[classmethod]
+    fn from_json(_cls: &PyType, data: &str) -> PyResult<PyAggregateKeyDescriptor> {
+        let inner = descriptor_from_json(data)?;
+        Ok(PyAggregateKeyDescriptor {inner})
+    }
+}
+
+
+#[pyclass]
+pub struct PyThresholdShare {
+    pub inner: ThresholdShare,
+}
+
+#[pymethods]
+impl PyThresholdShare {
+    /// generate(key: PyThresholdKey, share: bytes, Y: bytes, vss_points: list) -> PyThresholdShare
+    /// --
+    ///
+    /// collect results of keygen_t_n_parties to store
+    /// share: return of keygen_t_n_parties
+    /// Y: return of summarize_public_points
+    /// vss_points: all dealer's vss points
+    #[classmethod]
+    fn generate(_cls: &PyType, key: &PyThresholdKey, share: &PyBytes, Y: &PyBytes, vss_points: &PyAny)
+        -> PyResult<PyThresholdShare> {
+        let key = &key.inner;
+        let my_index = key.my_index.ok_or(
+            ValueError::py_err("keygen_t_n_parties is not finished"))?;
+        let inner = ThresholdShare {
+            t: key.t,
+            n: key.n,
+            my_index,
+            parties_index: key.parties_index.iter().map(|i| i - 1).collect(),
+            share: SecretScalar::new(ECScalar::from(&BigInt::from_bytes_be(share.as_bytes()))),
+            Y: bytes2point(Y.as_bytes())?,
+            vss_points: pylist2vss(key.t, key.n, vss_points)?,
+        };
+        inner.check_consistency()?;
+        Ok(PyThresholdShare {inner})
+    }
+
+    #[getter]
+    fn t(&self) -> usize {
+        self.inner.t
+    }
+
+    #[getter]
+    fn n(&self) -> usize {
+        self.inner.n
+    }
+
+    #[getter]
+    fn my_index(&self) -> usize {
+        self.inner.my_index
+    }
+
+    #[getter]
+    fn parties_index(&self) -> Vec<usize> {
+        self.inner.parties_index.clone()
+    }
+
+    #[getter]
+    fn share(&self, _py: Python) -> PyObject {
+        let bytes = self.inner.share.to_bytes();
+        PyBytes::new(_py, &bytes).to_object(_py)
+    }
+
+    #[getter]
+    fn Y(&self, _py: Python) -> PyObject {
+        PyBytes::new(_py, &point2bytes(&self.inner.Y, KeyType::ThresholdSig)).to_object(_py)
+    }
+
+    #[getter]
+    fn vss_points(&self, _py: Python) -> PyObject {
+        let vss_points: Vec<&PyTuple> = self.inner.vss_points.iter()
+            .map(|vss| {
+                let points: Vec<&PyBytes> = vss.commitments.iter()
+                    .map(|p| PyBytes::new(_py, &point2bytes(p, KeyType::SingleSig)))
+                    .collect();
+                PyTuple::new(_py, &points)
+            })
+            .collect();
+        PyTuple::new(_py, &vss_points).to_object(_py)
+    }
+
+    /// to_bytes() -> bytes
+    /// --
+    ///
+    /// versioned binary format
+    fn to_bytes(&self, _py: Python) -> PyObject {
+        PyBytes::new(_py, &threshold_to_bytes(&self.inner)).to_object(_py)
+    }
+
+    /// from_bytes(data: bytes) -> PyThresholdShare
+    /// --
+    ///
+    /// restore and check share and Y with vss commitments
+    #[classmethod]
+    fn from_bytes(_cls: &PyType, data: &PyBytes) -> PyResult<PyThresholdShare> {
+        let inner = threshold_from_bytes(data.as_bytes())?;
+        Ok(PyThresholdShare {inner})
+    }
+
+    /// to_json() -> str
+    /// --
+    ///
+    /// versioned json format
+    fn to_json(&self) -> String {
+        threshold_to_json(&self.inner)
+    }
+
+    /// from_json(data: str) -> PyThresholdShare
+    /// --
+    ///
+    /// restore and check share and Y with vss commitments
+    #[classmethod]
+    fn from_json(_cls: &PyType, data: &str) -> PyResult<PyThresholdShare> {
+        let inner = threshold_from_json(data)?;
+        Ok(PyThresholdShare {inner})
+    }
+
+    /// get_refresh_dealing() -> tuple
+    /// --
+    ///
+    /// return vss_point(list of 33b) and delta scalars(list of 32b) of zero-constant polynomial
+    /// send deltas[j] to party of parties_index[j], broadcast vss_point
+    fn get_refresh_dealing(&self, _py: Python) -> PyObject {
+        let (vss, mut deltas) = refresh_dealing(&self.inner);
+        let vss_point: Vec<&PyBytes> = vss.commitments.iter()
+            .map(|p| PyBytes::new(_py, &point2bytes(p, KeyType::SingleSig)))
+            .collect();
+        let deltas_bytes: Vec<&PyBytes> = deltas.iter()
+            .map(|d| PyBytes::new(_py, &bigint2bytes(&d.to_big_int()).unwrap()))
+            .collect();
+        deltas.wipe();
+        PyTuple::new(_py, &[
+            PyTuple::new(_py, &vss_point),
+            PyTuple::new(_py, &deltas_bytes),
+        ]).to_object(_py)
+    }
+
+    /// refresh(vss_points: list, deltas: list) -> PyThresholdShare
+    /// --
+    ///
+    /// new share of same Y, old share is not usable with new shares
+    /// vss_points: all dealer's refresh vss points, deltas: my delta from each dealer
+    fn refresh(&self, vss_points: &PyAny, deltas: &PyAny) -> PyResult<PyThresholdShare> {
+        let refresh_vss = pylist2vss(self.inner.t, self.inner.n, vss_points)?;
+        let mut deltas = pylist2bigint(deltas)?;
+        let result = apply_refresh(&self.inner, &refresh_vss, &deltas);
+        deltas.wipe();
+        Ok(PyThresholdShare {inner: result?})
+    }
+
+    /// get_reshare_dealing(dealers: list, new_t: int, new_n: int, new_parties_index: list = None) -> tuple
+    /// --
+    ///
+    /// return vss_point(list of 33b) and secret_scalar(list of 32b) of my lambda_i*x_i to new committee
+    /// dealers: parties_index of t+1 old holders taking part, include mine
+    /// send secret_scalar[j] to new holder j, broadcast vss_point
+    fn get_reshare_dealing(&self, _py: Python, dealers: Vec<usize>, new_t: usize, new_n: usize,
+                           new_parties_index: Option<&PyAny>) -> PyResult<PyObject> {
+        let new_parties_index = pylist2parties_index(new_parties_index)?;
+        let (vss, mut shares) = reshare_dealing(&self.inner, &dealers, new_t, new_n, new_parties_index)?;
+        let vss_point: Vec<&PyBytes> = vss.commitments.iter()
+            .map(|p| PyBytes::new(_py, &point2bytes(p, KeyType::SingleSig)))
+            .collect();
+        let secret_scalar: Vec<&PyBytes> = shares.iter()
+            .map(|s| PyBytes::new(_py, &bigint2bytes(&s.to_big_int()).unwrap()))
+            .collect();
+        shares.wipe();
+        Ok(PyTuple::new(_py, &[
+            PyTuple::new(_py, &vss_point),
+            PyTuple::new(_py, &secret_scalar),
+        ]).to_object(_py))
+    }
+
+    /// reshare(Y: bytes, old_t: int, old_n: int, old_vss_points: list, dealers: list, vss_points: list,
+    ///         secret_scalars: list, new_t: int, new_n: int, my_index: int, new_parties_index: list = None) -> PyThresholdShare
+    /// --
+    ///
+    /// new holder's share of same Y, checked with public data of old committee
+    /// old_vss_points: vss_points of old PyThresholdShare
+    /// vss_points and secret_scalars: one from each dealer (same order as dealers)
+    #[classmethod]
+    fn reshare(_cls: &PyType, Y: &PyBytes, old_t: usize, old_n: usize, old_vss_points: &PyAny, dealers: Vec<usize>,
+               vss_points: &PyAny, secret_scalars: &PyAny, new_t: usize, new_n: usize, my_index: usize,
+               new_parties_index: Option<&PyAny>) -> PyResult<PyThresholdShare> {
+        let Y: GE = bytes2point(Y.as_bytes())?;
+        let old_vss_points = pylist2vss(old_t, old_n, old_vss_points)?;
+        let reshare_vss = pylist2vss(new_t, new_n, vss_points)?;
+        let new_parties_index = pylist2parties_index(new_parties_index)?;
+        let mut shares = pylist2bigint(secret_scalars)?;
+        let result = apply_reshare(&Y, &old_vss_points, &dealers, &reshare_vss, &shares,
+                                   new_t, new_n, my_index, new_parties_index);
+        shares.wipe();
+        Ok(PyThresholdShare {inner: result?})
+    }
+
+    /// get_recovery_pieces(helpers: list, lost_index: int) -> tuple
+    /// --
+    ///
+    /// random pieces(list of 32b) of lambda_i*x_i at lost party, send pieces[j] to helpers[j]
+    /// helpers: parties_index of t+1 helpers, include mine
+    fn get_recovery_pieces(&self, _py: Python, helpers: Vec<usize>, lost_index: usize) -> PyResult<PyObject> {
+        let mut pieces = recovery_dealing(&self.inner, &helpers, lost_index)?;
+        let pieces_bytes: Vec<&PyBytes> = pieces.iter()
+            .map(|p| PyBytes::new(_py, &bigint2bytes(&p.to_big_int()).unwrap()))
+            .collect();
+        pieces.wipe();
+        Ok(PyTuple::new(_py, &pieces_bytes).to_object(_py))
+    }
+
+    /// get_recovery_contribution(pieces: list) -> bytes
+    /// --
+    ///
+    /// sum of pieces received from all helpers, send to lost party
+    fn get_recovery_contribution(&self, _py: Python, pieces: &PyAny) -> PyResult<PyObject> {
+        let mut pieces = pylist2bigint(pieces)?;
+        let result = recovery_contribution(&pieces);
+        pieces.wipe();
+        let mut contribution = result?;
+        let contribution_bytes = PyBytes::new(_py, &bigint2bytes(&contribution.to_big_int()).unwrap()).to_object(_py);
+        contribution.wipe();
+        Ok(contribution_bytes)
+    }
+
+    /// recover(t: int, n: int, my_index: int, parties_index: list, Y: bytes, vss_points: list, contributions: list) -> PyThresholdShare
+    /// --
+    ///
+    /// rebuild lost share from all helper's contributions and check with vss_points
+    #[classmethod]
+    fn recover(_cls: &PyType, t: usize, n: usize, my_index: usize, parties_index: Vec<usize>, Y: &PyBytes,
+               vss_points: &PyAny, contributions: &PyAny) -> PyResult<PyThresholdShare> {
+        let Y: GE = bytes2point(Y.as_bytes())?;
+        let vss_points = pylist2vss(t, n, vss_points)?;
+        let mut contributions = pylist2bigint(contributions)?;
+        let result = recover_share(t, n, my_index, parties_index, Y, vss_points, &contributions);
+        contributions.wipe();
+        Ok(PyThresholdShare {inner: result?})
+    }
+
+    /// wipe() -> None
+    /// --
+    ///
+    /// wipe share, do not use this object again
+    fn wipe(&mut self) {
+        self.inner.share.wipe();
+    }
+}
@@ -0,0 +1,145 @@
+use crate::error::Error;
+use crate::hashing::encode_r;
+use crate::musig2::{Musig2Nonce, Musig2Session};
+use crate::pyo3utils::{bytes2point, pylist2points, pylist2bigint};
+use crate::pykeypair::*;
+use crate::utils::bigint2bytes;
+use emerald_city::curv::elliptic::curves::secp256_k1::{FE, GE};
+use emerald_city::curv::arithmetic::num_bigint::BigInt;
+use emerald_city::curv::elliptic::curves::traits::{ECPoint, ECScalar};
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyTuple, PyType};
+
+
+#[pyclass]
+pub struct PyMusig2Nonce {
+    pub inner: Option<Musig2Nonce>,
+    pub public: (GE, GE),
+}
+
+impl PyMusig2Nonce {
+    /// move secret nonce out, used nonce raises error
+    pub fn take(&mut self) -> Result<Musig2Nonce, Error> {
+        self.inner.take().ok_or(Error::InvalidParams("nonce is already used".to_owned()))
+    }
+}
+
+#[pymethods]
+impl PyMusig2Nonce {
+    #[new]
+    fn new(py: Python) -> Self {
+        // release GIL
+        let inner = py.allow_threads(move || Musig2Nonce::new());
+        let public = inner.public.clone();
+        PyMusig2Nonce {inner: Some(inner), public}
+    }
+
+    /// get_public_nonce() -> tuple
+    /// --
+    ///
+    /// return R1(33b) and R2(33b), publish before signing
+    fn get_public_nonce(&self, _py: Python) -> PyObject {
+        let (r1, r2) = &self.public;
+        PyTuple::new(_py, &[
+            PyBytes::new(_py, &r1.get_element().serialize()),
+            PyBytes::new(_py, &r2.get_element().serialize()),
+        ]).to_object(_py)
+    }
+
+    /// wipe() -> None
+    /// --
+    ///
+    /// wipe secret nonce, do not use this object again
+    fn wipe(&mut self) {
+        // secret nonce is wiped on drop
+        self.inner = None;
+    }
+}
+
+#[pyclass]
+pub struct PyMusig2Session {
+    pub inner: Musig2Session,
+}
+
+#[pymethods]
+impl PyMusig2Session {
+
+    /// generate(signers: list, nonces: list, keypair: PyKeyPair, nonce: PyMusig2Nonce, message: bytes) -> PyMusig2Session
+    /// --
+    ///
+    /// start MuSig2 single round signing
+    /// nonces: list of (R1, R2) published by signers, same order as signers
+    /// apk does not depend on order of signers, same key can be listed twice
+    /// nonce is consumed, using it again raises error
+    #[classmethod]
+    fn generate(_cls: &PyType, signers: &PyAny, nonces: &PyAny, keypair: &PyKeyPair, mut nonce: PyRefMut<PyMusig2Nonce>, message: &PyBytes)
+        -> PyResult<PyMusig2Session> {
+        let signers = pylist2points(signers)?;
+        let nonces: Vec<(&[u8], &[u8])> = nonces.extract()?;
+        let mut points = Vec::with_capacity(nonces.len());
+        for (r1, r2) in nonces {
+            points.push((bytes2point(r1)?, bytes2point(r2)?));
+        }
+        let nonce = nonce.take()?;
+        let inner = Musig2Session::generate(&signers, &points, &keypair.inner, nonce, message.as_bytes())
+            .map_err(PyErr::from)?;
+        Ok(PyMusig2Session {inner})
+    }
+
+    /// get_partial_sign() -> bytes
+    /// --
+    ///
+    /// get partial signature, nonce is used only once
+    fn get_partial_sign(&mut self, _py: Python) -> PyResult<PyObject> {
+        let s_i = self.inner.partial_sign().map_err(PyErr::from)?;
+        let s_i = bigint2bytes(&s_i.to_big_int()).unwrap();
+        Ok(PyBytes::new(_py, &s_i).to_object(_py))
+    }
+
+    /// R() -> bytes
+    /// --
+    ///
+    /// get versioned R.x (33b)
+    fn R(&self, _py: Python) -> PyObject {
+        let bytes = encode_r(&bigint2bytes(&self.inner.R()).unwrap());
+        PyBytes::new(_py, &bytes).to_object(_py)
+    }
+
+    /// apk() -> bytes
+    /// --
+    ///
+    /// get shared public key
+    fn apk(&self, _py: Python) -> PyObject {
+        let bytes = self.inner.apk_bytes();
+        PyBytes::new(_py, &bytes).to_object(_py)
+    }
+
+    /// verify_partial_sign(index: int, s_i: bytes) -> bool
+    /// --
+    ///
+    /// check partial signature of signers[index] by its public key and public nonce
+    fn verify_partial_sign(&self, index: usize, s_i: &PyBytes) -> bool {
+        let s_i: FE = ECScalar::from(&BigInt::from_bytes_be(s_i.as_bytes()));
+        self.inner.verify_partial_sign(index, &s_i)
+    }
+
+    /// aggregate_signature_parts(parts: list) -> bytes
+    /// --
+    ///
+    /// check all partial signatures (same order as signers) and return their sum
+    /// raise InvalidShareError with indexes of faulty signers
+    fn aggregate_signature_parts(&self, _py: Python, parts: &PyAny) -> PyResult<PyObject> {
+        let parts = pylist2bigint(parts)?;
+        let s = self.inner.combine_partial_signs(&parts).map_err(PyErr::from)?;
+        let s = bigint2bytes(&s.to_big_int()).unwrap();
+        Ok(PyBytes::new(_py, &s).to_object(_py))
+    }
+
+    /// wipe() -> None
+    /// --
+    ///
+    /// wipe secret key and unused nonce, do not use this object again
+    fn wipe(&mut self) {
+        self.inner.wipe();
+    }
+}
@@ -1,87 +1,21 @@
 use emerald_city::curv::cryptographic_primitives::secret_sharing::feldman_vss::{
     VerifiableSS, ShamirSecretSharing,
 };
-use emerald_city::curv::arithmetic::traits::Converter;
-use emerald_city::curv::elliptic::curves::secp256_k1::{GE, PK, FE};
-use emerald_city::curv::elliptic::curves::traits::{ECPoint, ECScalar};
+use crate::threshold::{Complaint, KeygenProof};
+use crate::utils::{bigint2bytes, bytes2point_inner, point2bytes, KeyType};
+use emerald_city::curv::elliptic::curves::secp256_k1::{GE, FE};
+use emerald_city::curv::elliptic::curves::traits::ECScalar;
 use emerald_city::curv::arithmetic::num_bigint::BigInt;
 use pyo3::prelude::*;
-use pyo3::exceptions::ValueError;
+use pyo3::types::PyBytes;
 
 
-/// Points type
-#[derive(PartialEq, Debug)]
-pub enum PyKeyType {
-    SingleSig,
-    AggregateSig,
-    ThresholdSig
-}
-
 /// Bitcoin public key format converter
 /// compressed key   : 2 or 3 prefix + X
 /// uncompressed key : 4 prefix      + X + Y
 pub fn bytes2point(bytes: &[u8]) -> PyResult<GE> {
     let result = bytes2point_inner(bytes);
-    result.map_err(|err| ValueError::py_err(err))
-}
-
-#[inline]
-pub fn bytes2point_inner(bytes: &[u8]) -> Result<GE, String> {
-    let len = bytes.len();
-    let hex_bytes = hex::encode(bytes);
-    match decode_public_bytes(bytes) {
-        Ok((key_type, prefix)) => {
-            if len == 33 && (prefix == 2 || prefix == 3) {
-                let mut template = [4u8;33];
-                template.copy_from_slice(&bytes);
-                match key_type {
-                    PyKeyType::SingleSig => (),
-                    PyKeyType::AggregateSig => template[0] -= 3,
-                    PyKeyType::ThresholdSig => template[0] -= 6
-                }
-                let public = PK::from_slice(&template).map_err(
-                    |_| format!("0 invalid pk point: {}", hex_bytes))?;
-                GE::from_bytes(&public.serialize_uncompressed()[1..]).map_err(
-                    |_| format!("1 invalid pk point: {}", hex_bytes))
-            }else if len == 65 && prefix == 4 {
-                GE::from_bytes(&bytes[1..]).map_err(
-                    |_| format!("2 invalid pk point: {}", hex_bytes))
-            } else {
-                Err(format!("unknown type meta info len={} prefix={}", len, prefix))
-            }
-        },
-        Err(_) => Err(format!("invalid format pk: {}", hex_bytes))
-    }
-}
-
-/// Mpz bigint to 32bytes big endian
-pub fn bigint2bytes(int: &BigInt) -> Result<[u8;32], String> {
-    let vec = BigInt::to_vec(int);
-    if 32 < vec.len() {
-        return Err("too large bigint".to_owned());
-    }
-    let mut bytes = [0u8;32];
-    bytes[(32-vec.len())..].copy_from_slice(&vec);
-    Ok(bytes)
-}
-
-/// return (PyKeyType, normal_prefix)
-/// warning: I will add more params
-pub fn decode_public_bytes(bytes: &[u8]) -> Result<(PyKeyType, u8), ()> {
-    match bytes.get(0) {
-        Some(prefix) => {
-            if *prefix == 2 || *prefix == 3 || *prefix == 4 {
-                Ok((PyKeyType::SingleSig, *prefix))
-            } else if *prefix == 5 || *prefix == 6 || *prefix == 7 {
-                Ok((PyKeyType::AggregateSig, *prefix - 3))
-            } else if *prefix == 8 || *prefix == 9 || *prefix == 10 {
-                Ok((PyKeyType::ThresholdSig, *prefix - 6))
-            } else {
-                Err(())
-            }
-        },
-        None => Err(())
-    }
+    result.map_err(PyErr::from)
 }
 
 
@@ -128,17 +62,69 @@ pub fn pylist2vss(t: usize, n: usize, vss_points: &PyAny)
 }
 
 
-pub fn pylist2parties_index(n: usize, parties_index: Option<&PyAny>)
-    -> PyResult<Vec<usize>> {
+pub fn pylist2parties_index(parties_index: Option<&PyAny>)
+    -> PyResult<Option<Vec<usize>>> {
     match parties_index {
-        Some(vec) => {
-            let vec: Vec<usize> = vec.extract()?;
-            if vec.len() == n {
-                Ok(vec.into_iter().map(|i| i + 1).collect())
-            } else {
-                Err(ValueError::py_err("not correct parties_index length"))
-            }
-        },
-        None => Ok((0..n).map(|i| i + 1).collect()),
+        Some(vec) => Ok(Some(vec.extract()?)),
+        None => Ok(None),
+    }
+}
+
+
+pub fn pylist2secret_shares(secret_scalars: &PyAny) -> PyResult<Vec<Vec<FE>>> {
+    let secret_scalars: Vec<Vec<&[u8]>> = secret_scalars.extract()?;
+    let mut tmp = Vec::with_capacity(secret_scalars.len());
+    for lists in secret_scalars {
+        let mut inner = Vec::with_capacity(lists.len());
+        for scalar in lists {
+            let s: FE = ECScalar::from(&BigInt::from_bytes_be(scalar));
+            inner.push(s);
+        };
+        tmp.push(inner);
+    };
+    Ok(tmp)
+}
+
+
+/// list of (accuser: int, dealer: int, share: bytes or None)
+pub fn pylist2complaints(complaints: &PyAny) -> PyResult<Vec<Complaint>> {
+    let complaints: Vec<(usize, usize, Option<&[u8]>)> = complaints.extract()?;
+    Ok(complaints.into_iter()
+        .map(|(accuser, dealer, share)| Complaint {
+            accuser,
+            dealer,
+            share: share.map(|s| ECScalar::from(&BigInt::from_bytes_be(s))),
+        })
+        .collect())
+}
+
+
+pub fn complaints2pylist(_py: Python, complaints: &[Complaint]) -> PyObject {
+    let complaints: Vec<(usize, usize, Option<&PyBytes>)> = complaints.iter()
+        .map(|c| (c.accuser, c.dealer, c.share.as_ref()
+            .map(|s| PyBytes::new(_py, &bigint2bytes(&s.to_big_int()).unwrap()))))
+        .collect();
+    complaints.to_object(_py)
+}
+
+
+/// list of (R: bytes, s: bytes)
+pub fn pylist2proofs(proofs: &PyAny) -> PyResult<Vec<KeygenProof>> {
+    let proofs: Vec<(&[u8], &[u8])> = proofs.extract()?;
+    let mut tmp = Vec::with_capacity(proofs.len());
+    for (r, s) in proofs {
+        tmp.push(KeygenProof {
+            R: bytes2point(r)?,
+            s: ECScalar::from(&BigInt::from_bytes_be(s)),
+        });
     }
+    Ok(tmp)
+}
+
+
+pub fn proof2py(_py: Python, proof: &KeygenProof) -> PyObject {
+    (
+        PyBytes::new(_py, &point2bytes(&proof.R, KeyType::SingleSig)),
+        PyBytes::new(_py, &bigint2bytes(&proof.s.to_big_int()).unwrap()),
+    ).to_object(_py)
 }
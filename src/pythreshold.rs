@@ -1,38 +1,22 @@
+use crate::error::Error;
+use crate::frost::{FrostNonce, FrostSession};
+use crate::hashing::encode_frost_r;
 use crate::pykeypair::*;
+pub use crate::pykeystore::PyThresholdShare;
 use crate::pyo3utils::*;
-use emerald_city::curv::cryptographic_primitives::secret_sharing::feldman_vss::{
-    VerifiableSS,
-    ShamirSecretSharing,
-};
-use emerald_city::curv::cryptographic_primitives::commitments::{
-    hash_commitment::HashCommitment,
-    traits::Commitment,
-};
-use emerald_city::curv::cryptographic_primitives::hashing::hash_sha256::HSha256;
-use emerald_city::curv::arithmetic::traits::Samplable;
-use emerald_city::curv::cryptographic_primitives::hashing::traits::Hash;
-use emerald_city::curv::elliptic::curves::secp256_k1::{FE, GE};
+use crate::secret::{SecretScalar, Wipe};
+use crate::threshold::{Dealing, ThresholdKey};
+use crate::utils::bigint2bytes;
+use emerald_city::curv::elliptic::curves::secp256_k1::GE;
 use emerald_city::curv::elliptic::curves::traits::{ECPoint, ECScalar};
 use emerald_city::curv::arithmetic::num_bigint::BigInt;
 use pyo3::prelude::*;
-use pyo3::exceptions::ValueError;
 use pyo3::types::{PyBytes,PyTuple,PyType};
-use threadpool::ThreadPool;
-use std::sync::mpsc::channel;
 
 
 #[pyclass]
 pub struct PyThresholdKey {
-    #[pyo3(get)]
-    pub keypair: PyKeyPair,
-    #[pyo3(get)]
-    pub my_index: Option<usize>,
-    #[pyo3(get)]
-    pub parties_index: Vec<usize>,
-    #[pyo3(get)]
-    pub t: usize, // threshold
-    #[pyo3(get)]
-    pub n: usize,
+    pub inner: ThresholdKey,
 }
 
 #[pymethods]
@@ -48,27 +32,12 @@ impl PyThresholdKey {
     #[classmethod]
     fn generate(_cls: &PyType, _py: Python, t: usize, n: usize, parties_index: Option<&PyAny>)
         -> PyResult<PyThresholdKey> {
-        if t >= n {
-            return Err(ValueError::py_err("require \"t < n\""));
-        };
-        let parties_index = pylist2parties_index(n, parties_index)?;
-        let my_index = None;  // unknown at this point
-        let keypair = generate_keypair(_py);
-        {
-            // commitment check
-            let blind_factor = BigInt::sample(256);
-            let commitment = HashCommitment::create_commitment_with_user_defined_randomness(
-                &keypair.public.bytes_compressed_to_big_int(),
-                &blind_factor,
-            );  // com = bc1 of KeyGenBroadcastMessage1
-            let h = HashCommitment::create_commitment_with_user_defined_randomness(
-                    &keypair.public.bytes_compressed_to_big_int(), &blind_factor);
-            if h != commitment {
-                return Err(ValueError::py_err("test commitment check failed"));
-            }
-        }
-        //obj.init(PyThresholdKey { keypair, my_index, parties_index, t, n});
-        Ok(PyThresholdKey { keypair, my_index, parties_index, t, n})
+        let parties_index = pylist2parties_index(parties_index)?;
+        // release GIL
+        let inner = _py.allow_threads(move || {
+            ThresholdKey::generate(t, n, parties_index)
+        }).map_err(PyErr::from)?;
+        Ok(PyThresholdKey {inner})
     }
 
     /// from_secret_key(t: int, , n: int, secret: bytes, my_index: int, parties_index: list = None) -> PyThresholdKey
@@ -83,16 +52,35 @@ impl PyThresholdKey {
     #[classmethod]
     fn from_secret_key(_cls: &PyType, _py: Python, t: usize, n: usize, secret: &PyBytes, my_index: usize, parties_index: Option<&PyAny>)
         -> PyResult<PyThresholdKey> {
-        if t >= n {
-            return Err(ValueError::py_err("require \"t < n\""));
-        };
-        let ec_point: GE = ECPoint::generator();
-        let secret: FE = ECScalar::from(&BigInt::from_bytes_be(secret.as_bytes()));
-        let public: GE = ec_point.scalar_mul(&secret.get_element());
-        let keypair = PyKeyPair {secret, public};
-        let parties_index = pylist2parties_index(n, parties_index)?;
-        let my_index = Some(my_index);
-        Ok(PyThresholdKey { keypair, my_index, parties_index, t, n})
+        let parties_index = pylist2parties_index(parties_index)?;
+        let inner = ThresholdKey::from_secret_key(t, n, secret.as_bytes(), my_index, parties_index)
+            .map_err(PyErr::from)?;
+        Ok(PyThresholdKey {inner})
+    }
+
+    #[getter]
+    fn keypair(&self) -> PyKeyPair {
+        PyKeyPair {inner: self.inner.keypair.clone()}
+    }
+
+    #[getter]
+    fn my_index(&self) -> Option<usize> {
+        self.inner.my_index
+    }
+
+    #[getter]
+    fn parties_index(&self) -> Vec<usize> {
+        self.inner.parties_index.clone()
+    }
+
+    #[getter]
+    fn t(&self) -> usize {
+        self.inner.t
+    }
+
+    #[getter]
+    fn n(&self) -> usize {
+        self.inner.n
     }
 
     /// get_commitment() -> tuple
@@ -100,11 +88,7 @@ impl PyThresholdKey {
     ///
     /// return blind_factor(32b) & commitment(32b)
     fn get_commitment(&self, _py: Python) -> PyObject {
-        let blind_factor = BigInt::sample(256);
-        let commitment = HashCommitment::create_commitment_with_user_defined_randomness(
-            &self.keypair.public.bytes_compressed_to_big_int(),
-            &blind_factor,
-        );  // com = bc1 of KeyGenBroadcastMessage1
+        let (blind_factor, commitment) = self.inner.get_commitment();
         let blind_factor = bigint2bytes(&blind_factor).unwrap();
         let commitment = bigint2bytes(&commitment).unwrap();
         PyTuple::new(_py, &[
@@ -113,254 +97,325 @@ impl PyThresholdKey {
         ]).to_object(_py)
     }
 
-    /// get_variable_secret_sharing() -> tuple
+    /// get_variable_secret_sharing(signers: list = None, context: bytes = None) -> tuple
     /// --
     ///
     /// return vss_point(list of 32b) and secret_scalar(list of 32b)
-    fn get_variable_secret_sharing(&self, _py: Python) -> PyResult<PyObject> {
-        // index users [0, 1, .., n] => [1, 2, ...,n+1]
-        let (vss_scheme, secret_shares) = VerifiableSS::share_at_indices(
-                self.t, self.n, &self.keypair.secret, &self.parties_index);
-
-        let vss_point: Vec<&PyBytes> = vss_scheme.commitments.iter()
-            .map(|com| PyBytes::new(_py, &com.get_element().serialize()))
-            .collect();
-        let secret_scalar: Vec<&PyBytes> = secret_shares.iter()
-            .map(|int| PyBytes::new(_py, &bigint2bytes(&int.to_big_int()).unwrap()))
-            .collect();
-        Ok(PyTuple::new(_py, &[
-            PyTuple::new(_py, &vss_point),
-            PyTuple::new(_py, &secret_scalar),
-        ]).to_object(_py))
+    /// signers: secret_scalar[j] is ECIES encrypted to signers[j], context is bound to ciphertext
+    /// with signers, PyDealing is returned too for answer_complaints, wipe it after the complaint round
+    fn get_variable_secret_sharing(&self, _py: Python, signers: Option<&PyAny>, context: Option<&PyBytes>)
+        -> PyResult<PyObject> {
+        match signers {
+            Some(signers) => {
+                let signers = pylist2points(signers)?;
+                let context = context.map(|c| c.as_bytes()).unwrap_or(&b""[..]);
+                let (vss_scheme, ciphertexts, dealing) = self.inner.get_encrypted_secret_sharing(&signers, context)?;
+                let vss_point: Vec<&PyBytes> = vss_scheme.commitments.iter()
+                    .map(|com| PyBytes::new(_py, &com.get_element().serialize()))
+                    .collect();
+                let ciphertexts: Vec<&PyBytes> = ciphertexts.iter()
+                    .map(|c| PyBytes::new(_py, c))
+                    .collect();
+                let dealing = Py::new(_py, PyDealing {inner: dealing})?;
+                Ok(PyTuple::new(_py, &[
+                    PyTuple::new(_py, &vss_point).to_object(_py),
+                    PyTuple::new(_py, &ciphertexts).to_object(_py),
+                    dealing.to_object(_py),
+                ]).to_object(_py))
+            },
+            None => {
+                let (vss_scheme, mut secret_shares) = self.inner.get_variable_secret_sharing();
+                let vss_point: Vec<&PyBytes> = vss_scheme.commitments.iter()
+                    .map(|com| PyBytes::new(_py, &com.get_element().serialize()))
+                    .collect();
+                let secret_scalar: Vec<&PyBytes> = secret_shares.iter()
+                    .map(|int| PyBytes::new(_py, &bigint2bytes(&int.to_big_int()).unwrap()))
+                    .collect();
+                secret_shares.wipe();
+                Ok(PyTuple::new(_py, &[
+                    PyTuple::new(_py, &vss_point),
+                    PyTuple::new(_py, &secret_scalar),
+                ]).to_object(_py))
+            },
+        }
     }
 
-    /// keygen_t_n_parties(signers: list, vss_points: list, secret_scalars: list) -> bytes
+    /// get_proof(context: bytes, my_index: int) -> tuple
     /// --
     ///
-    /// generate threshold key (t of n)
-    fn keygen_t_n_parties(&mut self, _py: Python, signers: &PyAny, vss_points: &PyAny, secret_scalars: &PyAny)
-        -> PyResult<PyObject> {
-        if self.n != signers.len().unwrap_or(0) {
-            return Err(ValueError::py_err("not correct signers length"));
-        } else if self.n != vss_points.len().unwrap_or(0) {
-            return Err(ValueError::py_err("not correct vss_points length"));
-        } else if self.n != secret_scalars.len().unwrap_or(0) {
-            return Err(ValueError::py_err("not correct secret_scalars length"));
-        }
+    /// proof of knowledge (R, s) of my constant term, broadcast with vss_points
+    /// context: session id shared by all parties, my_index: my position in signers
+    fn get_proof(&self, _py: Python, context: &PyBytes, my_index: usize) -> PyObject {
+        proof2py(_py, &self.inner.prove_constant_term(context.as_bytes(), my_index))
+    }
 
+    /// keygen_t_n_parties(signers: list, vss_points: list, secret_scalars: list, proofs: list, context: bytes, encrypted: bool = False) -> bytes
+    /// --
+    ///
+    /// generate threshold key (t of n)
+    /// proofs: all dealer's get_proof, raise InvalidProofError if missing or broken
+    /// encrypted: secret_scalars are ciphertexts of get_variable_secret_sharing(signers, context)
+    fn keygen_t_n_parties(&mut self, _py: Python, signers: &PyAny, vss_points: &PyAny, secret_scalars: &PyAny,
+                          proofs: &PyAny, context: &PyBytes, encrypted: Option<bool>) -> PyResult<PyObject> {
         // convert python type => Rust type
         let signers = pylist2points(signers)?;  // = y_vec
-        let vss_scheme_vec = {
-            let vss_points: Vec<Vec<&[u8]>> = vss_points.extract()?;
-            let mut tmp = Vec::with_capacity(vss_points.len());
-            for vss in vss_points {
-                let mut points = Vec::with_capacity(vss.len());
-                for p in vss {
-                    let p = bytes2point(p)?;
-                    points.push(p);
-                }
-                tmp.push(VerifiableSS {
-                    parameters: ShamirSecretSharing {
-                        threshold: self.t, share_count: self.n
-                    },
-                    commitments: points
-                });
-            };
-            tmp
+        let vss_scheme_vec = pylist2vss(self.inner.t, self.inner.n, vss_points)?;
+        let proofs = pylist2proofs(proofs)?;
+        let context = context.as_bytes();
+
+        let inner = &mut self.inner;
+        let mut x_i = if encrypted.unwrap_or(false) {
+            let encrypted_shares_vec: Vec<Vec<Vec<u8>>> = secret_scalars.extract()?;
+            _py.allow_threads(move || {
+                inner.keygen_t_n_parties_encrypted(&signers, &vss_scheme_vec, &encrypted_shares_vec, &proofs, context)
+            }).map_err(PyErr::from)?
+        } else {
+            let mut secret_shares_vec = pylist2secret_shares(secret_scalars)?;
+            _py.allow_threads(move || {
+                let result = inner.keygen_t_n_parties(&signers, &vss_scheme_vec, &secret_shares_vec, &proofs, context);
+                secret_shares_vec.wipe();
+                result
+            }).map_err(PyErr::from)?
         };
-        let secret_shares_vec = {
-            let secret_scalars: Vec<Vec<&[u8]>> = secret_scalars.extract()?;
-            let mut tmp = Vec::with_capacity(secret_scalars.len());
-            for lists in secret_scalars {
-                let mut inner = Vec::with_capacity(lists.len());
-                for scalar in lists {
-                    let s: FE = ECScalar::from(&BigInt::from_bytes_be(scalar));
-                    inner.push(s);
-                };
-                tmp.push(inner);
-            };
-            tmp
+        let bytes = bigint2bytes(&x_i.to_big_int()).expect("too large x_i");
+        x_i.wipe();
+        Ok(PyBytes::new(_py, &bytes).to_object(_py))
+    }
+
+    /// get_complaints(signers: list, vss_points: list, secret_scalars: list, context: bytes = None, encrypted: bool = False) -> list
+    /// --
+    ///
+    /// GJKR complaint round, return (accuser, dealer, None) for every broken dealer
+    /// broadcast them, accused dealers answer by answer_complaints
+    /// encrypted: secret_scalars are ciphertexts, undecryptable share is a complaint too
+    fn get_complaints(&mut self, _py: Python, signers: &PyAny, vss_points: &PyAny, secret_scalars: &PyAny,
+                      context: Option<&PyBytes>, encrypted: Option<bool>) -> PyResult<PyObject> {
+        let signers = pylist2points(signers)?;
+        let vss_scheme_vec = pylist2vss(self.inner.t, self.inner.n, vss_points)?;
+        let result = if encrypted.unwrap_or(false) {
+            let encrypted_shares_vec: Vec<Vec<Vec<u8>>> = secret_scalars.extract()?;
+            let context = context.map(|c| c.as_bytes()).unwrap_or(&b""[..]);
+            self.inner.complaints_encrypted(&signers, &vss_scheme_vec, &encrypted_shares_vec, context)
+        } else {
+            let mut secret_shares_vec = pylist2secret_shares(secret_scalars)?;
+            let result = self.inner.complaints(&signers, &vss_scheme_vec, &secret_shares_vec);
+            secret_shares_vec.wipe();
+            result
         };
+        Ok(complaints2pylist(_py, &result?))
+    }
 
-        // your index
-        let my_index = match self.my_index {
-            Some(i) => i,
-            None => {
-                let mut my_index: Option<usize> = None;
-                for (index, signer) in signers.iter().enumerate() {
-                    if signer == &self.keypair.public {
-                        my_index = Some(index);
-                        break;
-                    }
-                }
-                if my_index.is_none() {
-                    return Err(ValueError::py_err("cannot find your position"));
-                }
-                my_index.unwrap()
-            }
+    /// answer_complaints(complaints: list, secret_scalars: list = None, dealing: PyDealing = None) -> list
+    /// --
+    ///
+    /// reveal my shares disputed by complaints against me, return (accuser, dealer, share)
+    /// secret_scalars: my shares of get_variable_secret_sharing
+    /// dealing: PyDealing of get_variable_secret_sharing(signers, context), instead of secret_scalars
+    fn answer_complaints(&self, _py: Python, complaints: &PyAny, secret_scalars: Option<&PyAny>,
+                         dealing: Option<&PyDealing>) -> PyResult<PyObject> {
+        let complaints = pylist2complaints(complaints)?;
+        let result = match (secret_scalars, dealing) {
+            (Some(secret_scalars), None) => {
+                let mut secret_shares = pylist2bigint(secret_scalars)?;
+                let result = self.inner.answer_complaints(&complaints, &secret_shares);
+                secret_shares.wipe();
+                result
+            },
+            (None, Some(dealing)) => self.inner.answer_complaints_encrypted(&complaints, &dealing.inner),
+            _ => Err(Error::InvalidParams("require either secret_scalars or dealing".to_owned())),
         };
+        Ok(complaints2pylist(_py, &result?))
+    }
 
-        // calculate party share
-        let mut party_share = Vec::with_capacity(self.n);
-        for i in 0..self.n {
-            let party = match secret_shares_vec[i].get(my_index) {
-                Some(scalar) => scalar.clone(),
-                None => return Err(ValueError::py_err("not found your index on secret_shares"))
-            };
-            party_share.push(party);
-        }
+    /// get_qualified_dealers(signers: list, vss_points: list, complaints: list, proofs: list, context: bytes) -> list
+    /// --
+    ///
+    /// QUAL set, index of dealers which are not disqualified
+    /// complaints: all complaints, replaced by answers when answered
+    fn get_qualified_dealers(&self, signers: &PyAny, vss_points: &PyAny, complaints: &PyAny,
+                             proofs: &PyAny, context: &PyBytes) -> PyResult<Vec<usize>> {
+        let signers = pylist2points(signers)?;
+        let vss_scheme_vec = pylist2vss(self.inner.t, self.inner.n, vss_points)?;
+        let complaints = pylist2complaints(complaints)?;
+        let proofs = pylist2proofs(proofs)?;
+        Ok(self.inner.qualified_dealers(&signers, &vss_scheme_vec, &complaints, &proofs, context.as_bytes())?)
+    }
 
-        // calculate party_share sum
-        let x_i = party_share.iter().fold(FE::zero(), |acc, x| acc + x);
-        let x_i = bigint2bytes(&x_i.to_big_int()).expect("too large x_i");
-
-        // verify vss construct keypair
-        {
-            let pool = ThreadPool::new(num_cpus::get());
-            let (tx, rx) = channel();
-            for i in 0..self.n {
-                let vss_scheme = vss_scheme_vec[i].to_owned();
-                let secret_share = party_share[i].to_owned();
-                let position = self.parties_index[my_index].to_owned();
-                let public_key = signers[i].to_owned();
-                let tx = tx.clone();
-                pool.execute(move || {
-                    match if vss_scheme.validate_share(&secret_share, position).is_err() {
-                        tx.send(Err(format!("failed vss validation check: idx={}", i)))
-                    } else if vss_scheme.commitments[0] != public_key {
-                        tx.send(Err(format!("failed vss commitment signer check: idx={}", i)))
-                    } else {
-                        tx.send(Ok(()))
-                    } {
-                        Ok(_) => (),
-                        Err(_err) => eprintln!("pipe error: {:?}", _err.to_string())
-                    };
-                });
-            };
-
-            // wait for all jobs finish
-            let n = self.n.to_owned();
-            let exception = _py.allow_threads(move || {
-                for result in rx.iter().take(n) {
-                    if result.is_err() {
-                        return Err(result.unwrap_err())
-                    }
-                }
-                Ok(())
-            });
-            exception.map_err(|err| {
-                ValueError::py_err(err)
-            })?;
-        }
-        // success generate sharedKey
-        self.my_index = Some(my_index);
-        Ok(PyBytes::new(_py, &x_i).to_object(_py))
+    /// keygen_t_n_parties_qual(signers: list, vss_points: list, secret_scalars: list, qual: list, complaints: list, proofs: list, context: bytes, encrypted: bool = False) -> bytes
+    /// --
+    ///
+    /// generate threshold key from QUAL dealers only
+    /// group key is summarize_public_points(signers, proofs, context, qual)
+    /// encrypted: secret_scalars are ciphertexts of get_variable_secret_sharing(signers, context)
+    fn keygen_t_n_parties_qual(&mut self, _py: Python, signers: &PyAny, vss_points: &PyAny, secret_scalars: &PyAny,
+                               qual: Vec<usize>, complaints: &PyAny, proofs: &PyAny, context: &PyBytes,
+                               encrypted: Option<bool>) -> PyResult<PyObject> {
+        let signers = pylist2points(signers)?;
+        let vss_scheme_vec = pylist2vss(self.inner.t, self.inner.n, vss_points)?;
+        let mut complaints = pylist2complaints(complaints)?;
+        let proofs = pylist2proofs(proofs)?;
+        let context = context.as_bytes();
+
+        let inner = &mut self.inner;
+        let mut x_i = if encrypted.unwrap_or(false) {
+            let encrypted_shares_vec: Vec<Vec<Vec<u8>>> = secret_scalars.extract()?;
+            _py.allow_threads(move || {
+                let result = inner.keygen_t_n_parties_qual_encrypted(
+                    &signers, &vss_scheme_vec, &encrypted_shares_vec, &qual, &complaints, &proofs, context);
+                complaints.wipe();
+                result
+            }).map_err(PyErr::from)?
+        } else {
+            let mut secret_shares_vec = pylist2secret_shares(secret_scalars)?;
+            _py.allow_threads(move || {
+                let result = inner.keygen_t_n_parties_qual(
+                    &signers, &vss_scheme_vec, &secret_shares_vec, &qual, &complaints, &proofs, context);
+                secret_shares_vec.wipe();
+                complaints.wipe();
+                result
+            }).map_err(PyErr::from)?
+        };
+        let bytes = bigint2bytes(&x_i.to_big_int()).expect("too large x_i");
+        x_i.wipe();
+        Ok(PyBytes::new(_py, &bytes).to_object(_py))
+    }
+
+    /// wipe() -> None
+    /// --
+    ///
+    /// wipe secret key, do not use this object again
+    fn wipe(&mut self) {
+        self.inner.wipe();
     }
 }
 
-pub fn sum_public_points(signers: &Vec<GE>) -> PyResult<GE> {
-    // return Y params of sharedKey
-    // return V params of eph sharedKey
-    if signers.len() < 1 {
-        return Err(ValueError::py_err("zero length point isn't allowed"));
+#[pyclass]
+pub struct PyDealing {
+    pub inner: Dealing,
+}
+
+#[pymethods]
+impl PyDealing {
+    /// wipe() -> None
+    /// --
+    ///
+    /// wipe plain shares after the complaint round, do not use this object again
+    fn wipe(&mut self) {
+        self.inner.wipe();
     }
-    let mut signers = signers.iter();
-    let head = signers.next().unwrap();
-    let sum = signers.fold(head.clone(), |acc, x| acc + x);
-    Ok(sum)
 }
 
-pub fn compute_local_signature(share: &FE, eph_share: &FE, Y: &GE, V: &GE, message: &[u8]) -> (FE, FE) {
-    // each party computes and share a local sig
-    let beta_i = eph_share.clone();
-    let alpha_i = share.clone();
-
-    let e_bn = HSha256::create_hash(&[
-            &V.bytes_compressed_to_big_int(),
-            &Y.bytes_compressed_to_big_int(),
-            &BigInt::from_bytes_be(message),
-    ]);
-    let e: FE = ECScalar::from(&e_bn);
-    let gamma_i = beta_i + e.clone() * alpha_i;
-    (e, gamma_i)
+#[pyclass]
+pub struct PyFrostNonce {
+    pub inner: Option<FrostNonce>,
+    pub public: (GE, GE),
 }
 
-pub fn sum_local_signature(
-    t: usize, e: &FE, gammmas: &Vec<FE>, parties_index: &Vec<usize>,
-    vss_points: &Vec<VerifiableSS>, eph_vss_points: &Vec<VerifiableSS>)
-    -> Result<FE, String> {
-    if vss_points.len() < 1 {
-        return Err(String::from("zero length vss_points isn't allowed"));
-    } else if eph_vss_points.len() < 1 {
-        return Err(String::from("zero length eph_vss_points isn't allowed"));
-    } else if gammmas.len() != eph_vss_points[0].parameters.share_count {
-        return Err(String::from("not correct gammmas length"));
-    }else if parties_index.len() != eph_vss_points[0].parameters.share_count {
-        return Err(String::from("not correct parties_index length"));
-    } else if t != vss_points[0].parameters.threshold {
-        return Err(String::from("not correct vss threshold"));
-    } else if vss_points.len() != vss_points[0].parameters.share_count {
-        return Err(String::from("not correct vss length"));
-    } else if t != eph_vss_points[0].parameters.threshold {
-        return Err(String::from("not correct eph_vss threshold"));
-    } else if eph_vss_points.len() != eph_vss_points[0].parameters.share_count {
-        return Err(String::from("not correct eph_vss length"));
+impl PyFrostNonce {
+    /// move secret nonce out, used nonce raises error
+    pub fn take(&mut self) -> Result<FrostNonce, Error> {
+        self.inner.take().ok_or(Error::InvalidParams("nonce is already used".to_owned()))
     }
-    // n' = num of signers, n - num of parties in keygen
-    let comm_vec = (0..t + 1)
-        .map(|i| {
-            let mut key_gen_comm_i_vec = vss_points.iter()
-                .map(|v| v.commitments[i].clone() * e)
-                .collect::<Vec<GE>>();
-            let mut eph_comm_i_vec = eph_vss_points.iter()
-                .map(|v| v.commitments[i].clone())
-                .collect::<Vec<GE>>();
-            key_gen_comm_i_vec.append(&mut eph_comm_i_vec);
-            let mut comm_i_vec_iter = key_gen_comm_i_vec.iter();
-            let comm_i_0 = comm_i_vec_iter.next().unwrap();
-            comm_i_vec_iter.fold(comm_i_0.clone(), |acc, x| acc + x)
-        })
-        .collect::<Vec<GE>>();
-
-    let vss_sum = VerifiableSS {
-        parameters: eph_vss_points[0].parameters.clone(),
-        commitments: comm_vec,
-    };
-
-    // validate share public
-    let g: GE = GE::generator();
-    for (position, gamma) in parties_index.iter().zip(gammmas.iter()) {
-        let gamma_i_g = &g * gamma;
-        let comm_to_point = vss_sum.get_point_commitment(position + 1);
-        if gamma_i_g != comm_to_point {
-            return Err(String::from("validate share public failed idx"));
-        }
+}
+
+#[pymethods]
+impl PyFrostNonce {
+    #[new]
+    fn new(py: Python) -> Self {
+        // release GIL
+        let inner = py.allow_threads(move || FrostNonce::new());
+        let public = inner.public.clone();
+        PyFrostNonce {inner: Some(inner), public}
+    }
+
+    /// get_public_nonce() -> tuple
+    /// --
+    ///
+    /// return D(33b) and E(33b), publish before signing
+    fn get_public_nonce(&self, _py: Python) -> PyObject {
+        let (d, e) = &self.public;
+        PyTuple::new(_py, &[
+            PyBytes::new(_py, &d.get_element().serialize()),
+            PyBytes::new(_py, &e.get_element().serialize()),
+        ]).to_object(_py)
+    }
+
+    /// wipe() -> None
+    /// --
+    ///
+    /// wipe secret nonce, do not use this object again
+    fn wipe(&mut self) {
+        // secret nonce is wiped on drop
+        self.inner = None;
     }
-    // each party / dealer can generate the signature
-    let gamma_vec = (0..parties_index.len())
-        .map(|i| gammmas[i].clone())
-        .collect::<Vec<FE>>();
-    let reconstruct_limit = vss_sum.parameters.threshold.clone() + 1;
-    let sigma = vss_sum.reconstruct(
-        &parties_index[0..reconstruct_limit.clone()],
-        &gamma_vec[0..reconstruct_limit.clone()],
-    );
-    Ok(sigma)
 }
 
+#[pyclass]
+pub struct PyFrostSession {
+    pub inner: FrostSession,
+}
+
+#[pymethods]
+impl PyFrostSession {
+
+    /// generate(t: int, Y: bytes, message: bytes, parties_index: list, nonces: list) -> PyFrostSession
+    /// --
+    ///
+    /// start FROST signing with preprocessed nonces
+    /// Y: group key of summarize_public_points
+    /// parties_index: signer's index (ex. [0, 1, 3]), t+1 or more
+    /// nonces: (D_i, E_i) of PyFrostNonce.get_public_nonce(), same order as parties_index
+    #[classmethod]
+    fn generate(_cls: &PyType, t: usize, Y: &PyBytes, message: &PyBytes, parties_index: &PyAny, nonces: &PyAny)
+        -> PyResult<PyFrostSession> {
+        let Y = bytes2point(Y.as_bytes())?;
+        let parties_index: Vec<usize> = parties_index.extract()?;
+        let nonces: Vec<(&[u8], &[u8])> = nonces.extract()?;
+        let mut points = Vec::with_capacity(nonces.len());
+        for (d, e) in nonces {
+            points.push((bytes2point(d)?, bytes2point(e)?));
+        }
+        let inner = FrostSession::new(t, &Y, message.as_bytes(), &parties_index, &points)
+            .map_err(PyErr::from)?;
+        Ok(PyFrostSession {inner})
+    }
 
-pub fn verify_threshold_signature(sigma: FE, Y: &GE, V: &GE, message: &[u8]) -> bool {
-    let e_bn = HSha256::create_hash(&[
-        &V.bytes_compressed_to_big_int(),
-        &Y.bytes_compressed_to_big_int(),
-        &BigInt::from_bytes_be(message),
-    ]);
-    let e: FE = ECScalar::from(&e_bn);
+    /// get_partial_sign(share: bytes, my_index: int, nonce: PyFrostNonce) -> bytes
+    /// --
+    ///
+    /// return gamma_i, the nonce is consumed and raises error when used again
+    fn get_partial_sign(&self, _py: Python, share: &PyBytes, my_index: usize, mut nonce: PyRefMut<PyFrostNonce>)
+        -> PyResult<PyObject> {
+        let mut share_int = BigInt::from_bytes_be(share.as_bytes());
+        let share = SecretScalar::new(ECScalar::from(&share_int));
+        share_int.wipe();
+        let nonce = nonce.take()?;
+        let gamma = self.inner.partial_sign(&share, my_index, nonce)
+            .map_err(PyErr::from)?;
+        let gamma = bigint2bytes(&gamma.to_big_int()).unwrap();
+        Ok(PyBytes::new(_py, &gamma).to_object(_py))
+    }
 
-    let g: GE = GE::generator();
-    let sigma_g = g * &sigma;
-    let e_y = Y * &e;
-    let e_y_plus_v = e_y + V;
+    /// summarize_signature(t: int, n: int, gammas: list, vss_points: list) -> bytes
+    /// --
+    ///
+    /// verify every gamma and return sigma
+    fn summarize_signature(&self, _py: Python, t: usize, n: usize, gammas: &PyAny, vss_points: &PyAny)
+        -> PyResult<PyObject> {
+        let gammas = pylist2bigint(gammas)?;
+        let vss_points = pylist2vss(t, n, vss_points)?;
+        let sigma = self.inner.aggregate(&gammas, &vss_points)
+            .map_err(PyErr::from)?;
+        let sigma = bigint2bytes(&sigma.to_big_int()).unwrap();
+        Ok(PyBytes::new(_py, &sigma).to_object(_py))
+    }
 
-    return e_y_plus_v == sigma_g;
+    /// R() -> bytes
+    /// --
+    ///
+    /// get FROST versioned R point (34b), used as V of verify_threshold_sign
+    fn R(&self, _py: Python) -> PyObject {
+        let bytes = encode_frost_r(&self.inner.R().get_element().serialize());
+        PyBytes::new(_py, &bytes).to_object(_py)
+    }
 }
@@ -0,0 +1,327 @@
+use crate::error::Error;
+use crate::keystore::ThresholdShare;
+use crate::secret::{SecretScalar, Wipe};
+use crate::threshold::{
+    lagrange_coefficient, lagrange_coefficient_at, make_parties_index, public_share, sum_public_points,
+};
+use emerald_city::curv::cryptographic_primitives::secret_sharing::feldman_vss::{
+    VerifiableSS, ShamirSecretSharing,
+};
+use emerald_city::curv::elliptic::curves::secp256_k1::{FE, GE};
+use emerald_city::curv::elliptic::curves::traits::{ECPoint, ECScalar};
+
+
+/// proactive refresh, share a zero-constant polynomial to all parties
+/// vss of random r is published, commitments[0] = r*G only masks the constant
+/// return vss and delta_j = f(x_j) - r of each party (same order as parties_index)
+pub fn refresh_dealing(share: &ThresholdShare) -> (VerifiableSS, Vec<FE>) {
+    let positions: Vec<usize> = share.parties_index.iter().map(|i| i + 1).collect();
+    let mut r: FE = ECScalar::new_random();
+    let (vss, mut shares) = VerifiableSS::share_at_indices(share.t, share.n, &r, &positions);
+    let deltas = shares.iter().map(|s| s.sub(&r.get_element())).collect();
+    shares.wipe();
+    r.wipe();
+    (vss, deltas)
+}
+
+/// delta*G + C_0 == f(x)*G, index is party index (start from 0)
+pub fn verify_refresh_share(vss: &VerifiableSS, delta: &FE, index: usize) -> bool {
+    let g: GE = ECPoint::generator();
+    match vss.commitments.get(0) {
+        Some(constant) => g * delta + constant.clone() == vss.get_point_commitment(index + 1),
+        None => false,
+    }
+}
+
+/// add all deltas sent to me and return refreshed share, Y is unchanged
+/// refresh_vss and deltas: one per dealer, every party must use the same dealers
+/// new vss_points is one group polynomial, old shares do not match with it
+pub fn apply_refresh(share: &ThresholdShare, refresh_vss: &[VerifiableSS], deltas: &[FE])
+    -> Result<ThresholdShare, Error> {
+    if refresh_vss.len() != deltas.len() {
+        return Err(Error::LengthMismatch { name: "deltas", expected: refresh_vss.len(), actual: deltas.len() });
+    } else if refresh_vss.len() < 1 {
+        return Err(Error::InvalidParams("no refresh dealer found".to_owned()));
+    }
+    let index = share.parties_index[share.my_index];
+    for (dealer, (vss, delta)) in refresh_vss.iter().zip(deltas.iter()).enumerate() {
+        if vss.parameters.threshold != share.t || vss.commitments.len() != share.t + 1
+            || !verify_refresh_share(vss, delta, index) {
+            return Err(Error::VssValidation { party_index: dealer });
+        }
+    }
+    let group_vss = combine_vss(share.t, share.n, &share.Y, &share.vss_points, refresh_vss);
+    let mut delta_sum = deltas[1..].iter().fold(deltas[0].clone(), |acc, delta| acc + delta.clone());
+    let new_share = SecretScalar::new(share.share.add(&delta_sum));
+    delta_sum.wipe();
+    let refreshed = ThresholdShare {
+        t: share.t,
+        n: share.n,
+        my_index: share.my_index,
+        parties_index: share.parties_index.clone(),
+        share: new_share,
+        Y: share.Y.clone(),
+        vss_points: vec![group_vss],
+    };
+    refreshed.check_consistency()?;
+    Ok(refreshed)
+}
+
+/// resharing to new committee of (new_t, new_n), Y is unchanged
+/// dealers: parties_index of old holders taking part (t+1 or more), must include mine
+/// new_parties_index: new holder's index (start from 0), None is [0, 1, .., new_n-1]
+/// return vss of lambda_i*x_i and shares of new holders (same order as new_parties_index)
+pub fn reshare_dealing(share: &ThresholdShare, dealers: &[usize], new_t: usize, new_n: usize,
+                       new_parties_index: Option<Vec<usize>>) -> Result<(VerifiableSS, Vec<FE>), Error> {
+    if new_t >= new_n {
+        return Err(Error::InvalidParams("require \"t < n\"".to_owned()));
+    }
+    check_dealers(share.t, dealers)?;
+    let index = share.parties_index[share.my_index];
+    if !dealers.contains(&index) {
+        return Err(Error::InvalidParams("cannot find your position in dealers".to_owned()));
+    }
+    let positions = make_parties_index(new_n, new_parties_index)?;
+    let mut w = share.share.mul(&lagrange_coefficient(index, dealers));
+    let result = VerifiableSS::share_at_indices(new_t, new_n, &w, &positions);
+    w.wipe();
+    Ok(result)
+}
+
+/// new holder collects one dealing from each dealer (same order as dealers) and returns new share
+/// old_vss_points and Y are public data of old committee, dealer's constant must be lambda_i*Y_i
+/// and sum of constants must be Y, dealer of broken dealing is reported by VssValidation
+pub fn apply_reshare(Y: &GE, old_vss_points: &[VerifiableSS], dealers: &[usize],
+                     reshare_vss: &[VerifiableSS], shares: &[FE], new_t: usize, new_n: usize,
+                     my_index: usize, new_parties_index: Option<Vec<usize>>) -> Result<ThresholdShare, Error> {
+    if new_t >= new_n {
+        return Err(Error::InvalidParams("require \"t < n\"".to_owned()));
+    } else if new_n <= my_index {
+        return Err(Error::InvalidParams(format!("my_index={} is out of range", my_index)));
+    } else if reshare_vss.len() != dealers.len() {
+        return Err(Error::LengthMismatch { name: "vss_points", expected: dealers.len(), actual: reshare_vss.len() });
+    } else if shares.len() != dealers.len() {
+        return Err(Error::LengthMismatch { name: "secret_scalars", expected: dealers.len(), actual: shares.len() });
+    }
+    let old_t = match old_vss_points.get(0) {
+        Some(vss) if vss.commitments.len() > 0 => vss.commitments.len() - 1,
+        _ => return Err(Error::InvalidParams("no old vss_points found".to_owned())),
+    };
+    if old_vss_points.iter().any(|vss| vss.commitments.len() != old_t + 1) {
+        return Err(Error::InvalidParams("old vss_points have different threshold".to_owned()));
+    }
+    check_dealers(old_t, dealers)?;
+    let parties_index: Vec<usize> = make_parties_index(new_n, new_parties_index)?
+        .into_iter().map(|i| i - 1).collect();
+    let position = parties_index[my_index] + 1;
+    for ((dealer, vss), share) in dealers.iter().zip(reshare_vss.iter()).zip(shares.iter()) {
+        let expected = public_share(old_vss_points, *dealer)? * &lagrange_coefficient(*dealer, dealers);
+        if vss.commitments.len() != new_t + 1 || vss.commitments[0] != expected
+            || vss.validate_share(share, position).is_err() {
+            return Err(Error::VssValidation { party_index: *dealer });
+        }
+    }
+    let constants: Vec<GE> = reshare_vss.iter().map(|vss| vss.commitments[0].clone()).collect();
+    if &sum_public_points(&constants)? != Y {
+        return Err(Error::InvalidParams("group key do not match with reshare commitments".to_owned()));
+    }
+    let mut iter = shares.iter();
+    let head = iter.next().unwrap().clone();
+    let reshared = ThresholdShare {
+        t: new_t,
+        n: new_n,
+        my_index,
+        parties_index,
+        share: SecretScalar::new(iter.fold(head, |acc, share| acc + share.clone())),
+        Y: Y.clone(),
+        vss_points: vec![combine_vss(new_t, new_n, Y, &[], reshare_vss)],
+    };
+    reshared.check_consistency()?;
+    Ok(reshared)
+}
+
+/// t+1 or more unique old holders
+fn check_dealers(t: usize, dealers: &[usize]) -> Result<(), Error> {
+    let mut sorted = dealers.to_vec();
+    sorted.sort();
+    sorted.dedup();
+    if sorted.len() != dealers.len() {
+        Err(Error::InvalidParams("duplicate index in dealers".to_owned()))
+    } else if dealers.len() < t + 1 {
+        Err(Error::LengthMismatch { name: "dealers", expected: t + 1, actual: dealers.len() })
+    } else {
+        Ok(())
+    }
+}
+
+/// lost share recovery, helper splits lambda_i(lost)*x_i into random pieces
+/// helpers: parties_index of t+1 (or more) helpers, must include mine and exclude lost_index
+/// send pieces[j] to helpers[j], keep my piece
+pub fn recovery_dealing(share: &ThresholdShare, helpers: &[usize], lost_index: usize) -> Result<Vec<FE>, Error> {
+    check_dealers(share.t, helpers)?;
+    let index = share.parties_index[share.my_index];
+    if !helpers.contains(&index) {
+        return Err(Error::InvalidParams("cannot find your position in helpers".to_owned()));
+    } else if helpers.contains(&lost_index) || !share.parties_index.contains(&lost_index) {
+        return Err(Error::InvalidParams(format!("lost_index={} is not recoverable by helpers", lost_index)));
+    }
+    let mut delta = share.share.mul(&lagrange_coefficient_at(index, helpers, lost_index));
+    let mut pieces: Vec<FE> = (1..helpers.len()).map(|_| ECScalar::new_random()).collect();
+    let last = pieces.iter().fold(delta.clone(), |acc, piece| acc.sub(&piece.get_element()));
+    pieces.push(last);
+    delta.wipe();
+    Ok(pieces)
+}
+
+/// sum of pieces received from all helpers, send to recovering party
+/// each piece is random, so neither helpers nor recovering party learn other's share
+pub fn recovery_contribution(pieces: &[FE]) -> Result<FE, Error> {
+    let mut iter = pieces.iter();
+    let head = iter.next().ok_or(Error::InvalidParams("no recovery piece found".to_owned()))?;
+    Ok(iter.fold(head.clone(), |acc, piece| acc + piece.clone()))
+}
+
+/// recovering party rebuilds the share from all helper's contributions
+/// public data (t, n, parties_index, Y, vss_points) is kept or given by helpers
+/// recovered share must match with vss_points, or VssValidation of my_index
+pub fn recover_share(t: usize, n: usize, my_index: usize, parties_index: Vec<usize>, Y: GE,
+                     vss_points: Vec<VerifiableSS>, contributions: &[FE]) -> Result<ThresholdShare, Error> {
+    if contributions.len() < t + 1 {
+        return Err(Error::LengthMismatch { name: "contributions", expected: t + 1, actual: contributions.len() });
+    }
+    let recovered = ThresholdShare {
+        t,
+        n,
+        my_index,
+        parties_index,
+        share: SecretScalar::new(recovery_contribution(contributions)?),
+        Y,
+        vss_points,
+    };
+    recovered.check_consistency()?;
+    Ok(recovered)
+}
+
+/// one vss of group polynomial, constant is Y and the others are sum of all coefficients
+/// constants of refresh_vss are masks, not added
+fn combine_vss(t: usize, n: usize, Y: &GE, vss_points: &[VerifiableSS], refresh_vss: &[VerifiableSS])
+    -> VerifiableSS {
+    let mut commitments = vec![Y.clone()];
+    for k in 1..(t + 1) {
+        let mut iter = vss_points.iter().chain(refresh_vss.iter())
+            .map(|vss| vss.commitments[k].clone());
+        let head = iter.next().unwrap();
+        commitments.push(iter.fold(head, |acc, point| acc + point));
+    }
+    VerifiableSS {
+        parameters: ShamirSecretSharing { threshold: t, share_count: n },
+        commitments,
+    }
+}
+
+
+#[cfg(test)]
+mod Test {
+    use crate::refresh::*;
+    use crate::testutils::keygen;
+
+    fn keygen_shares(t: usize, n: usize) -> (Vec<ThresholdShare>, GE) {
+        let (shares, vss_points, Y) = keygen(t, n);
+        let shares = shares.into_iter().enumerate()
+            .map(|(i, share)| ThresholdShare {
+                t, n, my_index: i, parties_index: (0..n).collect(), share: SecretScalar::new(share),
+                Y: Y.clone(), vss_points: vss_points.clone(),
+            })
+            .collect();
+        (shares, Y)
+    }
+
+    fn combine(shares: &[&ThresholdShare]) -> GE {
+        let index: Vec<usize> = shares.iter().map(|s| s.parties_index[s.my_index]).collect();
+        let g: GE = ECPoint::generator();
+        let points: Vec<GE> = shares.iter().zip(index.iter())
+            .map(|(s, i)| s.share.scalar_mul(&g) * &lagrange_coefficient(*i, &index))
+            .collect();
+        sum_public_points(&points).unwrap()
+    }
+
+    #[test]
+    fn test_refresh_keeps_group_key() {
+        let (shares, Y) = keygen_shares(1, 3);
+        let (refresh_vss, deltas): (Vec<VerifiableSS>, Vec<Vec<FE>>) = shares.iter()
+            .map(refresh_dealing).unzip();
+        let refreshed: Vec<ThresholdShare> = shares.iter().enumerate()
+            .map(|(i, share)| {
+                let mine: Vec<FE> = deltas.iter().map(|d| d[i].clone()).collect();
+                apply_refresh(share, &refresh_vss, &mine).unwrap()
+            })
+            .collect();
+        assert!(combine(&[&refreshed[0], &refreshed[2]]) == Y);
+        assert!(refreshed[0].share.with(|x| shares[0].share.with(|y| x != y)));
+        // old share with new share is not the key
+        assert!(combine(&[&shares[0], &refreshed[2]]) != Y);
+
+        // broken delta is detected
+        let mut broken: Vec<FE> = deltas.iter().map(|d| d[1].clone()).collect();
+        broken[2] = ECScalar::new_random();
+        assert!(apply_refresh(&shares[1], &refresh_vss, &broken).is_err());
+    }
+
+    #[test]
+    fn test_reshare_to_new_committee() {
+        let (shares, Y) = keygen_shares(1, 3);
+        let dealers = vec![0, 2];
+        let (reshare_vss, new_shares): (Vec<VerifiableSS>, Vec<Vec<FE>>) = dealers.iter()
+            .map(|i| reshare_dealing(&shares[*i], &dealers, 2, 4, None).unwrap()).unzip();
+        let reshared: Vec<ThresholdShare> = (0..4)
+            .map(|j| {
+                let mine: Vec<FE> = new_shares.iter().map(|s| s[j].clone()).collect();
+                apply_reshare(&Y, &shares[0].vss_points, &dealers, &reshare_vss, &mine, 2, 4, j, None).unwrap()
+            })
+            .collect();
+        assert!(combine(&[&reshared[0], &reshared[1], &reshared[3]]) == Y);
+        assert!(combine(&[&reshared[0], &reshared[3]]) != Y);
+
+        // dealer sharing other secret is detected
+        let (fake_vss, fake_shares) = VerifiableSS::share(2, 4, &ECScalar::new_random());
+        let mut vss = reshare_vss.clone();
+        vss[1] = fake_vss;
+        let mine = vec![new_shares[0][0].clone(), fake_shares[0].clone()];
+        match apply_reshare(&Y, &shares[0].vss_points, &dealers, &vss, &mine, 2, 4, 0, None) {
+            Err(Error::VssValidation { party_index }) => assert_eq!(party_index, 2),
+            _ => panic!("fake dealing is accepted"),
+        }
+    }
+
+    #[test]
+    fn test_recover_lost_share() {
+        let (shares, _) = keygen_shares(2, 4);
+        let helpers = vec![0, 1, 3];
+        let pieces: Vec<Vec<FE>> = helpers.iter()
+            .map(|i| recovery_dealing(&shares[*i], &helpers, 2).unwrap()).collect();
+        let contributions: Vec<FE> = (0..helpers.len())
+            .map(|j| {
+                let received: Vec<FE> = pieces.iter().map(|p| p[j].clone()).collect();
+                recovery_contribution(&received).unwrap()
+            })
+            .collect();
+        let lost = &shares[2];
+        let recovered = recover_share(lost.t, lost.n, 2, lost.parties_index.clone(), lost.Y.clone(),
+                                      lost.vss_points.clone(), &contributions).unwrap();
+        assert!(recovered.share.with(|x| lost.share.with(|y| x == y)));
+        // contributions without any one helper do not sum to the share
+        for skip in 0..contributions.len() {
+            let subset: Vec<FE> = contributions.iter().enumerate()
+                .filter(|(j, _)| *j != skip)
+                .map(|(_, c)| c.clone())
+                .collect();
+            let partial = recovery_contribution(&subset).unwrap();
+            assert!(lost.share.with(|y| &partial != y));
+        }
+
+        let mut broken = contributions.clone();
+        broken[0] = ECScalar::new_random();
+        assert!(recover_share(lost.t, lost.n, 2, lost.parties_index.clone(), lost.Y.clone(),
+                              lost.vss_points.clone(), &broken).is_err());
+    }
+}
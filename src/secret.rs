@@ -0,0 +1,257 @@
+use crate::utils::bigint2bytes;
+use emerald_city::curv::elliptic::curves::secp256_k1::{FE, GE};
+use emerald_city::curv::elliptic::curves::traits::{ECPoint, ECScalar};
+use emerald_city::curv::arithmetic::num_bigint::BigInt;
+use num_traits::Zero;
+use std::ptr;
+use std::sync::{Mutex, MutexGuard};
+use std::sync::atomic::{compiler_fence, Ordering};
+
+
+/// overwrite secret value by zero
+pub trait Wipe {
+    fn wipe(&mut self);
+}
+
+impl Wipe for FE {
+    fn wipe(&mut self) {
+        // volatile write is not removed by optimizer
+        unsafe { ptr::write_volatile(self, FE::zero()) };
+        compiler_fence(Ordering::SeqCst);
+    }
+}
+
+impl Wipe for BigInt {
+    /// note: old limbs are released without overwrite, best effort only
+    fn wipe(&mut self) {
+        *self = BigInt::zero();
+        compiler_fence(Ordering::SeqCst);
+    }
+}
+
+impl<T: Wipe> Wipe for Vec<T> {
+    fn wipe(&mut self) {
+        for value in self.iter_mut() {
+            value.wipe();
+        }
+    }
+}
+
+impl<A: Wipe, B: Wipe> Wipe for (A, B) {
+    fn wipe(&mut self) {
+        self.0.wipe();
+        self.1.wipe();
+    }
+}
+
+
+/// secret scalar on heap, mlock'ed on linux and wiped on drop
+/// clone() copies the secret into new locked memory, wipe() of a clone never touches the original
+/// no Deref, arithmetic is done by methods wiping their temporaries
+pub struct SecretScalar {
+    inner: Box<Locked>,
+}
+
+struct Locked {
+    value: Mutex<FE>,
+}
+
+impl Locked {
+    fn value(&self) -> MutexGuard<FE> {
+        // wipe never panics, poisoned value is still usable
+        self.value.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl Drop for Locked {
+    fn drop(&mut self) {
+        self.value().wipe();
+        memlock::unlock(&*self);
+    }
+}
+
+impl Clone for SecretScalar {
+    fn clone(&self) -> Self {
+        SecretScalar::new(self.with(|value| value.clone()))
+    }
+}
+
+impl SecretScalar {
+    pub fn new(value: FE) -> SecretScalar {
+        let inner = Box::new(Locked { value: Mutex::new(value) });
+        memlock::lock(&*inner);
+        SecretScalar { inner }
+    }
+
+    /// use secret without copy
+    pub fn with<T, F: FnOnce(&FE) -> T>(&self, f: F) -> T {
+        f(&self.inner.value())
+    }
+
+    /// factor*x, caller should wipe it after use
+    pub fn mul(&self, factor: &FE) -> FE {
+        ECScalar::mul(factor, &self.inner.value().get_element())
+    }
+
+    /// addend + factor*x, product of secret is wiped
+    pub fn mul_add(&self, factor: &FE, addend: &FE) -> FE {
+        let mut product = ECScalar::mul(factor, &self.inner.value().get_element());
+        let result = ECScalar::add(addend, &product.get_element());
+        product.wipe();
+        result
+    }
+
+    /// value + x
+    pub fn add(&self, value: &FE) -> FE {
+        self.with(|x| ECScalar::add(value, &x.get_element()))
+    }
+
+    /// addend + factor*x of secret addend (ex. nonce + c*x), copy of addend is wiped
+    pub fn mul_add_secret(&self, factor: &FE, addend: &SecretScalar) -> FE {
+        let mut copied = addend.with(|value| value.clone());
+        let result = self.mul_add(factor, &copied);
+        copied.wipe();
+        result
+    }
+
+    /// -x as new secret
+    pub fn negate(&self) -> SecretScalar {
+        SecretScalar::new(self.with(|value| ECScalar::sub(&FE::zero(), &value.get_element())))
+    }
+
+    /// 32bytes big endian, caller should overwrite it after use
+    pub fn to_bytes(&self) -> [u8;32] {
+        self.with(|value| {
+            let mut int = value.to_big_int();
+            let bytes = bigint2bytes(&int).unwrap();
+            int.wipe();
+            bytes
+        })
+    }
+
+    /// x*P
+    pub fn scalar_mul(&self, point: &GE) -> GE {
+        point.scalar_mul(&self.inner.value().get_element())
+    }
+
+    /// overwrite by zero
+    pub fn wipe(&self) {
+        self.inner.value().wipe();
+    }
+
+    pub fn is_wiped(&self) -> bool {
+        *self.inner.value() == FE::zero()
+    }
+
+    /// copy out and wipe in one lock, None when already wiped (one-time secrets like nonces)
+    pub fn take(&self) -> Option<FE> {
+        let mut value = self.inner.value();
+        if *value == FE::zero() {
+            return None;
+        }
+        let taken = value.clone();
+        value.wipe();
+        Some(taken)
+    }
+}
+
+
+#[cfg(target_os = "linux")]
+mod memlock {
+    use libc::{c_void, mlock, munlock, sysconf, _SC_PAGESIZE};
+    use std::collections::BTreeMap;
+    use std::mem::size_of;
+    use std::sync::Mutex;
+
+    /// number of live secrets on each locked page
+    /// munlock is not reference counted, a page is unlocked when its last secret is dropped
+    static PAGES: Mutex<BTreeMap<usize, usize>> = Mutex::new(BTreeMap::new());
+
+    fn pages<T>(value: &T) -> Vec<usize> {
+        let page_size = match unsafe { sysconf(_SC_PAGESIZE) } {
+            size if 0 < size => size as usize,
+            _ => 4096,
+        };
+        let start = value as *const T as usize;
+        let end = start + size_of::<T>();
+        (start / page_size..(end + page_size - 1) / page_size)
+            .map(|page| page * page_size)
+            .collect()
+    }
+
+    /// keep out of swap, failure (ex. RLIMIT_MEMLOCK) is ignored
+    pub fn lock<T>(value: &T) {
+        let mut counts = PAGES.lock().unwrap_or_else(|err| err.into_inner());
+        for page in pages(value) {
+            let count = counts.entry(page).or_insert(0);
+            if *count == 0 {
+                unsafe { mlock(page as *const c_void, 1) };
+            }
+            *count += 1;
+        }
+    }
+
+    pub fn unlock<T>(value: &T) {
+        let mut counts = PAGES.lock().unwrap_or_else(|err| err.into_inner());
+        for page in pages(value) {
+            let remove = match counts.get_mut(&page) {
+                Some(count) => {
+                    *count -= 1;
+                    *count == 0
+                },
+                None => false,
+            };
+            if remove {
+                counts.remove(&page);
+                unsafe { munlock(page as *const c_void, 1) };
+            }
+        }
+    }
+
+    #[cfg(test)]
+    pub fn lock_count<T>(value: &T) -> usize {
+        let counts = PAGES.lock().unwrap_or_else(|err| err.into_inner());
+        counts.get(&pages(value)[0]).cloned().unwrap_or(0)
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod memlock {
+    pub fn lock<T>(_value: &T) {}
+    pub fn unlock<T>(_value: &T) {}
+}
+
+
+#[cfg(test)]
+mod Test {
+    use crate::secret::*;
+
+    #[test]
+    fn test_secret_scalar_wipe() {
+        let value: FE = ECScalar::new_random();
+        let mut copied = value.clone();
+        let secret = SecretScalar::new(value.clone());
+        let cloned = secret.clone();
+        drop(secret);
+        assert!(cloned.with(|x| x == &value));
+        copied.wipe();
+        assert!(copied == FE::zero());
+        // clone is a copy, wipe of one never reaches the other
+        let other = cloned.clone();
+        cloned.wipe();
+        assert!(cloned.is_wiped());
+        assert!(other.with(|x| x == &value));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_page_lock_is_counted() {
+        let values = [1u64, 2u64];
+        memlock::lock(&values[0]);
+        memlock::lock(&values[1]);
+        memlock::unlock(&values[0]);
+        assert_eq!(memlock::lock_count(&values[1]), 1);
+        memlock::unlock(&values[1]);
+        assert_eq!(memlock::lock_count(&values[1]), 0);
+    }
+}
@@ -0,0 +1,50 @@
+use crate::aggregate::{Aggregate, EphemeralKey};
+use crate::keypair::KeyPair;
+use crate::threshold::{group_public_key, KeygenProof, ThresholdKey};
+use emerald_city::curv::cryptographic_primitives::secret_sharing::feldman_vss::VerifiableSS;
+use emerald_city::curv::elliptic::curves::secp256_k1::{FE, GE};
+use emerald_city::curv::arithmetic::num_bigint::BigInt;
+
+
+/// n of n aggregates of n random signers, aggs[i] is signer i
+pub fn aggregates(n: usize) -> Vec<Aggregate> {
+    let pairs: Vec<KeyPair> = (0..n).map(|_| KeyPair::new()).collect();
+    let ephs: Vec<EphemeralKey> = (0..n).map(|_| EphemeralKey::new()).collect();
+    let signers: Vec<GE> = pairs.iter().map(|p| p.public.clone()).collect();
+    let ephemeral: Vec<GE> = ephs.iter().map(|e| e.keypair.public.clone()).collect();
+    let commitments: Vec<BigInt> = ephs.iter().map(|e| e.commitment.clone()).collect();
+    let blind_factors: Vec<BigInt> = ephs.iter().map(|e| e.blind_factor.clone()).collect();
+    pairs.iter().zip(ephs.iter())
+        .map(|(p, e)| Aggregate::generate(
+            &signers, &ephemeral, &commitments, &blind_factors, p, e).unwrap())
+        .collect()
+}
+
+/// session id of test keygen
+pub const CONTEXT: &[u8] = b"test session";
+
+/// keygen round 1 of n parties, keys with public keys and proofs of constant term
+pub fn threshold_keys(t: usize, n: usize) -> (Vec<ThresholdKey>, Vec<GE>, Vec<KeygenProof>) {
+    let keys: Vec<ThresholdKey> = (0..n)
+        .map(|_| ThresholdKey::generate(t, n, None).unwrap()).collect();
+    let signers: Vec<GE> = keys.iter().map(|k| k.keypair.public.clone()).collect();
+    let proofs: Vec<KeygenProof> = keys.iter().enumerate()
+        .map(|(i, k)| k.prove_constant_term(CONTEXT, i)).collect();
+    (keys, signers, proofs)
+}
+
+/// plain vss_points and secret_shares of every dealer
+pub fn dealings(keys: &[ThresholdKey]) -> (Vec<VerifiableSS>, Vec<Vec<FE>>) {
+    keys.iter().map(|k| k.get_variable_secret_sharing()).unzip()
+}
+
+/// t of n keygen by all n dealers, return x_i of each party, vss_points and Y
+pub fn keygen(t: usize, n: usize) -> (Vec<FE>, Vec<VerifiableSS>, GE) {
+    let (mut keys, signers, proofs) = threshold_keys(t, n);
+    let (vss_points, secret_shares) = dealings(&keys);
+    let shares: Vec<FE> = keys.iter_mut()
+        .map(|k| k.keygen_t_n_parties(&signers, &vss_points, &secret_shares, &proofs, CONTEXT).unwrap())
+        .collect();
+    let qual: Vec<usize> = (0..n).collect();
+    (shares, vss_points, group_public_key(&signers, &proofs, CONTEXT, &qual).unwrap())
+}
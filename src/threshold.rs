@@ -0,0 +1,840 @@
+use crate::error::Error;
+use crate::ecies;
+use crate::hashing::{challenge, commitment, frost_challenge, index_bytes, keygen_pok_challenge, point_bytes, scalar_bytes, SigVersion};
+use crate::keypair::KeyPair;
+use crate::secret::{SecretScalar, Wipe};
+use crate::utils::KeyType;
+use emerald_city::curv::cryptographic_primitives::secret_sharing::feldman_vss::VerifiableSS;
+use emerald_city::curv::cryptographic_primitives::hashing::hash_sha256::HSha256;
+use emerald_city::curv::arithmetic::traits::Samplable;
+use emerald_city::curv::cryptographic_primitives::hashing::traits::Hash;
+use emerald_city::curv::elliptic::curves::secp256_k1::{FE, GE};
+use emerald_city::curv::elliptic::curves::traits::{ECPoint, ECScalar};
+use emerald_city::curv::arithmetic::num_bigint::BigInt;
+
+
+pub struct ThresholdKey {
+    pub keypair: KeyPair,
+    pub my_index: Option<usize>,
+    pub parties_index: Vec<usize>,
+    pub t: usize, // threshold
+    pub n: usize,
+}
+
+impl ThresholdKey {
+    /// generate random threshold key (ex. [0, 1, 2, 3, 4])
+    /// t: required number of cosigner (ex. 3)
+    /// n: total number of cosigner (ex. 5)
+    /// parties_index: (ex. [0, 1, 3] means signed by 0+1+3)
+    pub fn generate(t: usize, n: usize, parties_index: Option<Vec<usize>>)
+        -> Result<ThresholdKey, Error> {
+        if t >= n {
+            return Err(Error::InvalidParams("require \"t < n\"".to_owned()));
+        };
+        let parties_index = make_parties_index(n, parties_index)?;
+        let my_index = None;  // unknown at this point
+        let keypair = KeyPair::new();
+        {
+            // commitment check
+            let blind_factor = BigInt::sample(256);
+            let com = commitment(&keypair.public, &blind_factor)?;  // com = bc1 of KeyGenBroadcastMessage1
+            let h = commitment(&keypair.public, &blind_factor)?;
+            if h != com {
+                return Err(Error::Commitment { party_index: vec![] });
+            }
+        }
+        Ok(ThresholdKey { keypair, my_index, parties_index, t, n })
+    }
+
+    /// generate threshold key from secret (ex. [0, 1, 2, 3, 4])
+    /// t: required number of cosigner (ex. 3)
+    /// n: total number of cosigner (ex. 5)
+    /// secret: my threshold secret key
+    /// my_index: my index on party list (ex. 1)
+    /// parties_index: (ex. [0, 1, 3] means signed by 0+1+3)
+    pub fn from_secret_key(t: usize, n: usize, secret: &[u8], my_index: usize, parties_index: Option<Vec<usize>>)
+        -> Result<ThresholdKey, Error> {
+        if t >= n {
+            return Err(Error::InvalidParams("require \"t < n\"".to_owned()));
+        };
+        let keypair = KeyPair::from_secret_key(secret);
+        let parties_index = make_parties_index(n, parties_index)?;
+        let my_index = Some(my_index);
+        Ok(ThresholdKey { keypair, my_index, parties_index, t, n })
+    }
+
+    /// wipe my secret key
+    pub fn wipe(&mut self) {
+        self.keypair.wipe();
+    }
+
+    /// return blind_factor & commitment
+    pub fn get_commitment(&self) -> (BigInt, BigInt) {
+        let blind_factor = BigInt::sample(256);
+        // blind_factor is 256bit, encoding never fails
+        let com = commitment(&self.keypair.public, &blind_factor).unwrap();  // com = bc1 of KeyGenBroadcastMessage1
+        (blind_factor, com)
+    }
+
+    /// return vss_scheme and secret_shares
+    pub fn get_variable_secret_sharing(&self) -> (VerifiableSS, Vec<FE>) {
+        // index users [0, 1, .., n] => [1, 2, ...,n+1]
+        self.keypair.secret.with(|secret| VerifiableSS::share_at_indices(
+            self.t, self.n, secret, &self.parties_index))
+    }
+
+    /// return vss_scheme and secret_shares encrypted to each signer by ECIES
+    /// ciphertexts[j] is for signers[j], aad binds dealer, receiver and context
+    /// dealing keeps the plain shares for answer_complaints_encrypted, wipe it after the complaint round
+    pub fn get_encrypted_secret_sharing(&self, signers: &[GE], context: &[u8])
+        -> Result<(VerifiableSS, Vec<Vec<u8>>, Dealing), Error> {
+        if self.n != signers.len() {
+            return Err(Error::LengthMismatch {
+                name: "signers", expected: self.n, actual: signers.len() });
+        }
+        let dealer = self.find_my_index(signers)?;
+        let (vss_scheme, mut secret_shares) = self.get_variable_secret_sharing();
+        let ciphertexts = secret_shares.iter()
+            .zip(signers.iter())
+            .enumerate()
+            .map(|(receiver, (share, signer))| {
+                let mut plaintext = scalar_bytes(share);
+                let result = ecies::encrypt(signer, &plaintext, &share_aad(dealer, receiver, context));
+                for b in plaintext.iter_mut() {
+                    *b = 0;
+                }
+                result
+            })
+            .collect::<Result<Vec<Vec<u8>>, Error>>();
+        let ciphertexts = match ciphertexts {
+            Ok(ciphertexts) => ciphertexts,
+            Err(e) => {
+                secret_shares.wipe();
+                return Err(e);
+            }
+        };
+        let dealing = Dealing { shares: secret_shares.iter().map(|share| SecretScalar::new(share.clone())).collect() };
+        secret_shares.wipe();
+        Ok((vss_scheme, ciphertexts, dealing))
+    }
+
+    /// prove knowledge of my constant term (secret of keypair)
+    /// context: session id shared by all parties, my_index: my position in signers
+    pub fn prove_constant_term(&self, context: &[u8], my_index: usize) -> KeygenProof {
+        let g: GE = ECPoint::generator();
+        let mut k: FE = ECScalar::new_random();
+        let R = g * &k;
+        let c = keygen_pok_challenge(my_index, &self.keypair.public, &R, context);
+        let s = self.keypair.secret.mul_add(&c, &k);
+        k.wipe();
+        KeygenProof { R, s }
+    }
+
+    /// generate threshold key (t of n)
+    /// return my share x_i
+    /// proofs: all dealer's prove_constant_term, same order as signers
+    /// fails with the first dealer (lowest index) whose share or commitment is broken,
+    /// use complaints and keygen_t_n_parties_qual to continue without faulty dealers
+    pub fn keygen_t_n_parties(&mut self, signers: &[GE], vss_scheme_vec: &[VerifiableSS], secret_shares_vec: &[Vec<FE>],
+                              proofs: &[KeygenProof], context: &[u8]) -> Result<FE, Error> {
+        self.check_keygen_params(signers, vss_scheme_vec, secret_shares_vec.len())?;
+        verify_keygen_proofs(signers, proofs, context)?;
+        let my_index = self.find_my_index(signers)?;
+        let party_share = received_shares(my_index, secret_shares_vec)?;
+        self.keygen_from_received(my_index, signers, vss_scheme_vec, party_share)
+    }
+
+    /// keygen_t_n_parties with shares of get_encrypted_secret_sharing
+    /// encrypted_shares_vec: all dealer's ciphertexts, only mine is decrypted
+    /// share which cannot be decrypted is VssValidation of the dealer
+    pub fn keygen_t_n_parties_encrypted(&mut self, signers: &[GE], vss_scheme_vec: &[VerifiableSS],
+                                        encrypted_shares_vec: &[Vec<Vec<u8>>], proofs: &[KeygenProof],
+                                        context: &[u8]) -> Result<FE, Error> {
+        self.check_keygen_params(signers, vss_scheme_vec, encrypted_shares_vec.len())?;
+        verify_keygen_proofs(signers, proofs, context)?;
+        let my_index = self.find_my_index(signers)?;
+        let (mut party_share, undecrypted) = self.decrypt_received(my_index, encrypted_shares_vec, context);
+        if let Some(dealer) = undecrypted.first() {
+            party_share.wipe();
+            return Err(Error::VssValidation { party_index: *dealer });
+        }
+        self.keygen_from_received(my_index, signers, vss_scheme_vec, party_share)
+    }
+
+    // my share from each dealer, zero in place of shares which cannot be decrypted
+    // return shares and dealers of undecrypted shares
+    fn decrypt_received(&self, my_index: usize, encrypted_shares_vec: &[Vec<Vec<u8>>], context: &[u8])
+        -> (Vec<FE>, Vec<usize>) {
+        let mut party_share = Vec::with_capacity(encrypted_shares_vec.len());
+        let mut undecrypted = Vec::new();
+        for (dealer, ciphertexts) in encrypted_shares_vec.iter().enumerate() {
+            let decrypted = ciphertexts.get(my_index)
+                .ok_or(Error::VssValidation { party_index: dealer })
+                .and_then(|c| ecies::decrypt(&self.keypair, c, &share_aad(dealer, my_index, context)));
+            match decrypted {
+                Ok(ref plaintext) if plaintext.len() == 32 =>
+                    party_share.push(ECScalar::from(&BigInt::from_bytes_be(plaintext))),
+                _ => {
+                    party_share.push(FE::zero());
+                    undecrypted.push(dealer);
+                }
+            }
+            if let Ok(mut plaintext) = decrypted {
+                for b in plaintext.iter_mut() {
+                    *b = 0;
+                }
+            }
+        }
+        (party_share, undecrypted)
+    }
+
+    // sum my shares from each dealer after vss check
+    fn keygen_from_received(&mut self, my_index: usize, signers: &[GE], vss_scheme_vec: &[VerifiableSS],
+                            mut party_share: Vec<FE>) -> Result<FE, Error> {
+        // calculate party_share sum
+        let x_i = party_share.iter().fold(FE::zero(), |acc, x| acc + x);
+
+        // verify vss construct keypair
+        let failed = self.check_dealers(my_index, signers, vss_scheme_vec, &party_share);
+        party_share.wipe();
+        if let Some(dealer) = failed.first() {
+            return Err(Error::VssValidation { party_index: *dealer });
+        }
+        // success generate sharedKey
+        self.my_index = Some(my_index);
+        Ok(x_i)
+    }
+
+    /// GJKR complaint round, return my complaints against every broken dealer
+    /// broadcast them, accused dealers reveal the disputed share by answer_complaints
+    pub fn complaints(&mut self, signers: &[GE], vss_scheme_vec: &[VerifiableSS], secret_shares_vec: &[Vec<FE>])
+        -> Result<Vec<Complaint>, Error> {
+        self.check_keygen_params(signers, vss_scheme_vec, secret_shares_vec.len())?;
+        let my_index = self.find_my_index(signers)?;
+        let party_share = received_shares(my_index, secret_shares_vec)?;
+        Ok(self.complaints_from_received(my_index, signers, vss_scheme_vec, party_share, Vec::new()))
+    }
+
+    /// complaints with shares of get_encrypted_secret_sharing
+    /// share which cannot be decrypted is a complaint too
+    pub fn complaints_encrypted(&mut self, signers: &[GE], vss_scheme_vec: &[VerifiableSS],
+                                encrypted_shares_vec: &[Vec<Vec<u8>>], context: &[u8])
+        -> Result<Vec<Complaint>, Error> {
+        self.check_keygen_params(signers, vss_scheme_vec, encrypted_shares_vec.len())?;
+        let my_index = self.find_my_index(signers)?;
+        let (party_share, undecrypted) = self.decrypt_received(my_index, encrypted_shares_vec, context);
+        Ok(self.complaints_from_received(my_index, signers, vss_scheme_vec, party_share, undecrypted))
+    }
+
+    fn complaints_from_received(&mut self, my_index: usize, signers: &[GE], vss_scheme_vec: &[VerifiableSS],
+                                mut party_share: Vec<FE>, mut failed: Vec<usize>) -> Vec<Complaint> {
+        failed.extend(self.check_dealers(my_index, signers, vss_scheme_vec, &party_share));
+        failed.sort();
+        failed.dedup();
+        party_share.wipe();
+        self.my_index = Some(my_index);
+        failed.into_iter()
+            .map(|dealer| Complaint { accuser: my_index, dealer, share: None })
+            .collect()
+    }
+
+    /// reveal my shares disputed by complaints against me
+    /// secret_shares: my shares of get_variable_secret_sharing, see answer_complaints_encrypted for encrypted one
+    pub fn answer_complaints(&self, complaints: &[Complaint], secret_shares: &[FE]) -> Result<Vec<Complaint>, Error> {
+        let my_index = self.my_index.ok_or(
+            Error::InvalidParams("call complaints before answer".to_owned()))?;
+        let mut answers = Vec::new();
+        for complaint in complaints.iter().filter(|c| c.dealer == my_index) {
+            let share = secret_shares.get(complaint.accuser).ok_or(
+                Error::InvalidParams(format!("unknown accuser={}", complaint.accuser)))?;
+            answers.push(Complaint { share: Some(share.clone()), ..complaint.clone() });
+        }
+        Ok(answers)
+    }
+
+    /// answer_complaints with the dealing of get_encrypted_secret_sharing
+    pub fn answer_complaints_encrypted(&self, complaints: &[Complaint], dealing: &Dealing)
+        -> Result<Vec<Complaint>, Error> {
+        if dealing.shares.len() < 1 || dealing.shares.iter().any(|share| share.is_wiped()) {
+            return Err(Error::InvalidParams("dealing is empty or wiped".to_owned()));
+        }
+        let mut secret_shares: Vec<FE> = dealing.shares.iter().map(|share| share.with(|x| x.clone())).collect();
+        let result = self.answer_complaints(complaints, &secret_shares);
+        secret_shares.wipe();
+        result
+    }
+
+    /// QUAL set, dealers which are not disqualified
+    /// disqualified: commitment do not match public key, no valid proof, more than t accusers,
+    /// or a complaint is not answered with a valid share
+    /// complaints: all complaints, answered ones have share
+    /// up to t faulty dealers are excluded when n >= 2t+1
+    pub fn qualified_dealers(&self, signers: &[GE], vss_scheme_vec: &[VerifiableSS], complaints: &[Complaint],
+                             proofs: &[KeygenProof], context: &[u8]) -> Result<Vec<usize>, Error> {
+        if self.n != signers.len() {
+            return Err(Error::LengthMismatch {
+                name: "signers", expected: self.n, actual: signers.len() });
+        } else if self.n != vss_scheme_vec.len() {
+            return Err(Error::LengthMismatch {
+                name: "vss_points", expected: self.n, actual: vss_scheme_vec.len() });
+        }
+        let qual = (0..self.n)
+            .filter(|dealer| {
+                let vss_scheme = &vss_scheme_vec[*dealer];
+                if vss_scheme.commitments.get(0) != Some(&signers[*dealer]) {
+                    return false;
+                }
+                match proofs.get(*dealer) {
+                    Some(proof) if verify_constant_term(&signers[*dealer], *dealer, context, proof) => (),
+                    _ => return false,
+                }
+                let mut accusers: Vec<usize> = Vec::new();
+                for complaint in complaints.iter().filter(|c| c.dealer == *dealer) {
+                    let is_valid = match (&complaint.share, self.parties_index.get(complaint.accuser)) {
+                        (Some(share), Some(position)) => vss_scheme.validate_share(share, *position).is_ok(),
+                        _ => false,
+                    };
+                    if !is_valid {
+                        return false;
+                    } else if !accusers.contains(&complaint.accuser) {
+                        accusers.push(complaint.accuser);
+                    }
+                }
+                accusers.len() <= self.t
+            })
+            .collect();
+        Ok(qual)
+    }
+
+    /// generate threshold key from QUAL dealers only
+    /// revealed shares of my complaints replace the broken ones
+    /// group key is group_public_key of QUAL
+    pub fn keygen_t_n_parties_qual(&mut self, signers: &[GE], vss_scheme_vec: &[VerifiableSS],
+                                   secret_shares_vec: &[Vec<FE>], qual: &[usize], complaints: &[Complaint],
+                                   proofs: &[KeygenProof], context: &[u8]) -> Result<FE, Error> {
+        self.check_keygen_params(signers, vss_scheme_vec, secret_shares_vec.len())?;
+        self.check_qual(signers, qual, proofs, context)?;
+        let my_index = self.find_my_index(signers)?;
+        let party_share = received_shares(my_index, secret_shares_vec)?;
+        self.keygen_qual_from_received(my_index, vss_scheme_vec, party_share, qual, complaints)
+    }
+
+    /// keygen_t_n_parties_qual with shares of get_encrypted_secret_sharing
+    /// undecrypted share is replaced by the answer of my complaint
+    pub fn keygen_t_n_parties_qual_encrypted(&mut self, signers: &[GE], vss_scheme_vec: &[VerifiableSS],
+                                             encrypted_shares_vec: &[Vec<Vec<u8>>], qual: &[usize],
+                                             complaints: &[Complaint], proofs: &[KeygenProof], context: &[u8])
+        -> Result<FE, Error> {
+        self.check_keygen_params(signers, vss_scheme_vec, encrypted_shares_vec.len())?;
+        self.check_qual(signers, qual, proofs, context)?;
+        let my_index = self.find_my_index(signers)?;
+        let (party_share, _) = self.decrypt_received(my_index, encrypted_shares_vec, context);
+        self.keygen_qual_from_received(my_index, vss_scheme_vec, party_share, qual, complaints)
+    }
+
+    fn check_qual(&self, signers: &[GE], qual: &[usize], proofs: &[KeygenProof], context: &[u8])
+        -> Result<(), Error> {
+        if qual.len() < 1 {
+            return Err(Error::InvalidParams("QUAL is empty".to_owned()));
+        } else if qual.iter().any(|dealer| self.n <= *dealer) {
+            return Err(Error::InvalidParams("QUAL has unknown dealer".to_owned()));
+        }
+        group_public_key(signers, proofs, context, qual)?;
+        Ok(())
+    }
+
+    // sum QUAL dealer's shares, revealed shares of my complaints replace the broken ones
+    fn keygen_qual_from_received(&mut self, my_index: usize, vss_scheme_vec: &[VerifiableSS],
+                                 mut party_share: Vec<FE>, qual: &[usize], complaints: &[Complaint])
+        -> Result<FE, Error> {
+        for complaint in complaints.iter().filter(|c| c.accuser == my_index) {
+            if let (Some(share), Some(slot)) = (&complaint.share, party_share.get_mut(complaint.dealer)) {
+                *slot = share.clone();
+            }
+        }
+        let position = self.parties_index[my_index];
+        let mut x_i = FE::zero();
+        for dealer in qual {
+            if vss_scheme_vec[*dealer].validate_share(&party_share[*dealer], position).is_err() {
+                party_share.wipe();
+                x_i.wipe();
+                return Err(Error::VssValidation { party_index: *dealer });
+            }
+            x_i = x_i + party_share[*dealer].clone();
+        }
+        party_share.wipe();
+        self.my_index = Some(my_index);
+        Ok(x_i)
+    }
+
+    fn check_keygen_params(&self, signers: &[GE], vss_scheme_vec: &[VerifiableSS], secret_shares_count: usize)
+        -> Result<(), Error> {
+        if self.n != signers.len() {
+            return Err(Error::LengthMismatch {
+                name: "signers", expected: self.n, actual: signers.len() });
+        } else if self.n != vss_scheme_vec.len() {
+            return Err(Error::LengthMismatch {
+                name: "vss_points", expected: self.n, actual: vss_scheme_vec.len() });
+        } else if self.n != secret_shares_count {
+            return Err(Error::LengthMismatch {
+                name: "secret_scalars", expected: self.n, actual: secret_shares_count });
+        }
+        Ok(())
+    }
+
+    /// your index
+    fn find_my_index(&self, signers: &[GE]) -> Result<usize, Error> {
+        match self.my_index {
+            Some(i) => Ok(i),
+            None => signers.iter().position(|signer| signer == &self.keypair.public)
+                .ok_or(Error::InvalidParams("cannot find your position".to_owned())),
+        }
+    }
+
+    /// return all dealers (sorted) whose share or commitment is broken
+    fn check_dealers(&self, my_index: usize, signers: &[GE], vss_scheme_vec: &[VerifiableSS], party_share: &[FE])
+        -> Vec<usize> {
+        let position = self.parties_index[my_index];
+        (0..self.n)
+            .filter(|i| {
+                // share or signer's commitment is broken
+                vss_scheme_vec[*i].validate_share(&party_share[*i], position).is_err()
+                    || vss_scheme_vec[*i].commitments[0] != signers[*i]
+            })
+            .collect()
+    }
+}
+
+/// GJKR complaint, accuser received a broken share from dealer
+/// share is revealed by the dealer in answer, None until answered
+#[derive(Clone, Debug, PartialEq)]
+pub struct Complaint {
+    pub accuser: usize,
+    pub dealer: usize,
+    pub share: Option<FE>,
+}
+
+impl Wipe for Complaint {
+    fn wipe(&mut self) {
+        if let Some(share) = self.share.as_mut() {
+            share.wipe();
+        }
+    }
+}
+
+/// plain shares sent by get_encrypted_secret_sharing, owned by the dealer
+/// needed only to answer complaints, wipe it after the complaint round
+pub struct Dealing {
+    shares: Vec<SecretScalar>,
+}
+
+impl Wipe for Dealing {
+    fn wipe(&mut self) {
+        for share in self.shares.iter() {
+            share.wipe();
+        }
+        self.shares.clear();
+    }
+}
+
+/// Schnorr proof of knowledge of dealer's constant term A_i0 = commitments[0]
+/// bound to session context and dealer's index in signers
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeygenProof {
+    pub R: GE,
+    pub s: FE,
+}
+
+/// s*G == R + c*A_i0
+pub fn verify_constant_term(public: &GE, index: usize, context: &[u8], proof: &KeygenProof) -> bool {
+    let g: GE = ECPoint::generator();
+    let c = keygen_pok_challenge(index, public, &proof.R, context);
+    g * &proof.s == proof.R.clone() + public.clone() * &c
+}
+
+/// check proof of every dealer, error contains all dealers without valid proof
+pub fn verify_keygen_proofs(signers: &[GE], proofs: &[KeygenProof], context: &[u8]) -> Result<(), Error> {
+    if signers.len() != proofs.len() {
+        return Err(Error::LengthMismatch { name: "proofs", expected: signers.len(), actual: proofs.len() });
+    }
+    let failed: Vec<usize> = signers.iter().zip(proofs.iter())
+        .enumerate()
+        .filter(|(index, (public, proof))| !verify_constant_term(public, *index, context, proof))
+        .map(|(index, _)| index)
+        .collect();
+    if 0 < failed.len() {
+        return Err(Error::InvalidProof { party_index: failed });
+    }
+    Ok(())
+}
+
+/// Y = sum of QUAL dealer's public keys, each has a valid proof
+/// proofs are bound to index in all signers, not in QUAL
+pub fn group_public_key(signers: &[GE], proofs: &[KeygenProof], context: &[u8], qual: &[usize])
+    -> Result<GE, Error> {
+    if signers.len() != proofs.len() {
+        return Err(Error::LengthMismatch { name: "proofs", expected: signers.len(), actual: proofs.len() });
+    }
+    let failed: Vec<usize> = qual.iter()
+        .cloned()
+        .filter(|dealer| match (signers.get(*dealer), proofs.get(*dealer)) {
+            (Some(public), Some(proof)) => !verify_constant_term(public, *dealer, context, proof),
+            _ => true,
+        })
+        .collect();
+    if 0 < failed.len() {
+        return Err(Error::InvalidProof { party_index: failed });
+    }
+    sum_public_points(&select_qual(signers, qual))
+}
+
+/// aad of encrypted share, dealer || receiver || context
+fn share_aad(dealer: usize, receiver: usize, context: &[u8]) -> Vec<u8> {
+    [&index_bytes(dealer)[..], &index_bytes(receiver)[..], context].concat()
+}
+
+/// calculate party share, my share from each dealer
+fn received_shares(my_index: usize, secret_shares_vec: &[Vec<FE>]) -> Result<Vec<FE>, Error> {
+    let mut party_share = Vec::with_capacity(secret_shares_vec.len());
+    for shares in secret_shares_vec.iter() {
+        let party = match shares.get(my_index) {
+            Some(scalar) => scalar.clone(),
+            None => {
+                party_share.wipe();
+                return Err(Error::InvalidParams("not found your index on secret_shares".to_owned()))
+            }
+        };
+        party_share.push(party);
+    }
+    Ok(party_share)
+}
+
+/// items of QUAL dealers (ex. signers and vss_points)
+pub fn select_qual<T: Clone>(items: &[T], qual: &[usize]) -> Vec<T> {
+    qual.iter().filter_map(|dealer| items.get(*dealer).cloned()).collect()
+}
+
+/// index users [0, 1, .., n] => [1, 2, ...,n+1]
+pub fn make_parties_index(n: usize, parties_index: Option<Vec<usize>>)
+    -> Result<Vec<usize>, Error> {
+    match parties_index {
+        Some(vec) => {
+            if vec.len() == n {
+                Ok(vec.into_iter().map(|i| i + 1).collect())
+            } else {
+                Err(Error::LengthMismatch { name: "parties_index", expected: n, actual: vec.len() })
+            }
+        },
+        None => Ok((0..n).map(|i| i + 1).collect()),
+    }
+}
+
+/// evaluation point of party, index [0, 1, .., n] => [1, 2, ...,n+1]
+pub fn index2scalar(index: usize) -> FE {
+    let position = (index + 1) as u64;
+    ECScalar::from(&BigInt::from_bytes_be(&position.to_be_bytes()))
+}
+
+/// lagrange coefficient at zero of index in parties_index
+/// lambda_i = prod x_j / (x_j - x_i)
+pub fn lagrange_coefficient(index: usize, parties_index: &[usize]) -> FE {
+    let x_i = index2scalar(index);
+    let one = index2scalar(0);  // x of index 0 is 1
+    let (num, denom) = parties_index.iter()
+        .filter(|j| **j != index)
+        .map(|j| index2scalar(*j))
+        .fold((one.clone(), one), |(num, denom), x_j| {
+            let diff = x_j.sub(&x_i.get_element());
+            (num * x_j, denom * diff)
+        });
+    num * denom.invert()
+}
+
+/// lagrange coefficient at x of target (not in parties_index)
+/// lambda_i = prod (x_t - x_j) / (x_i - x_j)
+pub fn lagrange_coefficient_at(index: usize, parties_index: &[usize], target: usize) -> FE {
+    let x_i = index2scalar(index);
+    let x_t = index2scalar(target);
+    let one = index2scalar(0);  // x of index 0 is 1
+    let (num, denom) = parties_index.iter()
+        .filter(|j| **j != index)
+        .map(|j| index2scalar(*j))
+        .fold((one.clone(), one), |(num, denom), x_j| {
+            (num * x_t.sub(&x_j.get_element()), denom * x_i.sub(&x_j.get_element()))
+        });
+    num * denom.invert()
+}
+
+/// public share Y_i = x_i*G of party from all dealer's vss commitments
+pub fn public_share(vss_points: &[VerifiableSS], index: usize) -> Result<GE, Error> {
+    let points: Vec<GE> = vss_points.iter()
+        .map(|vss| vss.get_point_commitment(index + 1))
+        .collect();
+    sum_public_points(&points)
+}
+
+pub fn sum_public_points(signers: &[GE]) -> Result<GE, Error> {
+    // return Y params of sharedKey
+    // return V params of eph sharedKey
+    if signers.len() < 1 {
+        return Err(Error::InvalidParams("zero length point isn't allowed".to_owned()));
+    }
+    let mut signers = signers.iter();
+    let head = signers.next().unwrap();
+    let sum = signers.fold(head.clone(), |acc, x| acc + x);
+    Ok(sum)
+}
+
+pub fn compute_local_signature(share: &FE, eph_share: &FE, Y: &GE, V: &GE, message: &[u8], version: SigVersion)
+    -> (FE, FE) {
+    // each party computes and share a local sig
+    let beta_i = eph_share.clone();
+    let alpha_i = share.clone();
+
+    let e = threshold_challenge(Y, V, message, version);
+    let gamma_i = beta_i + e.clone() * alpha_i;
+    (e, gamma_i)
+}
+
+/// verify all gamma_i and reconstruct sigma
+/// error InvalidShare contains all failed parties_index, retry without them
+/// tweak: t of Q = Y + t*G when gamma_i are signed for Q, sigma becomes sigma + e*t
+pub fn sum_local_signature(
+    t: usize, e: &FE, gammas: &[FE], parties_index: &[usize],
+    vss_points: &[VerifiableSS], eph_vss_points: &[VerifiableSS], tweak: Option<&FE>)
+    -> Result<FE, Error> {
+    if vss_points.len() < 1 {
+        return Err(Error::InvalidParams("zero length vss_points isn't allowed".to_owned()));
+    } else if eph_vss_points.len() < 1 {
+        return Err(Error::InvalidParams("zero length eph_vss_points isn't allowed".to_owned()));
+    }
+    let share_count = eph_vss_points[0].parameters.share_count;
+    if gammas.len() != share_count {
+        return Err(Error::LengthMismatch { name: "gammas", expected: share_count, actual: gammas.len() });
+    } else if parties_index.len() != share_count {
+        return Err(Error::LengthMismatch {
+            name: "parties_index", expected: share_count, actual: parties_index.len() });
+    } else if t != vss_points[0].parameters.threshold {
+        return Err(Error::InvalidParams("not correct vss threshold".to_owned()));
+    } else if vss_points.len() > vss_points[0].parameters.share_count {
+        // less than n when generated by QUAL dealers
+        return Err(Error::LengthMismatch {
+            name: "vss", expected: vss_points[0].parameters.share_count, actual: vss_points.len() });
+    } else if t != eph_vss_points[0].parameters.threshold {
+        return Err(Error::InvalidParams("not correct eph_vss threshold".to_owned()));
+    } else if eph_vss_points.len() > share_count {
+        return Err(Error::LengthMismatch { name: "eph_vss", expected: share_count, actual: eph_vss_points.len() });
+    }
+    // n' = num of signers, n - num of parties in keygen
+    let comm_vec = (0..t + 1)
+        .map(|i| {
+            let mut key_gen_comm_i_vec = vss_points.iter()
+                .map(|v| v.commitments[i].clone() * e)
+                .collect::<Vec<GE>>();
+            let mut eph_comm_i_vec = eph_vss_points.iter()
+                .map(|v| v.commitments[i].clone())
+                .collect::<Vec<GE>>();
+            key_gen_comm_i_vec.append(&mut eph_comm_i_vec);
+            let mut comm_i_vec_iter = key_gen_comm_i_vec.iter();
+            let comm_i_0 = comm_i_vec_iter.next().unwrap();
+            comm_i_vec_iter.fold(comm_i_0.clone(), |acc, x| acc + x)
+        })
+        .collect::<Vec<GE>>();
+
+    let vss_sum = VerifiableSS {
+        parameters: eph_vss_points[0].parameters.clone(),
+        commitments: comm_vec,
+    };
+
+    // validate share public, check all and report every failed party
+    let g: GE = GE::generator();
+    let failed: Vec<usize> = parties_index.iter().zip(gammas.iter())
+        .filter(|(position, gamma)| &g * *gamma != vss_sum.get_point_commitment(*position + 1))
+        .map(|(position, _)| *position)
+        .collect();
+    if 0 < failed.len() {
+        return Err(Error::InvalidShare { party_index: failed });
+    }
+    // each party / dealer can generate the signature
+    let gamma_vec = (0..parties_index.len())
+        .map(|i| gammas[i].clone())
+        .collect::<Vec<FE>>();
+    let reconstruct_limit = vss_sum.parameters.threshold.clone() + 1;
+    let sigma = vss_sum.reconstruct(
+        &parties_index[0..reconstruct_limit.clone()],
+        &gamma_vec[0..reconstruct_limit.clone()],
+    );
+    match tweak {
+        Some(tweak) => Ok(sigma.add(&(e.clone() * tweak.clone()).get_element())),
+        None => Ok(sigma),
+    }
+}
+
+
+/// Legacy: e = H(V || Y || message)
+/// Tagged: e = H_challenge(V || Y || message)
+pub fn threshold_challenge(Y: &GE, V: &GE, message: &[u8], version: SigVersion) -> FE {
+    match version {
+        SigVersion::Legacy => {
+            let e_bn = HSha256::create_hash(&[
+                &V.bytes_compressed_to_big_int(),
+                &Y.bytes_compressed_to_big_int(),
+                &BigInt::from_bytes_be(message),
+            ]);
+            ECScalar::from(&e_bn)
+        },
+        SigVersion::Tagged => challenge(&point_bytes(V), Y, KeyType::ThresholdSig, message),
+        SigVersion::Frost => frost_challenge(V, Y, message),
+    }
+}
+
+
+pub fn verify_threshold_signature(sigma: FE, Y: &GE, V: &GE, message: &[u8], version: SigVersion) -> bool {
+    let e = threshold_challenge(Y, V, message, version);
+
+    let g: GE = GE::generator();
+    let sigma_g = g * &sigma;
+    let e_y = Y * &e;
+    let e_y_plus_v = e_y + V;
+
+    return e_y_plus_v == sigma_g;
+}
+
+
+#[cfg(test)]
+mod Test {
+    use crate::error::Error;
+    use crate::hashing::SigVersion;
+    use crate::secret::Wipe;
+    use crate::testutils::{dealings, keygen, threshold_keys, CONTEXT};
+    use crate::threshold::*;
+
+    #[test]
+    fn test_keygen_complaint_and_qual() {
+        let (t, n) = (1, 4);
+        let (mut keys, signers, proofs) = threshold_keys(t, n);
+        let (vss_points, secret_shares) = dealings(&keys);
+        // dealer 1 sends a broken share to party 0, dealer 3 also to party 2 and will not answer
+        let mut sent = secret_shares.clone();
+        sent[1][0] = ECScalar::new_random();
+        sent[3][2] = ECScalar::new_random();
+        assert_eq!(keys[0].keygen_t_n_parties(&signers, &vss_points, &sent, &proofs, CONTEXT).err().unwrap(),
+                   Error::VssValidation { party_index: 1 });
+
+        let complaints: Vec<Complaint> = keys.iter_mut()
+            .flat_map(|k| k.complaints(&signers, &vss_points, &sent).unwrap())
+            .collect();
+        assert_eq!(complaints.len(), 2);
+        let mut answered = keys[1].answer_complaints(&complaints, &secret_shares[1]).unwrap();
+        answered.push(complaints[1].clone());
+        let qual = keys[0].qualified_dealers(&signers, &vss_points, &answered, &proofs, CONTEXT).unwrap();
+        assert_eq!(qual, vec![0, 1, 2]);
+
+        let shares: Vec<FE> = keys.iter_mut()
+            .map(|k| k.keygen_t_n_parties_qual(&signers, &vss_points, &sent, &qual, &answered, &proofs, CONTEXT).unwrap())
+            .collect();
+        let Y = group_public_key(&signers, &proofs, CONTEXT, &qual).unwrap();
+        let qual_vss = select_qual(&vss_points, &qual);
+        for (index, share) in shares.iter().enumerate() {
+            assert!(GE::generator() * share == public_share(&qual_vss, index).unwrap());
+        }
+        let sigma = lagrange_coefficient(0, &[0, 3]) * shares[0].clone()
+            + lagrange_coefficient(3, &[0, 3]) * shares[3].clone();
+        assert!(GE::generator() * &sigma == Y);
+    }
+
+    #[test]
+    fn test_keygen_encrypted_shares() {
+        let (t, n) = (1, 3);
+        let (mut keys, signers, proofs) = threshold_keys(t, n);
+        let (vss_points, mut encrypted): (Vec<VerifiableSS>, Vec<Vec<Vec<u8>>>) = keys.iter()
+            .map(|k| {
+                let (vss_scheme, ciphertexts, mut dealing) = k.get_encrypted_secret_sharing(&signers, CONTEXT).unwrap();
+                dealing.wipe();
+                (vss_scheme, ciphertexts)
+            })
+            .unzip();
+        let g: GE = ECPoint::generator();
+        for (i, key) in keys.iter_mut().enumerate().skip(1) {
+            let x_i = key.keygen_t_n_parties_encrypted(&signers, &vss_points, &encrypted, &proofs, CONTEXT).unwrap();
+            assert!(g.clone() * &x_i == public_share(&vss_points, i).unwrap());
+        }
+        // share for other receiver is not accepted
+        encrypted[2].swap(0, 1);
+        assert_eq!(keys[0].keygen_t_n_parties_encrypted(&signers, &vss_points, &encrypted, &proofs, CONTEXT).err(),
+                   Some(Error::VssValidation { party_index: 2 }));
+    }
+
+    #[test]
+    fn test_keygen_encrypted_complaint() {
+        let (t, n) = (1, 4);
+        let (mut keys, signers, proofs) = threshold_keys(t, n);
+        let mut vss_points: Vec<VerifiableSS> = Vec::new();
+        let mut encrypted: Vec<Vec<Vec<u8>>> = Vec::new();
+        let mut dealing_vec: Vec<Dealing> = Vec::new();
+        for key in keys.iter() {
+            let (vss_scheme, ciphertexts, dealing) = key.get_encrypted_secret_sharing(&signers, CONTEXT).unwrap();
+            vss_points.push(vss_scheme);
+            encrypted.push(ciphertexts);
+            dealing_vec.push(dealing);
+        }
+        // party 0 cannot decrypt from dealer 1, the dealer reveals the share it actually encrypted
+        encrypted[1][0] = vec![0u8; 81];
+        let complaints: Vec<Complaint> = keys.iter_mut()
+            .flat_map(|k| k.complaints_encrypted(&signers, &vss_points, &encrypted, CONTEXT).unwrap())
+            .collect();
+        assert_eq!(complaints, vec![Complaint { accuser: 0, dealer: 1, share: None }]);
+        let answered = keys[1].answer_complaints_encrypted(&complaints, &dealing_vec[1]).unwrap();
+        for dealing in dealing_vec.iter_mut() {
+            dealing.wipe();
+        }
+        assert!(keys[1].answer_complaints_encrypted(&complaints, &dealing_vec[1]).is_err());
+        let qual = keys[0].qualified_dealers(&signers, &vss_points, &answered, &proofs, CONTEXT).unwrap();
+        assert_eq!(qual, vec![0, 1, 2, 3]);
+
+        let shares: Vec<FE> = keys.iter_mut()
+            .map(|k| k.keygen_t_n_parties_qual_encrypted(
+                &signers, &vss_points, &encrypted, &qual, &answered, &proofs, CONTEXT).unwrap())
+            .collect();
+        for (index, share) in shares.iter().enumerate() {
+            assert!(GE::generator() * share == public_share(&vss_points, index).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_keygen_proof_rejects_rogue_key() {
+        let (keys, mut signers, mut proofs) = threshold_keys(1, 3);
+        assert!(verify_keygen_proofs(&signers, &proofs, CONTEXT).is_ok());
+        // other session or other index
+        assert!(verify_keygen_proofs(&signers, &proofs, b"other session").is_err());
+        proofs.swap(0, 1);
+        signers.swap(0, 1);
+        assert_eq!(verify_keygen_proofs(&signers, &proofs, CONTEXT).err().unwrap(),
+                   Error::InvalidProof { party_index: vec![0, 1] });
+        // last dealer cancels others without knowing the secret
+        signers.swap(0, 1);
+        proofs.swap(0, 1);
+        signers[2] = signers[2].clone() + keys[0].keypair.public.clone();
+        assert_eq!(group_public_key(&signers, &proofs, CONTEXT, &[0, 1, 2]).err().unwrap(),
+                   Error::InvalidProof { party_index: vec![2] });
+    }
+
+    #[test]
+    fn test_sum_local_signature_reports_all_failed() {
+        let (t, n) = (1, 4);
+        let (shares, vss_points, Y) = keygen(t, n);
+        let (eph_shares, eph_vss_points, V) = keygen(t, n);
+        let msg = b"hello threshold";
+        let parties_index: Vec<usize> = (0..n).collect();
+        let (e, mut gammas): (Vec<FE>, Vec<FE>) = (0..n)
+            .map(|i| compute_local_signature(&shares[i], &eph_shares[i], &Y, &V, msg, SigVersion::Tagged))
+            .unzip();
+        let sigma = sum_local_signature(t, &e[0], &gammas, &parties_index, &vss_points, &eph_vss_points, None).unwrap();
+        assert!(verify_threshold_signature(sigma, &Y, &V, msg, SigVersion::Tagged));
+
+        gammas[1] = ECScalar::new_random();
+        gammas[3] = ECScalar::new_random();
+        assert_eq!(sum_local_signature(t, &e[0], &gammas, &parties_index, &vss_points, &eph_vss_points, None).err().unwrap(),
+                   Error::InvalidShare { party_index: vec![1, 3] });
+    }
+}
@@ -0,0 +1,75 @@
+use crate::error::Error;
+use crate::hashing::tweak_scalar;
+use crate::utils::{point2bytes, KeyType};
+use emerald_city::curv::elliptic::curves::secp256_k1::{FE, GE};
+use emerald_city::curv::elliptic::curves::traits::{ECPoint, ECScalar};
+
+
+/// Taproot-style tweak, Q = P + t*G where t = H_tweak(P || commitment)
+/// key_type is kept, tweaked 0x05 key is still 0x05
+/// return Q and t
+pub fn tweak_public_key(public: &GE, key_type: KeyType, commitment: &[u8]) -> Result<(GE, FE), Error> {
+    let t = tweak_scalar(public, key_type, commitment);
+    Ok((add_tweak(public, &t)?, t))
+}
+
+/// P + t*G, error if point at infinity
+pub fn add_tweak(public: &GE, t: &FE) -> Result<GE, Error> {
+    let base_point: GE = ECPoint::generator();
+    let tG = base_point.scalar_mul(&t.get_element());
+    if tG != *public && tG.x_coor() == public.x_coor() {
+        return Err(Error::InvalidPoint(hex::encode(&point2bytes(public, KeyType::SingleSig)[..])));
+    }
+    Ok(public.add_point(&tG.get_element()))
+}
+
+
+#[cfg(test)]
+mod Test {
+    use crate::aggregate::verify_aggregate_signature;
+    use crate::hashing::SigVersion;
+    use crate::keypair::KeyPair;
+    use crate::tweak::*;
+
+    #[test]
+    fn test_single_tweak() {
+        let keypair = KeyPair::new();
+        let tweaked = keypair.tweak(b"script root").unwrap();
+        let (Q, _) = tweak_public_key(&keypair.public, KeyType::SingleSig, b"script root").unwrap();
+        assert!(tweaked.public == Q);
+        let (r, s) = tweaked.single_sign(b"spend", &[1u8;32]).unwrap();
+        assert!(verify_aggregate_signature(&s, &r, &Q, b"spend", false, SigVersion::Tagged).is_ok());
+        assert!(verify_aggregate_signature(&s, &r, &keypair.public, b"spend", false, SigVersion::Tagged).is_err());
+    }
+
+    #[test]
+    fn test_aggregate_tweak() {
+        use crate::aggregate::Aggregate;
+        use crate::testutils::aggregates;
+        let aggs: Vec<Aggregate> = aggregates(2).iter()
+            .map(|agg| agg.tweak(b"script root").unwrap())
+            .collect();
+        let parts: Vec<FE> = aggs.iter().map(|agg| agg.partial_sign(b"spend").unwrap()).collect();
+        let agg = &aggs[0];
+        assert!(agg.verify_partial_sign(1, &parts[1], b"spend"));
+        let s = agg.combine_partial_signs(&parts, b"spend").unwrap();
+        assert!(verify_aggregate_signature(&s.to_big_int(), &agg.R(), &agg.apk, b"spend", true, SigVersion::Tagged).is_ok());
+    }
+
+    #[test]
+    fn test_threshold_tweak() {
+        use crate::testutils::keygen;
+        use crate::threshold::{compute_local_signature, sum_local_signature, verify_threshold_signature};
+        let (t, n) = (1, 3);
+        let (shares, vss_points, Y) = keygen(t, n);
+        let (eph_shares, eph_vss_points, V) = keygen(t, n);
+        let (Q, tweak) = tweak_public_key(&Y, KeyType::ThresholdSig, b"script root").unwrap();
+        let (e, gammas): (Vec<FE>, Vec<FE>) = (0..n)
+            .map(|i| compute_local_signature(&shares[i], &eph_shares[i], &Q, &V, b"spend", SigVersion::Tagged))
+            .unzip();
+        let parties_index: Vec<usize> = (0..n).collect();
+        let sigma = sum_local_signature(
+            t, &e[0], &gammas, &parties_index, &vss_points, &eph_vss_points, Some(&tweak)).unwrap();
+        assert!(verify_threshold_signature(sigma, &Q, &V, b"spend", SigVersion::Tagged));
+    }
+}
@@ -0,0 +1,86 @@
+use crate::error::Error;
+use emerald_city::curv::arithmetic::traits::Converter;
+use emerald_city::curv::elliptic::curves::secp256_k1::{GE, PK};
+use emerald_city::curv::elliptic::curves::traits::ECPoint;
+use emerald_city::curv::arithmetic::num_bigint::BigInt;
+
+
+/// Points type
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum KeyType {
+    SingleSig,
+    AggregateSig,
+    ThresholdSig
+}
+
+impl KeyType {
+    /// offset added to normal 0x02 0x03 0x04 prefix
+    pub fn prefix_offset(&self) -> u8 {
+        match self {
+            KeyType::SingleSig => 0,
+            KeyType::AggregateSig => 3,
+            KeyType::ThresholdSig => 6,
+        }
+    }
+}
+
+/// Bitcoin public key format converter
+/// compressed key   : 2 or 3 prefix + X
+/// uncompressed key : 4 prefix      + X + Y
+#[inline]
+pub fn bytes2point_inner(bytes: &[u8]) -> Result<GE, Error> {
+    let len = bytes.len();
+    let (key_type, prefix) = decode_public_bytes(bytes)?;
+    if len == 33 && (prefix == 2 || prefix == 3) {
+        let mut template = [4u8;33];
+        template.copy_from_slice(&bytes);
+        template[0] -= key_type.prefix_offset();
+        let public = PK::from_slice(&template).map_err(
+            |_| Error::InvalidPoint(hex::encode(bytes)))?;
+        GE::from_bytes(&public.serialize_uncompressed()[1..]).map_err(
+            |_| Error::InvalidPoint(hex::encode(bytes)))
+    }else if len == 65 && prefix == 4 {
+        GE::from_bytes(&bytes[1..]).map_err(
+            |_| Error::InvalidPoint(hex::encode(bytes)))
+    } else {
+        Err(Error::InvalidPoint(hex::encode(bytes)))
+    }
+}
+
+/// compressed point with key type prefix
+/// 0x02 0x03 => 0x05 0x06 (aggregate) or 0x08 0x09 (threshold)
+pub fn point2bytes(point: &GE, key_type: KeyType) -> [u8;33] {
+    let mut bytes = point.get_element().serialize();
+    bytes[0] += key_type.prefix_offset();
+    bytes
+}
+
+/// Mpz bigint to 32bytes big endian
+pub fn bigint2bytes(int: &BigInt) -> Result<[u8;32], Error> {
+    let vec = BigInt::to_vec(int);
+    if 32 < vec.len() {
+        return Err(Error::InvalidScalar("too large bigint".to_owned()));
+    }
+    let mut bytes = [0u8;32];
+    bytes[(32-vec.len())..].copy_from_slice(&vec);
+    Ok(bytes)
+}
+
+/// return (KeyType, normal_prefix)
+/// warning: I will add more params
+pub fn decode_public_bytes(bytes: &[u8]) -> Result<(KeyType, u8), Error> {
+    match bytes.get(0) {
+        Some(prefix) => {
+            if *prefix == 2 || *prefix == 3 || *prefix == 4 {
+                Ok((KeyType::SingleSig, *prefix))
+            } else if *prefix == 5 || *prefix == 6 || *prefix == 7 {
+                Ok((KeyType::AggregateSig, *prefix - 3))
+            } else if *prefix == 8 || *prefix == 9 || *prefix == 10 {
+                Ok((KeyType::ThresholdSig, *prefix - 6))
+            } else {
+                Err(Error::UnknownKeyPrefix(Some(*prefix)))
+            }
+        },
+        None => Err(Error::UnknownKeyPrefix(None))
+    }
+}
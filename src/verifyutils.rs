@@ -1,6 +1,9 @@
-use crate::pythreshold::verify_threshold_signature;
-use crate::pyagg::verify_aggregate_signature;
-use crate::pyo3utils::{decode_public_bytes, PyKeyType, bytes2point_inner};
+use crate::bip340::verify_bip340_signature;
+use crate::error::Error;
+use crate::hashing::decode_r;
+use crate::threshold::verify_threshold_signature;
+use crate::aggregate::verify_aggregate_signature;
+use crate::utils::{decode_public_bytes, KeyType, bytes2point_inner};
 use emerald_city::curv::cryptographic_primitives::hashing::{
     hash_sha256::HSha256,
     traits::Hash,
@@ -9,33 +12,59 @@ use emerald_city::curv::elliptic::curves::secp256_k1::{FE, GE};
 use emerald_city::curv::elliptic::curves::traits::{ECPoint, ECScalar};
 use emerald_city::curv::arithmetic::num_bigint::BigInt;
 use num_traits::Zero;
+use threadpool::ThreadPool;
+use std::sync::mpsc::channel;
 
 
+/// r of 1 of 1 and n of n is R.x (32b), r of threshold is V (33b)
+/// versioned r (SIGNATURE_VERSION || r) is verified by tagged challenge
 #[inline]
 pub fn verify_auto_signature(s: &[u8], r: &[u8], apk: &[u8], message: &[u8])
-    -> Result<bool, String> {
-    let is_verify = match decode_public_bytes(apk) {
-        Ok((key_type, _prefix)) => match key_type {
-            PyKeyType::SingleSig | PyKeyType::AggregateSig => {
-                let signature = BigInt::from_bytes_be(s);
-                let r_x = BigInt::from_bytes_be(r);
-                let apk = bytes2point_inner(apk)?;
-                let is_musig = key_type == PyKeyType::AggregateSig;
-                verify_aggregate_signature(&signature, &r_x, &apk, message, is_musig).is_ok()
-            },
-            PyKeyType::ThresholdSig => {
-                let sigma = ECScalar::from(&BigInt::from_bytes_be(s));
-                let Y = bytes2point_inner(apk)?;
-                let V = bytes2point_inner(r)?;
-                verify_threshold_signature(sigma, &Y, &V, message)
-            }
+    -> Result<bool, Error> {
+    if apk.len() == 32 {
+        // BIP-340 x-only public key, signature is R.x || s
+        let signature = [r, s].concat();
+        return Ok(verify_bip340_signature(&signature, apk, message).is_ok());
+    }
+    let (key_type, _prefix) = decode_public_bytes(apk)?;
+    let is_verify = match key_type {
+        KeyType::SingleSig | KeyType::AggregateSig => {
+            let (version, r) = decode_r(r, 32)?;
+            let signature = BigInt::from_bytes_be(s);
+            let r_x = BigInt::from_bytes_be(r);
+            let apk = bytes2point_inner(apk)?;
+            let is_musig = key_type == KeyType::AggregateSig;
+            verify_aggregate_signature(&signature, &r_x, &apk, message, is_musig, version).is_ok()
         },
-        Err(_) => return Err("decode public point failed".to_string())
+        KeyType::ThresholdSig => {
+            let (version, r) = decode_r(r, 33)?;
+            let sigma = ECScalar::from(&BigInt::from_bytes_be(s));
+            let Y = bytes2point_inner(apk)?;
+            let V = bytes2point_inner(r)?;
+            verify_threshold_signature(sigma, &Y, &V, message, version)
+        }
     };
     Ok(is_verify)
 }
 
 
+/// verify many signature (s, r, apk, message) on multi-core
+/// results are not ordered same as tasks
+pub fn verify_auto_signature_multi(tasks: Vec<(Vec<u8>, Vec<u8>, Vec<u8>, Vec<u8>)>, n_workers: usize)
+    -> Vec<Result<bool, Error>> {
+    let pool = ThreadPool::new(n_workers);
+    let (tx, rx) = channel();
+    let n_jobs = tasks.len();
+    for (s, r, apk, message) in tasks {
+        let tx = tx.clone();
+        pool.execute(move || {
+            tx.send(verify_auto_signature(&s, &r, &apk, &message)).unwrap()
+        });
+    };
+    rx.iter().take(n_jobs).collect()
+}
+
+
 pub fn ephemeral_hash_0(r_hat: &GE, apk: &GE, message: &[u8], musig_bit: bool) -> BigInt {
     if musig_bit {
         HSha256::create_hash(&[
@@ -0,0 +1,38 @@
+from multi_party_schnorr import PyThresholdKey, PyFrostNonce, PyFrostSession, \
+    summarize_public_points, verify_threshold_sign, verify_auto, PyKeyPair, PyThresholdShare
+from time import time
+
+PyKeyPair()  # dummy
+
+start = time()
+t = 2  # threshold
+n = 5  # signers
+pairs = [PyThresholdKey.generate(t, n) for _ in range(n)]
+vss_points, scalars = zip(*[x.get_variable_secret_sharing() for x in pairs])
+signers = [x.keypair.get_public_key() for x in pairs]
+context = b"frost keygen"
+proofs = [x.get_proof(context, i) for i, x in enumerate(pairs)]
+shares = [x.keygen_t_n_parties(signers, vss_points, scalars, proofs, context) for x in pairs]
+Y = summarize_public_points(signers, proofs, context)
+print("keygen", round(time() - start, 3), "Sec")
+
+# store key shares, loading checks shares and Y with vss commitments
+stored = [PyThresholdShare.generate(x, share, Y, vss_points).to_bytes() for x, share in zip(pairs, shares)]
+restored = [PyThresholdShare.from_bytes(data) for data in stored]
+assert [x.share for x in restored] == shares
+
+# preprocess: publish nonce commitments before message is known
+parties_index = [0, 2, 4]
+nonces = [PyFrostNonce() for _ in parties_index]
+public_nonces = [x.get_public_nonce() for x in nonces]
+
+# one round signing
+sign_start = time()
+msg = b"hello frost"
+session = PyFrostSession.generate(t, Y, msg, parties_index, public_nonces)
+gammas = [session.get_partial_sign(shares[i], i, nonce) for i, nonce in zip(parties_index, nonces)]
+sigma = session.summarize_signature(t, n, gammas, vss_points)
+V = session.R()
+print("sign", round((time() - sign_start) * 1000), "mSec")
+print("verify?", verify_threshold_sign(sigma, Y, V, msg))
+print("verify auto?", verify_auto(sigma, V, Y, msg))
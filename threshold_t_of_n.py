@@ -22,13 +22,16 @@ vss4, sec4 = pair4.get_variable_secret_sharing()
 signers = [x.keypair.get_public_key() for x in (pair0, pair1, pair2, pair3, pair4)]
 vss_points = [vss0, vss1, vss2, vss3, vss4]
 scalars = [sec0, sec1, sec2, sec3, sec4]
+# proof of knowledge of each dealer's key, bound to session and index
+context = b"keygen session"
+proofs = [x.get_proof(context, i) for i, x in enumerate((pair0, pair1, pair2, pair3, pair4))]
 
-share0 = pair0.keygen_t_n_parties(signers, vss_points, scalars)
-share1 = pair1.keygen_t_n_parties(signers, vss_points, scalars)
-share2 = pair2.keygen_t_n_parties(signers, vss_points, scalars)
-share3 = pair3.keygen_t_n_parties(signers, vss_points, scalars)
-share4 = pair4.keygen_t_n_parties(signers, vss_points, scalars)
-Y = multi_party_schnorr.summarize_public_points(signers)
+share0 = pair0.keygen_t_n_parties(signers, vss_points, scalars, proofs, context)
+share1 = pair1.keygen_t_n_parties(signers, vss_points, scalars, proofs, context)
+share2 = pair2.keygen_t_n_parties(signers, vss_points, scalars, proofs, context)
+share3 = pair3.keygen_t_n_parties(signers, vss_points, scalars, proofs, context)
+share4 = pair4.keygen_t_n_parties(signers, vss_points, scalars, proofs, context)
+Y = multi_party_schnorr.summarize_public_points(signers, proofs, context)
 print("Y", Y.hex())
 print("share0", share0.hex())
 print("share1", share1.hex())
@@ -51,12 +54,16 @@ eph_vss3, eph_sec3 = eph3.get_variable_secret_sharing()
 eph_signers = [x.keypair.get_public_key() for x in (eph0, eph1, eph2, eph3)]
 eph_vss_points = [eph_vss0, eph_vss1, eph_vss2, eph_vss3]
 eph_scalars = [eph_sec0, eph_sec1, eph_sec2, eph_sec3]
+eph_context = b"ephemeral session"
+eph_proofs = [x.get_proof(eph_context, i) for i, x in enumerate((eph0, eph1, eph2, eph3))]
 
-eph_share0 = eph0.keygen_t_n_parties(eph_signers, eph_vss_points, eph_scalars)
-eph_share1 = eph1.keygen_t_n_parties(eph_signers, eph_vss_points, eph_scalars)
-eph_share2 = eph2.keygen_t_n_parties(eph_signers, eph_vss_points, eph_scalars)
-eph_share3 = eph3.keygen_t_n_parties(eph_signers, eph_vss_points, eph_scalars)
-V = multi_party_schnorr.summarize_public_points(eph_signers)
+eph_share0 = eph0.keygen_t_n_parties(eph_signers, eph_vss_points, eph_scalars, eph_proofs, eph_context)
+eph_share1 = eph1.keygen_t_n_parties(eph_signers, eph_vss_points, eph_scalars, eph_proofs, eph_context)
+eph_share2 = eph2.keygen_t_n_parties(eph_signers, eph_vss_points, eph_scalars, eph_proofs, eph_context)
+eph_share3 = eph3.keygen_t_n_parties(eph_signers, eph_vss_points, eph_scalars, eph_proofs, eph_context)
+# versioned V signs with tagged challenge, raw 33bytes V is legacy
+V = bytes([multi_party_schnorr.SIGNATURE_VERSION]) + \
+    multi_party_schnorr.summarize_public_points(eph_signers, eph_proofs, eph_context)
 print("V", V.hex(), eph0.my_index, eph1.my_index, eph2.my_index)
 print("eph share0", eph_share0.hex())
 print("eph share1", eph_share1.hex())
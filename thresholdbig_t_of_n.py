@@ -1,5 +1,6 @@
 from multi_party_schnorr import PyThresholdKey, summarize_public_points, \
-    get_local_signature, summarize_local_signature, verify_threshold_sign, verify_auto, PyKeyPair
+    get_local_signature, summarize_local_signature, verify_threshold_sign, verify_auto, PyKeyPair, \
+    SIGNATURE_VERSION
 from time import time
 from random import shuffle
 
@@ -25,14 +26,16 @@ for p in pair_list:
 print("1..", round(time() - start, 3), "Sec")
 
 signers = [x.keypair.get_public_key() for x in pair_list]
+context = b"big keygen"
+proofs = [x.get_proof(context, i) for i, x in enumerate(pair_list)]
 
 share_list = list()
 for p in pair_list:
-    share_list.append(p.keygen_t_n_parties(signers, vss_points, scalars))
+    share_list.append(p.keygen_t_n_parties(signers, vss_points, scalars, proofs, context))
 
 print("2..", round(time() - start, 3), "Sec")
 
-Y = summarize_public_points(signers)
+Y = summarize_public_points(signers, proofs, context)
 print("Y", Y.hex())
 for i, s in enumerate(share_list):
     print("share", i, s.hex())
@@ -67,14 +70,16 @@ for e in eph_list:
 print("5..", round(time() - start, 3), "Sec")
 
 eph_signers = [x.keypair.get_public_key() for x in eph_list]
+eph_context = b"big ephemeral"
+eph_proofs = [x.get_proof(eph_context, i) for i, x in enumerate(eph_list)]
 
 eph_share_list = list()
 for e in eph_list:
-    eph_share_list.append(e.keygen_t_n_parties(eph_signers, eph_vss_points, eph_scalars))
+    eph_share_list.append(e.keygen_t_n_parties(eph_signers, eph_vss_points, eph_scalars, eph_proofs, eph_context))
 
 print("6..", round(time() - start, 3), "Sec")
 
-V = summarize_public_points(eph_signers)
+V = bytes([SIGNATURE_VERSION]) + summarize_public_points(eph_signers, eph_proofs, eph_context)
 print("V", V.hex())
 for i, s in enumerate(eph_share_list):
     print("eph_share", i, s.hex())